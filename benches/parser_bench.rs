@@ -6,46 +6,65 @@ use lib::sourcemap::SourceMapInner;
 use std::path;
 use std::rc::Rc;
 
+const CODE: &str = r#"/*
+hlleoa1234567890qwertyuiopasdfghjklzxcvbnm,./?><;'":[]\|}{=-0987654321`~!@#$%^&*()_+
+one two
+djawd
+sfghsdjajdksajfiwjijfa
+*/
+
+let other = () {
+    let other_1 = () -> () {
+        return ();
+    };
+    let x: i64 = 10;
+
+    let other_2 = (a: i64, b: (i64, str)) -> i64 {
+        if a <= 1 {
+            return 10 * a - -a % 3;
+        } else if a == 2 {
+            return other_2(a - 1, b) + other_2(a - 2, b);
+        } else {
+            return a as i32 as i64;
+        };
+    };
+
+    let _qwertyuiopasdfghjklzxcvbnm: str;
+    _qwertyuiopasdfghjklzxcvbnm = "hi";
+    hi(10, 102, _qwertyuiopasdfghjklzxcvbnm + "other hi", x);
+    return (((((((1023), (((123))), x = 10, (((let y: i64 = 10))), (("awd"))))))));
+    (((((((((((((())))))))))))));
+};
+"#;
+
 #[allow(unused_must_use)]
 fn criterion_benchmark(c: &mut Criterion) {
     let sourcemap = SourceMapInner::new();
-    sourcemap.borrow_mut().insert_file(
+    let simple = sourcemap.borrow_mut().insert_file(
         path::PathBuf::from(
             "my_long_filename_test_this_is_really_long_but_its_for_a_Test_so_who_cares.fl",
         ),
-        r#"/*	
-hlleoa1234567890qwertyuiopasdfghjklzxcvbnm,./?><;'":[]\|}{=-0987654321`~!@#$%^&*()_+	
-one two	
-djawd	
-sfghsdjajdksajfiwjijfa	
-*/	
-
-def other() {	
-    def other_1() -> () {	
-        return 10;	
-    }	
-    let x: int = 10;	
-
-    def other_1() -> int {	
-        return 10;	
-    }
-
-    let _qwertyuiopasdfghjklzxcvbnm: int;	
-    _qwertyuiopasdfghjklzxcvbnm = "hi";	
-    hi(10, 102, _qwertyuiopasdfghjklzxcvbnm+"other hi", x);	
-    return (((((((1023), (((123))), x = 10, (((let x: int = 10))), (("awd"))))))));
-    (((((((((((((())))))))))))));
-}
-"#
-        .to_string(),
+        CODE.to_string(),
     );
+    let large = sourcemap
+        .borrow_mut()
+        .insert_file(path::PathBuf::from("large.fl"), CODE.repeat(100));
 
     let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
 
     c.bench_function("parser simple", |b| {
         b.iter(|| {
             let mut my_parser =
-                parser::parser::Parser::new(0, Rc::clone(&logger), Rc::clone(&sourcemap));
+                parser::parser::Parser::new(simple, Rc::clone(&logger), Rc::clone(&sourcemap));
+            my_parser.initialize_expr();
+            my_parser.parse();
+        })
+    });
+
+    c.bench_function("parser large", |b| {
+        b.iter(|| {
+            let mut my_parser =
+                parser::parser::Parser::new(large, Rc::clone(&logger), Rc::clone(&sourcemap));
             my_parser.initialize_expr();
             my_parser.parse();
         })
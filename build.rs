@@ -47,8 +47,13 @@ impl<'a> Generator<'a> {
 
     fn generate_fmt(&mut self) {
         self.generate_printf();
+        self.generate_putchar();
         self.generate_print_int();
         self.generate_print_long();
+        self.generate_print_sized();
+        self.generate_print_float();
+        self.generate_print_bool();
+        self.generate_print_str();
     }
 
     fn generate_printf(&mut self) {
@@ -62,6 +67,14 @@ impl<'a> Generator<'a> {
         self.module.add_function("printf", fn_type, None);
     }
 
+    fn generate_putchar(&mut self) {
+        // C putchar - NOT for external use, only for use by the builtins
+        let i32_type = self.context.i32_type();
+        let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+
+        self.module.add_function("putchar", fn_type, None);
+    }
+
     fn generate_print_int(&mut self) {
         // print_int ( int ) -> ()
         let i32_type = self.context.i32_type();
@@ -124,6 +137,223 @@ impl<'a> Generator<'a> {
             )));
     }
 
+    fn generate_print_sized(&mut self) {
+        // print_<name> ( <type> ) -> () for the other integer types
+        let usize_type = self
+            .context
+            .custom_width_int_type(std::mem::size_of::<usize>() as u32 * 8);
+        let key_vals = [
+            ("print_byte", self.context.i8_type(), "%hhu\n", false),
+            ("print_sbyte", self.context.i8_type(), "%hhd\n", true),
+            ("print_ushort", self.context.i16_type(), "%hu\n", false),
+            ("print_short", self.context.i16_type(), "%hd\n", true),
+            ("print_uint", self.context.i32_type(), "%u\n", false),
+            ("print_ulong", self.context.i64_type(), "%lu\n", false),
+            ("print_usize", usize_type, "%zu\n", false),
+            ("print_isize", usize_type, "%zd\n", true),
+        ];
+
+        for (func_name, int_type, format, signed) in key_vals.iter() {
+            let i32_type = self.context.i32_type();
+            let empty_tuple = self.context.struct_type(&[], false);
+
+            let fn_type = empty_tuple.fn_type(&[(*int_type).into()], false);
+            let fn_addr = self.module.add_function(func_name, fn_type, None);
+
+            let entry_block = self.context.append_basic_block(fn_addr, "entry");
+
+            self.builder.position_at_end(entry_block);
+
+            let format = self.builder.build_global_string_ptr(format, "format");
+
+            // Variadic arguments smaller than an int are passed as an int
+            let mut value = fn_addr.get_nth_param(0).unwrap().into_int_value();
+            if int_type.get_bit_width() < i32_type.get_bit_width() {
+                value = if *signed {
+                    self.builder.build_int_s_extend(value, i32_type, "extend")
+                } else {
+                    self.builder.build_int_z_extend(value, i32_type, "extend")
+                };
+            }
+
+            self.builder.build_call(
+                self.module
+                    .get_function("printf")
+                    .expect("There is no printf defined??"),
+                &[
+                    inkwell::values::BasicValueEnum::PointerValue(format.as_pointer_value()),
+                    value.into(),
+                ],
+                "temp2",
+            );
+
+            self.builder
+                .build_return(Some(&inkwell::values::BasicValueEnum::StructValue(
+                    empty_tuple.const_named_struct(&[]),
+                )));
+        }
+    }
+
+    fn generate_print_float(&mut self) {
+        // print_float ( f32 ) -> () and print_double ( f64 ) -> ()
+        let f64_type = self.context.f64_type();
+        let key_vals = [
+            ("print_float", self.context.f32_type(), true),
+            ("print_double", f64_type, false),
+        ];
+
+        for (func_name, float_type, extend) in key_vals.iter() {
+            let empty_tuple = self.context.struct_type(&[], false);
+
+            let fn_type = empty_tuple.fn_type(&[(*float_type).into()], false);
+            let fn_addr = self.module.add_function(func_name, fn_type, None);
+
+            let entry_block = self.context.append_basic_block(fn_addr, "entry");
+
+            self.builder.position_at_end(entry_block);
+
+            let format = self.builder.build_global_string_ptr("%g\n", "format");
+
+            // Variadic arguments smaller than a double are passed as a double
+            let mut value = fn_addr.get_nth_param(0).unwrap().into_float_value();
+            if *extend {
+                value = self.builder.build_float_ext(value, f64_type, "extend");
+            }
+
+            self.builder.build_call(
+                self.module
+                    .get_function("printf")
+                    .expect("There is no printf defined??"),
+                &[
+                    inkwell::values::BasicValueEnum::PointerValue(format.as_pointer_value()),
+                    value.into(),
+                ],
+                "temp2",
+            );
+
+            self.builder
+                .build_return(Some(&inkwell::values::BasicValueEnum::StructValue(
+                    empty_tuple.const_named_struct(&[]),
+                )));
+        }
+    }
+
+    fn generate_print_bool(&mut self) {
+        // print_bool ( bool ) -> ()
+        let bool_type = self.context.bool_type();
+        let empty_tuple = self.context.struct_type(&[], false);
+
+        let fn_type = empty_tuple.fn_type(&[bool_type.into()], false);
+        let fn_addr = self.module.add_function("print_bool", fn_type, None);
+
+        let entry_block = self.context.append_basic_block(fn_addr, "entry");
+
+        self.builder.position_at_end(entry_block);
+
+        let format = self.builder.build_global_string_ptr("%s\n", "format");
+        let true_str = self.builder.build_global_string_ptr("true", "true");
+        let false_str = self.builder.build_global_string_ptr("false", "false");
+
+        let value = self.builder.build_select(
+            fn_addr.get_nth_param(0).unwrap().into_int_value(),
+            true_str.as_pointer_value(),
+            false_str.as_pointer_value(),
+            "value",
+        );
+
+        self.builder.build_call(
+            self.module
+                .get_function("printf")
+                .expect("There is no printf defined??"),
+            &[
+                inkwell::values::BasicValueEnum::PointerValue(format.as_pointer_value()),
+                value,
+            ],
+            "temp2",
+        );
+
+        self.builder
+            .build_return(Some(&inkwell::values::BasicValueEnum::StructValue(
+                empty_tuple.const_named_struct(&[]),
+            )));
+    }
+
+    fn generate_print_str(&mut self) {
+        // print_str ( str ) -> (), where str is { i8*, usize }
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let usize_type = self
+            .context
+            .custom_width_int_type(std::mem::size_of::<usize>() as u32 * 8);
+        let str_type = self
+            .context
+            .struct_type(&[i8_ptr_type.into(), usize_type.into()], false);
+        let empty_tuple = self.context.struct_type(&[], false);
+
+        let fn_type = empty_tuple.fn_type(&[str_type.into()], false);
+        let fn_addr = self.module.add_function("print_str", fn_type, None);
+
+        let entry_block = self.context.append_basic_block(fn_addr, "entry");
+
+        self.builder.position_at_end(entry_block);
+
+        // Strings are not null terminated and may contain `\0`, so every byte is written
+        let putchar = self
+            .module
+            .get_function("putchar")
+            .expect("There is no putchar defined??");
+        let i32_type = self.context.i32_type();
+
+        let string = fn_addr.get_nth_param(0).unwrap().into_struct_value();
+        let bytes = self
+            .builder
+            .build_extract_value(string, 0, "bytes")
+            .unwrap()
+            .into_pointer_value();
+        let len = self
+            .builder
+            .build_extract_value(string, 1, "len")
+            .unwrap()
+            .into_int_value();
+        let index = self.builder.build_alloca(usize_type, "index");
+        self.builder.build_store(index, usize_type.const_zero());
+
+        let cond_block = self.context.append_basic_block(fn_addr, "cond");
+        let body_block = self.context.append_basic_block(fn_addr, "body");
+        let end_block = self.context.append_basic_block(fn_addr, "end");
+        self.builder.build_unconditional_branch(cond_block);
+
+        self.builder.position_at_end(cond_block);
+        let current = self.builder.build_load(index, "current").into_int_value();
+        let in_bounds =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::ULT, current, len, "in_bounds");
+        self.builder
+            .build_conditional_branch(in_bounds, body_block, end_block);
+
+        self.builder.position_at_end(body_block);
+        let byte_addr = unsafe { self.builder.build_gep(bytes, &[current], "byte_addr") };
+        let byte = self.builder.build_load(byte_addr, "byte").into_int_value();
+        let byte = self.builder.build_int_z_extend(byte, i32_type, "extend");
+        self.builder.build_call(putchar, &[byte.into()], "temp");
+        let next = self
+            .builder
+            .build_int_add(current, usize_type.const_int(1, false), "next");
+        self.builder.build_store(index, next);
+        self.builder.build_unconditional_branch(cond_block);
+
+        self.builder.position_at_end(end_block);
+        self.builder.build_call(
+            putchar,
+            &[i32_type.const_int(u64::from(b'\n'), false).into()],
+            "temp2",
+        );
+
+        self.builder
+            .build_return(Some(&inkwell::values::BasicValueEnum::StructValue(
+                empty_tuple.const_named_struct(&[]),
+            )));
+    }
+
     fn generate_op(&mut self) {
         self.generate_add_int();
         self.generate_mul_int();
@@ -1,18 +1,24 @@
 use crate::helpers;
+use crate::lexer;
 use crate::logger::{ErrorValue, Logger};
+use crate::mir;
+use crate::mir::lower_to_mir;
 use crate::parser::ast;
 use crate::sourcemap::SourceMap;
+use crate::typecheck::annotation::Prim;
 use crate::typecheck::TypeCheckModule;
-use crate::mir::lower_to_mir;
 
+use inkwell::module::Linkage;
 use inkwell::types::BasicType;
 use inkwell::values::BasicValue;
-use inkwell::{builder, context, module, types, values};
+use inkwell::{
+    basic_block, builder, context, module, types, values, AddressSpace, FloatPredicate,
+    IntPredicate,
+};
 
-use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::convert::TryInto;
-use std::ops::Deref;
 use std::path;
 use std::rc::Rc;
 use std::time::Instant;
@@ -29,19 +35,25 @@ impl<'a> CodeGenSymbTab<'a> {
         }
     }
 
-    fn clear(&mut self) {
-        self.items.clear();
-    }
-
     fn insert(&mut self, name: Rc<ast::Namespace>, value: values::BasicValueEnum<'a>) {
         self.items.insert(name, value);
     }
 
-    fn get(&mut self, name: Rc<ast::Namespace>) -> values::BasicValueEnum<'a> {
-        *self.items.get(&name).unwrap()
+    fn get(&self, name: &Rc<ast::Namespace>) -> Option<values::BasicValueEnum<'a>> {
+        self.items.get(name).copied()
     }
 }
 
+#[derive(Clone, Copy)]
+/// Where `break` and `continue` jump to in a loop
+struct LoopTarget<'a> {
+    id: usize,
+    /// Where the value of a `break` is stored
+    value_alloca: values::PointerValue<'a>,
+    break_block: basic_block::BasicBlock<'a>,
+    continue_block: basic_block::BasicBlock<'a>,
+}
+
 /// Module object
 ///
 /// There is one module object per unit
@@ -50,10 +62,20 @@ pub struct CodeGenModule<'a> {
     context: &'a context::Context,
     typecheck: TypeCheckModule,
     builder: builder::Builder<'a>,
+    /// Local variables of the function being generated
     symbtab: CodeGenSymbTab<'a>,
+    /// Variables on the outer scope, visible in every function
+    globals: CodeGenSymbTab<'a>,
+    /// Every function defined or declared in the module
+    functions: HashMap<Rc<ast::Namespace>, values::FunctionValue<'a>>,
+    /// Where to store the value of a `yield`, and where to jump afterwards
+    yield_targets: Vec<(values::PointerValue<'a>, basic_block::BasicBlock<'a>)>,
+    /// Loops around the code being generated, the innermost one is last
+    loop_targets: Vec<LoopTarget<'a>>,
     output_ir: &'a path::Path,
     pub output_obj: &'a path::Path,
     sourcemap: SourceMap,
+    logger: Logger,
 }
 
 impl<'a> CodeGenModule<'a> {
@@ -75,406 +97,1098 @@ impl<'a> CodeGenModule<'a> {
             typecheck,
             builder: context.create_builder(),
             symbtab: CodeGenSymbTab::new(),
+            globals: CodeGenSymbTab::new(),
+            functions: HashMap::new(),
+            yield_targets: Vec::new(),
+            loop_targets: Vec::new(),
             output_ir,
             output_obj,
             sourcemap,
+            logger,
         }
     }
 
     pub fn generate(&mut self) -> Result<(), Vec<ErrorValue>> {
         let typed_ast = self.typecheck.type_check()?;
-        let mir_rep = lower_to_mir(typed_ast);
+        let mir_rep = lower_to_mir(typed_ast, Rc::clone(&self.sourcemap))?;
 
-        /*
         let gen_start = Instant::now();
 
-        let mut statements = None;
-
-        std::mem::swap(&mut self.typecheck.parser.ast, &mut statements);
-        let statements = statements.unwrap();
-        for statement in &statements.nodes {
-            self.gen_stmt_pass_1(statement)
+        for stmt in &mir_rep {
+            self.gen_stmt_pass_1(stmt);
         }
 
-        for statement in &statements.nodes {
-            self.gen_stmt_pass_2(statement)
+        for stmt in &mir_rep {
+            self.gen_stmt_pass_2(stmt);
         }
 
-        self.typecheck.parser.logger.borrow().log_verbose(&|| {
+        self.gen_main(&mir_rep);
+
+        self.logger.borrow().log_verbose(&|| {
             format!(
                 "{}: LLVM IR generated",
                 helpers::display_duration(gen_start.elapsed())
             )
         }); // Lazily run it so no impact on performance
-        */
 
         Ok(())
     }
 
-    /*
-
-    fn gen_stmt_pass_1(&mut self, statement: &ast::Statement) {
-        match statement {
-            ast::Statement::FunctionDefine(func_def) => self.gen_function_prototype(func_def),
-            ast::Statement::Unit(unit) => self.get_unit_proto(unit),
+    /// Generate the prototypes of all the functions and the globals of all the variables on the
+    /// outer scope, so they can be used before they are defined
+    fn gen_stmt_pass_1(&mut self, stmt: &mir::MirStmt) {
+        match stmt {
+            mir::MirStmt::Expression(mir::MirExpr {
+                value: mir::MirExprEnum::VariableAssignDeclaration(var_dec),
+                ..
+            }) => {
+                let linkage = var_dec.visibility.get_linkage();
+                if let mir::MirExprEnum::Function(func) = &var_dec.expr.value {
+                    self.gen_function_prototype(
+                        &var_dec.var_name,
+                        &var_dec.var_name.to_string(),
+                        &func.signature,
+                        linkage,
+                    );
+                } else {
+                    self.gen_global(
+                        &var_dec.var_name,
+                        &var_dec.var_name.to_string(),
+                        &var_dec.expr.ty,
+                        Some(linkage),
+                    );
+                }
+            }
+            mir::MirStmt::VariableDeclaration(var_dec) if var_dec.is_extern => {
+                // External symbols are not namespaced
+                let symbol = var_dec.binding.name.scopes.last().unwrap();
+                let symbol = get_segment!(self.sourcemap, symbol.pos).to_string();
+                if let mir::MirType::FunctionSig(sig, _) = &var_dec.binding.ty {
+                    self.gen_function_prototype(
+                        &var_dec.binding.name,
+                        &symbol,
+                        sig,
+                        Linkage::External,
+                    );
+                } else {
+                    self.gen_global(&var_dec.binding.name, &symbol, &var_dec.binding.ty, None);
+                }
+            }
+            mir::MirStmt::VariableDeclaration(var_dec) => {
+                // Given a value by an assignment later on
+                self.gen_global(
+                    &var_dec.binding.name,
+                    &var_dec.binding.name.to_string(),
+                    &var_dec.binding.ty,
+                    Some(Linkage::Private),
+                );
+            }
             _ => {}
         }
     }
 
-    fn gen_stmt_pass_2(&mut self, statement: &ast::Statement) {
-        match statement {
-            ast::Statement::FunctionDefine(func_def) => self.gen_function_define(
-                func_def,
-                self.module
-                    .get_function(&func_def.mangled_name.as_ref().unwrap()[..])
-                    .unwrap(),
-            ),
-            ast::Statement::Unit(unit) => self.get_unit(unit),
-            _ => {}
+    fn gen_stmt_pass_2(&mut self, stmt: &mir::MirStmt) {
+        if let mir::MirStmt::Expression(mir::MirExpr {
+            value: mir::MirExprEnum::VariableAssignDeclaration(var_dec),
+            ..
+        }) = stmt
+        {
+            if let mir::MirExprEnum::Function(func) = &var_dec.expr.value {
+                let func_val = self.functions[&var_dec.var_name];
+                self.gen_function_define(func, func_val);
+            }
+        }
+    }
+
+    /// Generate the C `main` function, which gives the variables on the outer scope their values
+    /// in order and then calls `entry`
+    fn gen_main(&mut self, mir_rep: &[mir::MirStmt]) {
+        let entry = match self
+            .functions
+            .iter()
+            .find(|(name, _)| name.to_string() == "entry")
+        {
+            Some((_, entry)) => *entry,
+            None => return,
+        };
+
+        let i32_type = self.context.i32_type();
+        let main_fn = self
+            .module
+            .add_function("main", i32_type.fn_type(&[], false), None);
+
+        let entry_bb = self.context.append_basic_block(main_fn, "entry");
+        self.builder.position_at_end(entry_bb);
+
+        self.symbtab = self.globals.clone();
+        for stmt in mir_rep {
+            match stmt {
+                mir::MirStmt::Expression(mir::MirExpr {
+                    value: mir::MirExprEnum::VariableAssignDeclaration(var_dec),
+                    ..
+                }) => {
+                    // Functions are already defined
+                    if let mir::MirExprEnum::Function(_) = &var_dec.expr.value {
+                        continue;
+                    }
+                    let value = self.gen_expr(&var_dec.expr);
+                    let addr = self.globals.get(&var_dec.var_name).unwrap();
+                    self.builder.build_store(addr.into_pointer_value(), value);
+                }
+                mir::MirStmt::Expression(expr) => {
+                    self.gen_expr(expr);
+                }
+                _ => {}
+            }
         }
+
+        self.builder.build_call(entry, &[], "entry");
+        self.builder
+            .build_return(Some(&i32_type.const_int(0, false)));
+    }
+
+    fn gen_function_prototype(
+        &mut self,
+        name: &Rc<ast::Namespace>,
+        symbol: &str,
+        signature: &mir::FunctionSig,
+        linkage: Linkage,
+    ) -> values::FunctionValue<'a> {
+        let fn_type = self.get_fn_type(signature);
+        let function = self.module.add_function(symbol, fn_type, Some(linkage));
+        self.functions.insert(Rc::clone(name), function);
+        function
     }
 
-    fn generate_inner_block(
+    /// Variables on the outer scope live in global memory, those without a linkage are external
+    /// symbols defined in another object
+    fn gen_global(
         &mut self,
-        block: &ast::Block,
-        function: inkwell::values::FunctionValue<'a>,
+        name: &Rc<ast::Namespace>,
+        symbol: &str,
+        ty: &mir::MirType,
+        linkage: Option<Linkage>,
     ) {
-        for stmt in &block.nodes {
-            self.gen_inner_stmt(stmt, function);
+        let ty = self.get_type(ty);
+        let global = self.module.add_global(ty, None, symbol);
+        if let Some(linkage) = linkage {
+            // The value is only stored once the program starts
+            global.set_linkage(linkage);
+            global.set_initializer(&zero_value(ty));
         }
+        self.globals
+            .insert(Rc::clone(name), global.as_pointer_value().into());
     }
 
-    fn gen_inner_stmt(
+    fn gen_function_define(
         &mut self,
-        statement: &ast::Statement,
-        function: inkwell::values::FunctionValue<'a>,
+        func: &mir::FunctionExpr,
+        func_val: values::FunctionValue<'a>,
     ) {
-        match statement {
-            ast::Statement::ExpressionStatement(expr_stmt) => {
-                self.eval_expr(&expr_stmt.expression);
+        // Functions do not capture anything, so they only see the globals
+        let outer_symbtab = std::mem::replace(&mut self.symbtab, self.globals.clone());
+        let outer_targets = std::mem::replace(&mut self.yield_targets, Vec::new());
+        let outer_loops = std::mem::replace(&mut self.loop_targets, Vec::new());
+        let outer_block = self.builder.get_insert_block();
+
+        let entry_bb = self.context.append_basic_block(func_val, "entry");
+        self.builder.position_at_end(entry_bb);
+
+        for (idx, (name, ty)) in func
+            .arg_names
+            .iter()
+            .zip(&func.signature.pos_args)
+            .enumerate()
+        {
+            let arg_alloca = self.create_entry_alloca(self.get_type(ty), &name.to_string());
+            self.builder.build_store(
+                arg_alloca,
+                func_val.get_nth_param(idx.try_into().unwrap()).unwrap(),
+            );
+            self.symbtab
+                .insert(Rc::clone(name), arg_alloca.as_basic_value_enum());
+        }
+
+        // The value of the function body is the return value
+        let ret_val = self.gen_block(&func.block, &func.signature.return_type);
+        self.builder.build_return(Some(&ret_val));
+
+        self.symbtab = outer_symbtab;
+        self.yield_targets = outer_targets;
+        self.loop_targets = outer_loops;
+        if let Some(block) = outer_block {
+            self.builder.position_at_end(block);
+        }
+    }
+
+    fn gen_block(&mut self, block: &mir::Block, ty: &mir::MirType) -> values::BasicValueEnum<'a> {
+        let function = self.current_function();
+
+        let value_alloca = self.create_entry_alloca(self.get_type(ty), "block_value");
+        let after_block = self.context.append_basic_block(function, "after_block");
+
+        self.yield_targets.push((value_alloca, after_block));
+        for stmt in &block.nodes {
+            self.gen_stmt(stmt);
+        }
+        self.yield_targets.pop();
+
+        self.builder.build_unconditional_branch(after_block);
+        self.builder.position_at_end(after_block);
+
+        self.builder.build_load(value_alloca, "block_value")
+    }
+
+    fn gen_stmt(&mut self, stmt: &mir::MirStmt) {
+        match stmt {
+            mir::MirStmt::Expression(expr) => {
+                self.gen_expr(expr);
             }
-            ast::Statement::Return(ret) => self.gen_return(ret),
-            ast::Statement::TypeAssign(_type_assign) => {
-                panic!("statement codegen not implemented for type_assign")
+            mir::MirStmt::VariableDeclaration(var_dec) => {
+                let var_alloca = self.create_entry_alloca(
+                    self.get_type(&var_dec.binding.ty),
+                    &var_dec.binding.name.to_string(),
+                );
+                self.symbtab.insert(
+                    Rc::clone(&var_dec.binding.name),
+                    var_alloca.as_basic_value_enum(),
+                );
             }
-            ast::Statement::VariableDeclaration(var_dec) => {
-                self.gen_variable_dec(var_dec);
+            mir::MirStmt::Tag(_) => {}
+        }
+    }
+
+    fn gen_expr(&mut self, expr: &mir::MirExpr) -> values::BasicValueEnum<'a> {
+        match &expr.value {
+            mir::MirExprEnum::Variable(name) => self.gen_variable(name),
+            mir::MirExprEnum::Literal(literal) => self.gen_literal(literal),
+            mir::MirExprEnum::Tuple(values) => self.gen_tuple(values, &expr.ty),
+            mir::MirExprEnum::Block(block) => self.gen_block(block, &expr.ty),
+            mir::MirExprEnum::Function(func) => {
+                // Anonymous function
+                let func_val = self.module.add_function(
+                    "anonymous",
+                    self.get_fn_type(&func.signature),
+                    Some(Linkage::Private),
+                );
+                self.gen_function_define(func, func_val);
+                func_val.as_global_value().as_pointer_value().into()
             }
-            ast::Statement::Conditional(conditional) => self.gen_conditional(conditional, function),
-            _ => {}
+            mir::MirExprEnum::FunctionCall(call) => self.gen_call(call),
+            mir::MirExprEnum::VariableAssign(var_assign) => self.gen_variable_assign(var_assign),
+            mir::MirExprEnum::VariableAssignDeclaration(var_dec) => {
+                self.gen_variable_assign_dec(var_dec)
+            }
+            mir::MirExprEnum::Return(value) => {
+                let ret_val = self.gen_expr(value);
+                self.builder.build_return(Some(&ret_val));
+                self.position_at_dead_block();
+                self.unit_value()
+            }
+            mir::MirExprEnum::Yield(value) => {
+                let yield_val = self.gen_expr(value);
+                let (value_alloca, after_block) = *self
+                    .yield_targets
+                    .last()
+                    .expect("`yield` outside of a block");
+                self.builder.build_store(value_alloca, yield_val);
+                self.builder.build_unconditional_branch(after_block);
+                self.position_at_dead_block();
+                self.unit_value()
+            }
+            mir::MirExprEnum::Infix(infix) => self.gen_infix(infix),
+            mir::MirExprEnum::Logical(logical) => self.gen_logical(logical),
+            mir::MirExprEnum::Prefix(prefix) => self.gen_prefix(prefix),
+            mir::MirExprEnum::Cast(value) => self.gen_cast(value, &expr.ty),
+            mir::MirExprEnum::Conditional(conditional) => {
+                self.gen_conditional(conditional, &expr.ty)
+            }
+            mir::MirExprEnum::Loop(loop_expr) => self.gen_loop(loop_expr, &expr.ty),
+            mir::MirExprEnum::Match(match_expr) => self.gen_match(match_expr, &expr.ty),
+            mir::MirExprEnum::Break(break_expr) => {
+                let value = break_expr.value.as_ref().map(|value| self.gen_expr(value));
+                let target = self.loop_target(break_expr.loop_id);
+                if let Some(value) = value {
+                    self.builder.build_store(target.value_alloca, value);
+                }
+                self.builder.build_unconditional_branch(target.break_block);
+                self.position_at_dead_block();
+                self.unit_value()
+            }
+            mir::MirExprEnum::Continue(loop_id) => {
+                let target = self.loop_target(*loop_id);
+                self.builder
+                    .build_unconditional_branch(target.continue_block);
+                self.position_at_dead_block();
+                self.unit_value()
+            }
+        }
+    }
+
+    fn gen_variable(&mut self, name: &Rc<ast::Namespace>) -> values::BasicValueEnum<'a> {
+        match self.symbtab.get(name) {
+            Some(addr) => self
+                .builder
+                .build_load(addr.into_pointer_value(), &name.to_string()),
+            None => self.functions[name]
+                .as_global_value()
+                .as_pointer_value()
+                .into(),
+        }
+    }
+
+    fn gen_literal(&mut self, literal: &mir::Literal) -> values::BasicValueEnum<'a> {
+        match &literal.ty {
+            mir::MirType::Primitive(Prim::Bool, _) => self
+                .context
+                .bool_type()
+                .const_int((literal.value == "true") as u64, false)
+                .into(),
+            mir::MirType::Primitive(Prim::Str, _) => {
+                // The bytes are a global constant without a terminator, so the length covers
+                // every byte including an embedded `\0`
+                let bytes = literal.value.as_bytes();
+                let i8_type = self.context.i8_type();
+                let global =
+                    self.module
+                        .add_global(i8_type.array_type(bytes.len() as u32), None, "str");
+                global.set_linkage(Linkage::Private);
+                global.set_constant(true);
+                global.set_initializer(&self.context.const_string(bytes, false));
+                let bytes = global
+                    .as_pointer_value()
+                    .const_cast(i8_type.ptr_type(AddressSpace::Generic));
+                let len = self
+                    .usize_type()
+                    .const_int(literal.value.len() as u64, false);
+                self.str_type()
+                    .const_named_struct(&[bytes.into(), len.into()])
+                    .into()
+            }
+            mir::MirType::Primitive(prim, _) if prim.is_float() => {
+                self.get_type(&literal.ty)
+                    .into_float_type()
+                    .const_float(
+                        literal.value.parse::<f64>().unwrap_or_else(|_| {
+                            panic!("Cannot convert `{}` to float", literal.value)
+                        }),
+                    )
+                    .into()
+            }
+            mir::MirType::Primitive(_, _) => {
+                self.get_type(&literal.ty)
+                    .into_int_type()
+                    .const_int(
+                        literal.value.parse::<u64>().unwrap_or_else(|_| {
+                            panic!("Cannot convert `{}` to int", literal.value)
+                        }),
+                        false,
+                    )
+                    .into()
+            }
+            _ => panic!("literal {:?} not implemented yet", literal),
+        }
+    }
+
+    fn gen_infix(&mut self, infix: &mir::Infix) -> values::BasicValueEnum<'a> {
+        let signed = match &infix.left.ty {
+            mir::MirType::Primitive(prim, _) if prim.is_float() => {
+                return self.gen_float_infix(infix);
+            }
+            mir::MirType::Primitive(prim, _) => prim.is_signed(),
+            _ => true,
         };
+
+        let left = self.gen_expr(&infix.left).into_int_value();
+        let right = self.gen_expr(&infix.right).into_int_value();
+
+        let compare = |signed_predicate, unsigned_predicate, name| {
+            let predicate = if signed {
+                signed_predicate
+            } else {
+                unsigned_predicate
+            };
+            self.builder.build_int_compare(predicate, left, right, name)
+        };
+
+        match infix.operator {
+            lexer::TokenType::Add => self.builder.build_int_add(left, right, "add"),
+            lexer::TokenType::Sub => self.builder.build_int_sub(left, right, "sub"),
+            lexer::TokenType::Mul => self.builder.build_int_mul(left, right, "mul"),
+            lexer::TokenType::Div if signed => {
+                self.builder.build_int_signed_div(left, right, "div")
+            }
+            lexer::TokenType::Div => self.builder.build_int_unsigned_div(left, right, "div"),
+            lexer::TokenType::Mod => self.gen_int_rem(left, right, signed),
+            lexer::TokenType::DMod => {
+                // `a %% b` is true when `a` is divisible by `b`
+                let rem = self.gen_int_rem(left, right, signed);
+                self.builder.build_int_compare(
+                    IntPredicate::EQ,
+                    rem,
+                    rem.get_type().const_int(0, false),
+                    "dmod",
+                )
+            }
+            lexer::TokenType::GT => compare(IntPredicate::SGT, IntPredicate::UGT, "gt"),
+            lexer::TokenType::LT => compare(IntPredicate::SLT, IntPredicate::ULT, "lt"),
+            lexer::TokenType::GE => compare(IntPredicate::SGE, IntPredicate::UGE, "ge"),
+            lexer::TokenType::LE => compare(IntPredicate::SLE, IntPredicate::ULE, "le"),
+            lexer::TokenType::EQ => compare(IntPredicate::EQ, IntPredicate::EQ, "eq"),
+            lexer::TokenType::NE => compare(IntPredicate::NE, IntPredicate::NE, "ne"),
+            lexer::TokenType::BitAnd => self.builder.build_and(left, right, "and"),
+            lexer::TokenType::BitOr => self.builder.build_or(left, right, "or"),
+            lexer::TokenType::BitXor => self.builder.build_xor(left, right, "xor"),
+            lexer::TokenType::Shl => self.builder.build_left_shift(left, right, "shl"),
+            // Signed values keep their sign with an arithmetic shift
+            lexer::TokenType::Shr => self.builder.build_right_shift(left, right, signed, "shr"),
+            _ => panic!("infix operator {:?} not implemented yet", infix.operator),
+        }
+        .into()
+    }
+
+    fn gen_int_rem(
+        &self,
+        left: values::IntValue<'a>,
+        right: values::IntValue<'a>,
+        signed: bool,
+    ) -> values::IntValue<'a> {
+        if signed {
+            self.builder.build_int_signed_rem(left, right, "mod")
+        } else {
+            self.builder.build_int_unsigned_rem(left, right, "mod")
+        }
     }
 
-    fn eval_expr(&mut self, expr: &ast::Expr) -> values::BasicValueEnum<'a> {
-        match expr {
-            ast::Expr::VariableAssignDeclaration(variable_assign_dec) => {
-                self.eval_variable_assignment_dec(variable_assign_dec)
+    fn gen_float_infix(&mut self, infix: &mir::Infix) -> values::BasicValueEnum<'a> {
+        let left = self.gen_expr(&infix.left).into_float_value();
+        let right = self.gen_expr(&infix.right).into_float_value();
+
+        let compare = |predicate, name| -> values::BasicValueEnum<'a> {
+            self.builder
+                .build_float_compare(predicate, left, right, name)
+                .into()
+        };
+
+        match infix.operator {
+            lexer::TokenType::Add => self.builder.build_float_add(left, right, "add").into(),
+            lexer::TokenType::Sub => self.builder.build_float_sub(left, right, "sub").into(),
+            lexer::TokenType::Mul => self.builder.build_float_mul(left, right, "mul").into(),
+            lexer::TokenType::Div => self.builder.build_float_div(left, right, "div").into(),
+            lexer::TokenType::Mod => self.builder.build_float_rem(left, right, "mod").into(),
+            lexer::TokenType::DMod => {
+                let rem = self.builder.build_float_rem(left, right, "mod");
+                self.builder
+                    .build_float_compare(
+                        FloatPredicate::OEQ,
+                        rem,
+                        rem.get_type().const_float(0.0),
+                        "dmod",
+                    )
+                    .into()
             }
-            ast::Expr::VariableAssign(variable_assign) => {
-                self.eval_variable_assign(variable_assign)
+            lexer::TokenType::GT => compare(FloatPredicate::OGT, "gt"),
+            lexer::TokenType::LT => compare(FloatPredicate::OLT, "lt"),
+            lexer::TokenType::GE => compare(FloatPredicate::OGE, "ge"),
+            lexer::TokenType::LE => compare(FloatPredicate::OLE, "le"),
+            lexer::TokenType::EQ => compare(FloatPredicate::OEQ, "eq"),
+            // `NaN` is unequal to every value
+            lexer::TokenType::NE => compare(FloatPredicate::UNE, "ne"),
+            _ => panic!("infix operator {:?} not implemented yet", infix.operator),
+        }
+    }
+
+    /// The left operand decides `a && b` if it is false and `a || b` if it is true, the right
+    /// operand is only evaluated otherwise
+    fn gen_logical(&mut self, logical: &mir::Logical) -> values::BasicValueEnum<'a> {
+        let function = self.current_function();
+
+        let value_alloca =
+            self.create_entry_alloca(self.context.bool_type().into(), "logical_value");
+        let right_block = self.context.append_basic_block(function, "logical_right");
+        let after_logical = self.context.append_basic_block(function, "after_logical");
+
+        let left = self.gen_expr(&logical.left).into_int_value();
+        self.builder.build_store(value_alloca, left);
+        match logical.operator {
+            lexer::TokenType::And => {
+                self.builder
+                    .build_conditional_branch(left, right_block, after_logical)
             }
-            ast::Expr::RefID(ref_id) => self.builder.build_load(
-                self.symbtab
-                    .get(Rc::clone(&ref_id.value))
-                    .into_pointer_value(),
-                "temp",
+            lexer::TokenType::Or => {
+                self.builder
+                    .build_conditional_branch(left, after_logical, right_block)
+            }
+            _ => panic!(
+                "logical operator {:?} not implemented yet",
+                logical.operator
             ),
-            ast::Expr::Literal(lit) => self.eval_literal(lit),
-            ast::Expr::FunctionCall(func_call) => self.eval_func_call(func_call),
-            ast::Expr::Tuple(tuple) => self.eval_tuple(tuple),
-            ast::Expr::Infix(value) => self.eval_infix(value),
-            _ => panic!("{:?} not implemented yet", expr.to_str()),
+        };
+
+        self.builder.position_at_end(right_block);
+        let right = self.gen_expr(&logical.right);
+        self.builder.build_store(value_alloca, right);
+        self.builder.build_unconditional_branch(after_logical);
+
+        self.builder.position_at_end(after_logical);
+        self.builder.build_load(value_alloca, "logical_value")
+    }
+
+    fn gen_prefix(&mut self, prefix: &mir::Prefix) -> values::BasicValueEnum<'a> {
+        if let mir::MirType::Primitive(prim, _) = &prefix.val.ty {
+            if prim.is_float() {
+                let val = self.gen_expr(&prefix.val).into_float_value();
+                return match prefix.operator {
+                    lexer::TokenType::Sub => self.builder.build_float_neg(val, "neg").into(),
+                    _ => panic!("prefix operator {:?} not implemented yet", prefix.operator),
+                };
+            }
         }
+
+        let val = self.gen_expr(&prefix.val).into_int_value();
+
+        match prefix.operator {
+            lexer::TokenType::Sub => self.builder.build_int_neg(val, "neg"),
+            lexer::TokenType::Not | lexer::TokenType::BitNot => self.builder.build_not(val, "not"),
+            _ => panic!("prefix operator {:?} not implemented yet", prefix.operator),
+        }
+        .into()
     }
 
-    fn eval_infix(&mut self, infix: &ast::Infix) -> values::BasicValueEnum<'a> {
-        self.eval_func_call(infix.function_call.as_ref().unwrap())
+    /// Convert `value` into `ty`, integers are extended according to the signedness of `value`
+    fn gen_cast(&mut self, value: &mir::MirExpr, ty: &mir::MirType) -> values::BasicValueEnum<'a> {
+        let (from, to) = match (&value.ty, ty) {
+            (mir::MirType::Primitive(from, _), mir::MirType::Primitive(to, _)) => (*from, *to),
+            _ => panic!("cast from {:?} to {:?} not implemented yet", value.ty, ty),
+        };
+        let val = self.gen_expr(value);
+        let to_type = self.get_type(ty);
+
+        match (from.is_float(), to.is_float()) {
+            (true, true) => self
+                .builder
+                .build_float_cast(val.into_float_value(), to_type.into_float_type(), "cast")
+                .into(),
+            (true, false) if to.is_signed() => self
+                .builder
+                .build_float_to_signed_int(val.into_float_value(), to_type.into_int_type(), "cast")
+                .into(),
+            (true, false) => self
+                .builder
+                .build_float_to_unsigned_int(
+                    val.into_float_value(),
+                    to_type.into_int_type(),
+                    "cast",
+                )
+                .into(),
+            (false, true) if from.is_signed() => self
+                .builder
+                .build_signed_int_to_float(val.into_int_value(), to_type.into_float_type(), "cast")
+                .into(),
+            (false, true) => self
+                .builder
+                .build_unsigned_int_to_float(
+                    val.into_int_value(),
+                    to_type.into_float_type(),
+                    "cast",
+                )
+                .into(),
+            (false, false) => {
+                let val = val.into_int_value();
+                let to_type = to_type.into_int_type();
+                match val.get_type().get_bit_width().cmp(&to_type.get_bit_width()) {
+                    Ordering::Greater => self.builder.build_int_truncate(val, to_type, "cast"),
+                    Ordering::Less if from.is_signed() => {
+                        self.builder.build_int_s_extend(val, to_type, "cast")
+                    }
+                    Ordering::Less => self.builder.build_int_z_extend(val, to_type, "cast"),
+                    Ordering::Equal => val,
+                }
+                .into()
+            }
+        }
     }
 
-    fn eval_tuple(&mut self, tuple: &ast::Tuple) -> values::BasicValueEnum<'a> {
-        let values: Vec<values::BasicValueEnum<'_>> = tuple
-            .values
-            .iter()
-            .map(|value| self.eval_expr(value))
-            .collect();
-        self.get_type(tuple.type_val.as_ref().unwrap())
-            .into_struct_type()
-            .const_named_struct(&values[..])
-            .into()
+    fn gen_tuple(
+        &mut self,
+        values: &[mir::MirExpr],
+        ty: &mir::MirType,
+    ) -> values::BasicValueEnum<'a> {
+        let mut tuple = self.get_type(ty).into_struct_type().get_undef();
+        for (idx, value) in values.iter().enumerate() {
+            let value = self.gen_expr(value);
+            tuple = self
+                .builder
+                .build_insert_value(tuple, value, idx.try_into().unwrap(), "tuple")
+                .unwrap()
+                .into_struct_value();
+        }
+        tuple.into()
     }
 
-    fn eval_func_call(&mut self, func_call: &ast::FunctionCall) -> values::BasicValueEnum<'a> {
-        let arguments: Vec<values::BasicValueEnum<'a>> = func_call
+    fn gen_call(&mut self, call: &mir::FunctionCall) -> values::BasicValueEnum<'a> {
+        let arguments: Vec<values::BasicValueEnum<'a>> = call
             .arguments
-            .positional
             .iter()
-            .map(|argument| self.eval_expr(argument))
+            .map(|argument| self.gen_expr(argument))
             .collect();
-        let str_val = func_call.mangled_name.as_ref().unwrap();
 
-        self.builder
-            .build_call(
-                self.module.get_function(&str_val[..]).unwrap(),
-                &arguments[..],
-                &func_call.name.to_string()[..],
-            )
-            .try_as_basic_value()
-            .left()
-            .unwrap()
+        let name = call.name.to_string();
+        let call_site = match self.symbtab.get(&call.name) {
+            // Functions passed as arguments or stored in variables are called through a pointer
+            Some(addr) => {
+                let function = self
+                    .builder
+                    .build_load(addr.into_pointer_value(), &name)
+                    .into_pointer_value();
+                self.builder.build_call(function, &arguments[..], &name)
+            }
+            None => self
+                .builder
+                .build_call(self.functions[&call.name], &arguments[..], &name),
+        };
+        call_site.try_as_basic_value().left().unwrap()
     }
 
-    fn eval_variable_assign(
+    fn gen_variable_assign(
         &mut self,
-        var_assign: &ast::VariableAssign,
+        var_assign: &mir::VariableAssign,
     ) -> values::BasicValueEnum<'a> {
         let addr = self
             .symbtab
-            .get(Rc::clone(&var_assign.name))
+            .get(&var_assign.var_name)
+            .unwrap()
             .into_pointer_value();
-        let evaled_expr = self.eval_expr(&var_assign.expr);
-        self.builder.build_store(addr, evaled_expr);
-        self.symbtab
-            .insert(Rc::clone(&var_assign.name), addr.as_basic_value_enum());
-        addr.as_basic_value_enum()
-    }
-
-    fn eval_literal(&mut self, literal: &ast::Literal) -> values::BasicValueEnum<'a> {
-        match self.get_type(&literal.type_val.unwrap_type_check_ref()) {
-            int_val if int_val.is_int_type() => std::convert::From::from(
-                int_val.into_int_type().const_int(
-                    literal
-                        .value
-                        .parse::<u64>()
-                        .expect(&format!("Cannot convert `{}` to int", literal.value)[..]),
-                    false,
-                ),
-            ),
-            _ => panic!("literal {:?} not implemented yet", literal),
-        }
+        let value = self.gen_expr(&var_assign.expr);
+        self.builder.build_store(addr, value);
+        value
     }
 
-    fn eval_variable_assignment_dec(
+    fn gen_variable_assign_dec(
         &mut self,
-        variable_assign_dec: &ast::VariableAssignDeclaration,
+        var_dec: &mir::VariableAssignDeclaration,
     ) -> values::BasicValueEnum<'a> {
-        let assigned_type = self.get_type(variable_assign_dec.t.unwrap_type_check_ref());
-        let expr_alloca = self.builder.build_alloca(
-            assigned_type,
-            &variable_assign_dec.name.deref().to_string()[..],
+        if let mir::MirExprEnum::Function(func) = &var_dec.expr.value {
+            // Inner functions become their own llvm function
+            let func_val = self.gen_function_prototype(
+                &var_dec.var_name,
+                &var_dec.var_name.to_string(),
+                &func.signature,
+                Linkage::Private,
+            );
+            self.gen_function_define(func, func_val);
+            return func_val.as_global_value().as_pointer_value().into();
+        }
+
+        let value = self.gen_expr(&var_dec.expr);
+        let var_alloca = self.create_entry_alloca(
+            self.get_type(&var_dec.expr.ty),
+            &var_dec.var_name.to_string(),
         );
-        let assigned_val = self.eval_expr(&variable_assign_dec.expr);
-        self.builder.build_store(expr_alloca, assigned_val);
+        self.builder.build_store(var_alloca, value);
         self.symbtab.insert(
-            Rc::clone(&variable_assign_dec.name),
-            expr_alloca.as_basic_value_enum(),
+            Rc::clone(&var_dec.var_name),
+            var_alloca.as_basic_value_enum(),
         );
-        expr_alloca.as_basic_value_enum()
+        value
     }
 
-    fn gen_variable_dec(&mut self, var_dec: &ast::VariableDeclaration) {
-        let var_type = self.get_type(var_dec.t.unwrap_type_check_ref());
-        let var_addr = self
-            .builder
-            .build_alloca(var_type, &var_dec.name.deref().to_string()[..]);
-        self.symbtab
-            .insert(Rc::clone(&var_dec.name), std::convert::From::from(var_addr));
+    fn gen_conditional(
+        &mut self,
+        conditional: &mir::Conditional,
+        ty: &mir::MirType,
+    ) -> values::BasicValueEnum<'a> {
+        let function = self.current_function();
+
+        let value_alloca = self.create_entry_alloca(self.get_type(ty), "cond_value");
+
+        let then_block = self.context.append_basic_block(function, "if_block");
+        let else_block = self.context.append_basic_block(function, "else_block");
+        let after_cond = self.context.append_basic_block(function, "after_cond");
+
+        let cond_expr = self.gen_expr(&conditional.if_b.cond).into_int_value();
+        self.builder
+            .build_conditional_branch(cond_expr, then_block, else_block);
+
+        // Both branches have the type of the conditional and always store their value, a branch
+        // that returns leaves the builder in an unreachable block so its store never runs
+        self.builder.position_at_end(then_block);
+        let then_value = self.gen_block(&conditional.if_b.block, ty);
+        self.builder.build_store(value_alloca, then_value);
+        self.builder.build_unconditional_branch(after_cond);
+
+        self.builder.position_at_end(else_block);
+        let else_value = self.gen_block(&conditional.else_b.block, ty);
+        self.builder.build_store(value_alloca, else_value);
+        self.builder.build_unconditional_branch(after_cond);
+
+        self.builder.position_at_end(after_cond);
+        self.builder.build_load(value_alloca, "cond_value")
     }
 
-    fn get_unit_proto(&mut self, unit: &ast::Unit) {
-        for node in &unit.block.nodes {
-            self.gen_stmt_pass_1(node);
+    /// The condition is checked before every iteration, `continue` jumps to the step that
+    /// counts up in a `for` loop and `break` jumps after the loop
+    fn gen_loop(&mut self, loop_expr: &mir::Loop, ty: &mir::MirType) -> values::BasicValueEnum<'a> {
+        let function = self.current_function();
+
+        let value_alloca = self.create_entry_alloca(self.get_type(ty), "loop_value");
+
+        // The range of a `for` loop is evaluated once, before the first iteration
+        let counter = match &loop_expr.kind {
+            mir::LoopKind::For { range, .. } => {
+                let signed = match &range.ty {
+                    mir::MirType::Tuple(tys, _) => {
+                        matches!(tys[0], mir::MirType::Primitive(prim, _) if prim.is_signed())
+                    }
+                    _ => true,
+                };
+                let range = self.gen_expr(range).into_struct_value();
+                let start = self.builder.build_extract_value(range, 0, "start").unwrap();
+                let end = self.builder.build_extract_value(range, 1, "end").unwrap();
+                let inclusive = self
+                    .builder
+                    .build_extract_value(range, 2, "inclusive")
+                    .unwrap();
+
+                let counter_alloca = self.create_entry_alloca(start.get_type(), "counter");
+                self.builder.build_store(counter_alloca, start);
+                Some((
+                    counter_alloca,
+                    end.into_int_value(),
+                    inclusive.into_int_value(),
+                    signed,
+                ))
+            }
+            _ => None,
+        };
+
+        let cond_block = self.context.append_basic_block(function, "loop_cond");
+        let body_block = self.context.append_basic_block(function, "loop_body");
+        let step_block = self.context.append_basic_block(function, "loop_step");
+        let after_loop = self.context.append_basic_block(function, "after_loop");
+
+        self.builder.build_unconditional_branch(cond_block);
+        self.builder.position_at_end(cond_block);
+        match (&loop_expr.kind, counter) {
+            (mir::LoopKind::While(cond), _) => {
+                let cond = self.gen_expr(cond).into_int_value();
+                self.builder
+                    .build_conditional_branch(cond, body_block, after_loop);
+            }
+            (_, Some((counter_alloca, end, inclusive, signed))) => {
+                let count = self
+                    .builder
+                    .build_load(counter_alloca, "count")
+                    .into_int_value();
+                let predicate = if signed {
+                    IntPredicate::SLT
+                } else {
+                    IntPredicate::ULT
+                };
+                let before_end =
+                    self.builder
+                        .build_int_compare(predicate, count, end, "before_end");
+                let at_end = self
+                    .builder
+                    .build_int_compare(IntPredicate::EQ, count, end, "at_end");
+                let included_end = self.builder.build_and(inclusive, at_end, "included_end");
+                let cond = self.builder.build_or(before_end, included_end, "in_range");
+                self.builder
+                    .build_conditional_branch(cond, body_block, after_loop);
+            }
+            _ => {
+                self.builder.build_unconditional_branch(body_block);
+            }
         }
-    }
 
-    fn get_unit(&mut self, unit: &ast::Unit) {
-        for node in &unit.block.nodes {
-            self.gen_stmt_pass_2(node);
+        // Symbols declared in the body are only visible in it
+        let outer_symbtab = self.symbtab.clone();
+        self.builder.position_at_end(body_block);
+        if let (mir::LoopKind::For { name, .. }, Some((counter_alloca, _, _, _))) =
+            (&loop_expr.kind, counter)
+        {
+            // The body gets a copy of the count, so changing it doesn't change the iterations
+            let count = self.builder.build_load(counter_alloca, &name.to_string());
+            let var_alloca = self.create_entry_alloca(count.get_type(), &name.to_string());
+            self.builder.build_store(var_alloca, count);
+            self.symbtab
+                .insert(Rc::clone(name), var_alloca.as_basic_value_enum());
         }
-    }
 
-    fn gen_function_prototype(&mut self, func_def: &ast::FunctionDefine<'_>) {
-        let func_name = &func_def.mangled_name.as_ref().unwrap()[..];
-        let return_type = self.get_type(func_def.return_type.unwrap_type_check_ref());
-        let fn_type = return_type.fn_type(
-            &func_def
-                .arguments
-                .positional
-                .iter()
-                .map(|arg| self.get_type(arg.1.unwrap_type_check_ref()))
-                .collect::<Vec<types::BasicTypeEnum<'_>>>()[..],
-            false,
-        );
+        self.loop_targets.push(LoopTarget {
+            id: loop_expr.id,
+            value_alloca,
+            break_block: after_loop,
+            continue_block: step_block,
+        });
+        self.gen_block(&loop_expr.block, &loop_expr.block_ty);
+        self.loop_targets.pop();
+        self.symbtab = outer_symbtab;
+        self.builder.build_unconditional_branch(step_block);
+
+        self.builder.position_at_end(step_block);
+        if let Some((counter_alloca, end, _, _)) = counter {
+            let count = self
+                .builder
+                .build_load(counter_alloca, "count")
+                .into_int_value();
 
-        let function = self.module.add_function(func_name, fn_type, None);
-        function.set_linkage(func_def.visibility.get_linkage());
+            // Only the end of an inclusive range gets here, counting past it could overflow
+            let at_end = self
+                .builder
+                .build_int_compare(IntPredicate::EQ, count, end, "at_end");
+            let count_block = self.context.append_basic_block(function, "loop_count");
+            self.builder
+                .build_conditional_branch(at_end, after_loop, count_block);
+            self.builder.position_at_end(count_block);
+
+            let next = self.builder.build_int_add(
+                count,
+                count.get_type().const_int(1, false),
+                "next_count",
+            );
+            self.builder.build_store(counter_alloca, next);
+        }
+        self.builder.build_unconditional_branch(cond_block);
+
+        self.builder.position_at_end(after_loop);
+        self.builder.build_load(value_alloca, "loop_value")
     }
 
-    fn gen_function_define(
+    /// Arms are tested in order, a failed pattern or guard jumps to the next arm
+    fn gen_match(
         &mut self,
-        func_def: &ast::FunctionDefine,
-        func_val: values::FunctionValue<'a>,
-    ) {
-        match &func_def.block {
-            Some(block) => {
-                self.symbtab.clear();
-
-                let entry_bb = self.context.append_basic_block(func_val, "entry");
-                let builder = self.context.create_builder();
-                builder.position_at_end(entry_bb);
-
-                for (idx, (argument, type_val)) in func_def.arguments.positional.iter().enumerate()
-                {
-                    let arg_type = self.get_type(type_val.unwrap_type_check_ref());
-                    let argument_alloca = builder.build_alloca(arg_type, &argument.value[..]);
-                    builder.build_store(
-                        argument_alloca,
-                        func_val.get_nth_param(idx.try_into().unwrap()).unwrap(),
-                    );
+        match_expr: &mir::Match,
+        ty: &mir::MirType,
+    ) -> values::BasicValueEnum<'a> {
+        let function = self.current_function();
 
-                    self.symbtab.insert(
-                        Rc::new(argument.into_namespace()),
-                        argument_alloca.as_basic_value_enum(),
-                    );
-                }
+        let value_alloca = self.create_entry_alloca(self.get_type(ty), "match_value");
+        let after_match = self.context.append_basic_block(function, "after_match");
 
-                self.builder = builder;
-                self.generate_inner_block(block, func_val);
+        let value = self.gen_expr(&match_expr.expr);
+        for arm in &match_expr.arms {
+            let next_arm = self.context.append_basic_block(function, "next_arm");
+
+            // Bindings of the pattern are only visible in the arm
+            let outer_symbtab = self.symbtab.clone();
+            self.gen_pattern(&arm.pattern, value, next_arm);
+            if let Some(guard) = &arm.guard {
+                let guard = self.gen_expr(guard).into_int_value();
+                let arm_block = self.context.append_basic_block(function, "match_arm");
+                self.builder
+                    .build_conditional_branch(guard, arm_block, next_arm);
+                self.builder.position_at_end(arm_block);
             }
-            None => {}
-        }
-    }
 
-    fn gen_return(&mut self, ret: &ast::Return) {
-        let ret_val = self.eval_expr(&ret.expression);
-        self.builder.build_return(Some(&ret_val));
-    }
+            let arm_value = self.gen_expr(&arm.expr);
+            if !matches!(arm.expr.ty, mir::MirType::Never(_)) {
+                self.builder.build_store(value_alloca, arm_value);
+            }
+            self.builder.build_unconditional_branch(after_match);
+            self.symbtab = outer_symbtab;
 
-    fn gen_if_some(&mut self, after_cond: Option<inkwell::basic_block::BasicBlock<'_>>) {
-        if let Some(after_cond) = after_cond {
-            self.builder.build_unconditional_branch(after_cond);
+            self.builder.position_at_end(next_arm);
         }
+        // Exhaustiveness was checked, no value gets past the last arm
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(after_match);
+        self.builder.build_load(value_alloca, "match_value")
     }
 
-    fn gen_conditional(
+    /// Continue in a new block if `value` matches the pattern, otherwise jump to `no_match`
+    fn gen_pattern(
         &mut self,
-        conditional: &ast::Conditional,
-        func_val: inkwell::values::FunctionValue<'a>,
+        pattern: &mir::Pattern,
+        value: values::BasicValueEnum<'a>,
+        no_match: basic_block::BasicBlock<'a>,
     ) {
-        // fluo: if {}
-        //       else if {}
-        //       else if {}
-        //       else {}
-        //
-        // llvm: if {}
-        //       else {
-        //         if {
-        //           // else if #1
-        //         }
-        //         else {
-        //           if { // else if #2  }
-        //           else { // final else }
-        //         } // etc
-        //       }
-        // | ----------------------------------------------------------------- |
-        // | We traverse down the conditional starting from the first if block |
-        // | ----------------------------------------------------------------- |
-
-        // The block positioned after the conditionals
-        let after_cond = if !conditional.all_branches_return() {
-            Some(self.context.append_basic_block(func_val, "after_cond"))
-        } else {
-            None
+        let signed = match &pattern.ty {
+            mir::MirType::Primitive(prim, _) => prim.is_signed(),
+            _ => true,
         };
 
-        // "highest" block
-        let mut highest_block = self
+        match &pattern.kind {
+            mir::PatternKind::Wildcard => {}
+            mir::PatternKind::Literal(literal) => {
+                let literal = self.gen_expr(literal).into_int_value();
+                let cond = self.builder.build_int_compare(
+                    IntPredicate::EQ,
+                    value.into_int_value(),
+                    literal,
+                    "pattern_eq",
+                );
+                self.gen_pattern_test(cond, no_match);
+            }
+            mir::PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let start = self.gen_expr(start).into_int_value();
+                let end = self.gen_expr(end).into_int_value();
+                let (ge, lt) = match (signed, inclusive) {
+                    (true, false) => (IntPredicate::SGE, IntPredicate::SLT),
+                    (true, true) => (IntPredicate::SGE, IntPredicate::SLE),
+                    (false, false) => (IntPredicate::UGE, IntPredicate::ULT),
+                    (false, true) => (IntPredicate::UGE, IntPredicate::ULE),
+                };
+
+                let cond =
+                    self.builder
+                        .build_int_compare(ge, value.into_int_value(), start, "pattern_ge");
+                self.gen_pattern_test(cond, no_match);
+                let cond =
+                    self.builder
+                        .build_int_compare(lt, value.into_int_value(), end, "pattern_lt");
+                self.gen_pattern_test(cond, no_match);
+            }
+            mir::PatternKind::Binding(name) => {
+                let var_alloca = self.create_entry_alloca(value.get_type(), &name.to_string());
+                self.builder.build_store(var_alloca, value);
+                self.symbtab
+                    .insert(Rc::clone(name), var_alloca.as_basic_value_enum());
+            }
+            mir::PatternKind::Tuple(patterns) => {
+                for (i, pattern) in patterns.iter().enumerate() {
+                    let item = self
+                        .builder
+                        .build_extract_value(value.into_struct_value(), i as u32, "item")
+                        .unwrap();
+                    self.gen_pattern(pattern, item, no_match);
+                }
+            }
+        }
+    }
+
+    /// Each test of a pattern either continues in a new block or gives up on the arm
+    fn gen_pattern_test(&self, cond: values::IntValue<'a>, no_match: basic_block::BasicBlock<'a>) {
+        let matched = self
             .context
-            .append_basic_block(func_val, "conditional_block");
+            .append_basic_block(self.current_function(), "pattern_matched");
+        self.builder
+            .build_conditional_branch(cond, matched, no_match);
+        self.builder.position_at_end(matched);
+    }
 
-        self.builder.build_unconditional_branch(highest_block);
+    fn loop_target(&self, loop_id: usize) -> LoopTarget<'a> {
+        *self
+            .loop_targets
+            .iter()
+            .rev()
+            .find(|target| target.id == loop_id)
+            .expect("`break` or `continue` outside of its loop")
+    }
 
-        let mut else_block = highest_block;
+    /// Allocate memory in the entry block of the current function, so that the
+    /// `mem2reg` pass can promote it to a register
+    fn create_entry_alloca(
+        &self,
+        ty: types::BasicTypeEnum<'a>,
+        name: &str,
+    ) -> values::PointerValue<'a> {
+        let builder = self.context.create_builder();
+        let entry = self.current_function().get_first_basic_block().unwrap();
 
-        assert!(!conditional.if_branches.is_empty());
+        match entry.get_first_instruction() {
+            Some(first_instr) => builder.position_before(&first_instr),
+            None => builder.position_at_end(entry),
+        }
 
-        for cond in &conditional.if_branches {
-            else_block = self.context.append_basic_block(func_val, "else_block");
+        builder.build_alloca(ty, name)
+    }
 
-            // Create then block, generate appropriate contents
-            let then_block = self.context.append_basic_block(func_val, "if_block");
-            self.builder.position_at_end(then_block);
-            self.generate_inner_block(&cond.block, func_val);
+    /// Code after a terminator (i.e. `return`) is unreachable, so it's put in a block with no
+    /// predecessors
+    fn position_at_dead_block(&mut self) {
+        let dead_block = self
+            .context
+            .append_basic_block(self.current_function(), "unreachable");
+        self.builder.position_at_end(dead_block);
+    }
 
-            // Jump to next part of flow after this
-            // (Only if the block doesn't return)
-            if !cond.block.returns {
-                self.gen_if_some(after_cond);
-            }
+    fn current_function(&self) -> values::FunctionValue<'a> {
+        self.builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .expect("Builder is not inside a function")
+    }
 
-            // Create conditional statement on the highest block
-            self.builder.position_at_end(highest_block);
-            let cond_expr = self.eval_expr(&cond.cond).into_int_value();
-            self.builder
-                .build_conditional_branch(cond_expr, then_block, else_block);
+    /// Modules are compiled for the host, so pointers have the host's width
+    fn usize_type(&self) -> types::IntType<'a> {
+        self.context
+            .custom_width_int_type(std::mem::size_of::<usize>() as u32 * 8)
+    }
 
-            // Set the new highest block to be the else block
-            highest_block = else_block;
-        }
+    /// Strings are a pointer to their UTF-8 bytes and their length in bytes
+    fn str_type(&self) -> types::StructType<'a> {
+        self.context.struct_type(
+            &[
+                self.context
+                    .i8_type()
+                    .ptr_type(AddressSpace::Generic)
+                    .into(),
+                self.usize_type().into(),
+            ],
+            false,
+        )
+    }
 
-        match &conditional.else_branch {
-            Some(else_branch) => {
-                // There is an else branch
-                // Edit it
-                self.builder.position_at_end(else_block);
-                self.generate_inner_block(&else_branch.block, func_val);
-
-                // Jump to next part of flow after this
-                // (Only if the block doesn't return)
-                if !else_branch.block.returns {
-                    self.gen_if_some(after_cond);
-                }
-            }
-            None => {
-                // Jump to next part of flow after this if theres something after, else dont
-                self.builder.position_at_end(else_block);
-                self.gen_if_some(after_cond);
-            }
-        };
+    fn unit_type(&self) -> types::StructType<'a> {
+        self.context.struct_type(&[], false)
+    }
 
-        if let Some(after_cond) = after_cond {
-            self.builder.position_at_end(after_cond);
-        }
+    fn unit_value(&self) -> values::BasicValueEnum<'a> {
+        self.unit_type().const_named_struct(&[]).into()
+    }
+
+    fn get_fn_type(&self, signature: &mir::FunctionSig) -> types::FunctionType<'a> {
+        let arg_types: Vec<types::BasicTypeEnum<'a>> = signature
+            .pos_args
+            .iter()
+            .map(|arg| self.get_type(arg))
+            .collect();
+        self.get_type(&signature.return_type)
+            .fn_type(&arg_types[..], false)
     }
 
-    fn get_type(&mut self, type_ast: &ast_typecheck::TypeCheckType) -> types::BasicTypeEnum<'a> {
-        match &type_ast.value {
-            ast_typecheck::TypeCheckTypeType::SingleType(value) => match &value.to_string()[..] {
-                "int" => self.context.i32_type().into(),
-                "long" => self.context.i64_type().into(),
-                "str" => self.context.i8_type().array_type(10).into(),
-                "bool" => self.context.bool_type().into(),
-                val => panic!("`{}` type not implemented yet! {:?}", val, type_ast.pos),
+    fn get_type(&self, ty: &mir::MirType) -> types::BasicTypeEnum<'a> {
+        match ty {
+            mir::MirType::Primitive(prim, _) => match prim {
+                Prim::Bool => self.context.bool_type().into(),
+                Prim::I64 | Prim::U64 => self.context.i64_type().into(),
+                Prim::I32 | Prim::U32 | Prim::Char => self.context.i32_type().into(),
+                Prim::I16 | Prim::U16 => self.context.i16_type().into(),
+                Prim::I8 | Prim::U8 => self.context.i8_type().into(),
+                Prim::Isize | Prim::Usize => self.usize_type().into(),
+                Prim::F64 => self.context.f64_type().into(),
+                Prim::F32 => self.context.f32_type().into(),
+                Prim::Str => self.str_type().into(),
             },
-            ast_typecheck::TypeCheckTypeType::CustomType(_, value) => {
-                self.get_type(&*(value.as_ref().unwrap()))
-            }
-            ast_typecheck::TypeCheckTypeType::ArrayType(_, _) => {
-                panic!("Array type not implemented for codegen yet!");
-            }
-            ast_typecheck::TypeCheckTypeType::TupleType(tuple_union) => {
-                let item_types: Vec<types::BasicTypeEnum<'_>> = tuple_union
-                    .types
-                    .iter()
-                    .map(|type_val| self.get_type(type_val))
-                    .collect();
+            mir::MirType::Tuple(types, _) => {
+                let item_types: Vec<types::BasicTypeEnum<'a>> =
+                    types.iter().map(|ty| self.get_type(ty)).collect();
                 self.context.struct_type(&item_types[..], false).into()
             }
-            ast_typecheck::TypeCheckTypeType::FunctionSig(_, _, _) => {
-                panic!("Function pointers not implemented for codegen yet!");
-            }
-            ast_typecheck::TypeCheckTypeType::Placeholder => {
-                panic!("Tried to turn placeholder value into llvm type")
-            }
+            mir::MirType::FunctionSig(signature, _) => self
+                .get_fn_type(signature)
+                .ptr_type(AddressSpace::Generic)
+                .into(),
+            // Never produces a value, so this doesn't matter
+            mir::MirType::Never(_) => self.unit_type().into(),
         }
-    }*/
+    }
+}
+
+/// Value with every bit set to zero, the initial value of globals
+fn zero_value(ty: types::BasicTypeEnum<'_>) -> values::BasicValueEnum<'_> {
+    match ty {
+        types::BasicTypeEnum::ArrayType(ty) => ty.const_zero().into(),
+        types::BasicTypeEnum::FloatType(ty) => ty.const_zero().into(),
+        types::BasicTypeEnum::IntType(ty) => ty.const_zero().into(),
+        types::BasicTypeEnum::PointerType(ty) => ty.const_zero().into(),
+        types::BasicTypeEnum::StructType(ty) => ty.const_zero().into(),
+        types::BasicTypeEnum::VectorType(ty) => ty.const_zero().into(),
+    }
 }
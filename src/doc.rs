@@ -0,0 +1,248 @@
+use crate::parser::ast;
+use crate::typecheck::annotation::{
+    AnnotationType, TypedExpr, TypedExprEnum, TypedStmt, TypedStmtEnum,
+};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path;
+
+/// A documented `pub` item of a unit
+struct Item {
+    /// Name of the item inside of its unit
+    name: String,
+    /// Declaration with its resolved type, i.e. `pub let double: fn (i64) => i64`
+    signature: String,
+    docs: Option<String>,
+}
+
+/// Documented items of a unit
+#[derive(Default)]
+struct Page {
+    /// Doc comments of every declaration of the unit
+    docs: Vec<String>,
+    functions: Vec<Item>,
+    aliases: Vec<Item>,
+}
+
+/// API documentation of a typechecked program, one Markdown page per unit
+pub struct Documentation {
+    /// Pages by the path of their unit, items outside of any unit are on the page with an
+    /// empty path
+    pages: BTreeMap<Vec<String>, Page>,
+}
+
+impl Documentation {
+    pub fn new(stmts: &[TypedStmt]) -> Self {
+        let mut documentation = Documentation {
+            pages: BTreeMap::new(),
+        };
+        documentation.pages.insert(Vec::new(), Page::default());
+        documentation.collect(stmts);
+        documentation
+    }
+
+    /// Collect the `pub` functions and type aliases of `stmts` and of the units inside of it
+    fn collect(&mut self, stmts: &[TypedStmt]) {
+        for stmt in stmts {
+            match &stmt.stmt {
+                TypedStmtEnum::Expression(TypedExpr {
+                    expr: TypedExprEnum::VariableAssignDeclaration(assign),
+                    ..
+                }) if assign.visibility == ast::Visibility::Public => {
+                    if let TypedExprEnum::Function(_) = assign.expr.expr {
+                        let (unit, name) = split_name(assign.binder.name.as_ref().unwrap());
+                        self.page(unit).functions.push(Item {
+                            signature: format!("pub let {}: {};", name, assign.binder.ty),
+                            name,
+                            docs: assign.docs.clone(),
+                        });
+                    }
+                }
+                TypedStmtEnum::VariableDeclaration(declaration)
+                    if declaration.visibility == ast::Visibility::Public =>
+                {
+                    if let AnnotationType::Function(..) = declaration.binder.ty {
+                        let (unit, name) = split_name(declaration.binder.name.as_ref().unwrap());
+                        self.page(unit).functions.push(Item {
+                            signature: format!(
+                                "pub extern let {}: {};",
+                                name, declaration.binder.ty
+                            ),
+                            name,
+                            docs: declaration.docs.clone(),
+                        });
+                    }
+                }
+                TypedStmtEnum::TypeAssign(alias) if alias.visibility == ast::Visibility::Public => {
+                    let (unit, name) = split_name(&alias.name);
+                    self.page(unit).aliases.push(Item {
+                        signature: format!("pub type {} = {};", name, alias.ty),
+                        name,
+                        docs: alias.docs.clone(),
+                    });
+                }
+                TypedStmtEnum::Unit(unit) => {
+                    let page = self.page(path_of(&unit.name));
+                    page.docs.extend(unit.docs.iter().cloned());
+                    self.collect(&unit.stmts);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Page of a unit, the pages of the units it is declared in are created with it so every
+    /// part of its path can be linked
+    fn page(&mut self, unit: Vec<String>) -> &mut Page {
+        for idx in 1..unit.len() {
+            self.pages.entry(unit[..idx].to_vec()).or_default();
+        }
+        self.pages.entry(unit).or_default()
+    }
+
+    /// Write every page to `dir`, the page of the items outside of any unit is `index.md`
+    pub fn write(&self, dir: &path::Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for unit in self.pages.keys() {
+            fs::write(dir.join(file_name(unit)), self.render(unit))?;
+        }
+        Ok(())
+    }
+
+    /// Markdown of the page of `unit`
+    pub fn render(&self, unit: &[String]) -> String {
+        let page = &self.pages[unit];
+        let mut out = String::new();
+
+        if unit.is_empty() {
+            writeln!(out, "# Index").unwrap();
+        } else {
+            writeln!(out, "# Unit `{}`\n", unit.join("::")).unwrap();
+            // Links to the units it is declared in, i.e. `[index] / [core] / fmt`
+            let mut breadcrumbs = vec![format!("[index]({})", file_name(&[]))];
+            for idx in 1..unit.len() {
+                breadcrumbs.push(format!("[{}]({})", unit[idx - 1], file_name(&unit[..idx])));
+            }
+            breadcrumbs.push(unit[unit.len() - 1].clone());
+            writeln!(out, "{}", breadcrumbs.join(" / ")).unwrap();
+        }
+
+        for docs in &page.docs {
+            writeln!(out, "\n{}", self.link_paths(unit, docs)).unwrap();
+        }
+
+        let units = self
+            .pages
+            .keys()
+            .filter(|path| path.len() == unit.len() + 1 && path.starts_with(unit))
+            .collect::<Vec<_>>();
+        if !units.is_empty() {
+            writeln!(out, "\n## Units\n").unwrap();
+            for path in units {
+                writeln!(out, "- [`{}`]({})", path.join("::"), file_name(path)).unwrap();
+            }
+        }
+
+        self.render_items(&mut out, unit, "Functions", "fn", &page.functions);
+        self.render_items(&mut out, unit, "Type Aliases", "type", &page.aliases);
+
+        out
+    }
+
+    fn render_items(
+        &self,
+        out: &mut String,
+        unit: &[String],
+        title: &str,
+        kind: &str,
+        items: &[Item],
+    ) {
+        if items.is_empty() {
+            return;
+        }
+
+        writeln!(out, "\n## {}", title).unwrap();
+        for item in items {
+            writeln!(out, "\n<a id=\"{}.{}\"></a>", kind, item.name).unwrap();
+            writeln!(out, "### `{}`\n", item.name).unwrap();
+            writeln!(out, "```fluo\n{}\n```", item.signature).unwrap();
+            if let Some(docs) = &item.docs {
+                writeln!(out, "\n{}", self.link_paths(unit, docs)).unwrap();
+            }
+        }
+    }
+
+    /// Turn the code spans of `docs` that name a documented unit or item into links, i.e.
+    /// `` `core::fmt::print_long` ``, paths are looked up from `unit` outwards
+    fn link_paths(&self, unit: &[String], docs: &str) -> String {
+        let parts = docs.split('`').collect::<Vec<_>>();
+        let mut out = String::from(parts[0]);
+
+        for chunk in parts[1..].chunks(2) {
+            match chunk {
+                [code, text] => {
+                    match self.resolve(unit, code) {
+                        Some(link) => write!(out, "[`{}`]({})", code, link).unwrap(),
+                        None => write!(out, "`{}`", code).unwrap(),
+                    }
+                    out.push_str(text);
+                }
+                // An unmatched backtick doesn't start a code span
+                [text] => write!(out, "`{}", text).unwrap(),
+                _ => unreachable!(),
+            }
+        }
+
+        out
+    }
+
+    /// Link to the unit or item named by `path` as seen from `unit`
+    fn resolve(&self, unit: &[String], path: &str) -> Option<String> {
+        let path = path.split("::").map(str::to_string).collect::<Vec<_>>();
+        if path.iter().any(|scope| scope.is_empty()) {
+            return None;
+        }
+
+        (0..=unit.len()).rev().find_map(|idx| {
+            let full_path = [&unit[..idx], &path[..]].concat();
+            if self.pages.contains_key(&full_path) {
+                return Some(file_name(&full_path));
+            }
+
+            let (name, parent) = full_path.split_last()?;
+            let page = self.pages.get(parent)?;
+            let kind = if page.functions.iter().any(|item| &item.name == name) {
+                "fn"
+            } else if page.aliases.iter().any(|item| &item.name == name) {
+                "type"
+            } else {
+                return None;
+            };
+            Some(format!("{}#{}.{}", file_name(parent), kind, name))
+        })
+    }
+}
+
+/// Scopes of a name, i.e. `["core", "fmt"]` for `core::fmt`
+fn path_of(name: &ast::Namespace) -> Vec<String> {
+    name.to_string().split("::").map(str::to_string).collect()
+}
+
+/// Split the full name of an item into the path of its unit and its name
+fn split_name(name: &ast::Namespace) -> (Vec<String>, String) {
+    let mut path = path_of(name);
+    let name = path.pop().unwrap();
+    (path, name)
+}
+
+/// File of the page of a unit, i.e. `unit.core.fmt.md` for `core::fmt`
+pub fn file_name(unit: &[String]) -> String {
+    if unit.is_empty() {
+        "index.md".to_string()
+    } else {
+        format!("unit.{}.md", unit.join("."))
+    }
+}
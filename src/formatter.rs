@@ -0,0 +1,426 @@
+use crate::lexer::TokenType;
+use crate::logger::{ErrorValue, Logger};
+use crate::parser::cst::{SyntaxNode, SyntaxToken};
+use crate::parser::Parser;
+use crate::sourcemap::SourceMap;
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+const INDENT: &str = "    ";
+
+/// Parse a file and format it
+pub fn format_file(
+    filename_id: usize,
+    logger: Logger,
+    sourcemap: SourceMap,
+) -> Result<String, Vec<ErrorValue>> {
+    let mut parser = Parser::new(filename_id, logger, Rc::clone(&sourcemap));
+    parser.initialize_expr();
+    parser.parse()?;
+    Ok(format(&parser.syntax_tree()))
+}
+
+/// Format the syntax tree of a file, comments are kept where they are
+pub fn format(tree: &SyntaxNode) -> String {
+    let tokens = tree
+        .tokens()
+        .into_iter()
+        .filter(|token| token.kind != TokenType::EOF)
+        .collect::<Vec<_>>();
+
+    let groups = Group::collect(&tokens);
+    // Items written one per line all end with a comma
+    let commas = groups
+        .values()
+        .filter(|group| group.multiline && group.trailing_comma())
+        .filter_map(|group| group.last)
+        .filter(|&last| tokens[last].kind != TokenType::Comma)
+        .collect();
+
+    let mut formatter = Formatter {
+        groups,
+        commas,
+        next: next_significant(&tokens),
+        tokens,
+        out: String::new(),
+        indent: 0,
+        open_groups: Vec::new(),
+        prev: None,
+        pending: Sep::Pair,
+    };
+    formatter.run();
+    formatter.out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What comes before the next token
+enum Sep {
+    /// Decided by the tokens around it, i.e. a space around `+`
+    Pair,
+    Nothing,
+    Space,
+    Newline,
+    /// A newline if the source has one, a space if not
+    Source,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Items between parentheses, i.e. a tuple or the arguments of a call
+struct Group {
+    /// The source has the items on multiple lines, so they are written one per line
+    multiline: bool,
+    /// Arguments of a function call
+    is_call: bool,
+    commas: usize,
+    /// Index of the last token of the last item
+    last: Option<usize>,
+}
+
+impl Group {
+    /// Groups by the index of their `(`
+    fn collect(tokens: &[&SyntaxToken]) -> HashMap<usize, Group> {
+        let mut groups = HashMap::new();
+        // Open delimiters, with the group of each `(`
+        let mut open: Vec<(usize, Option<Group>)> = Vec::new();
+        let mut prev = None;
+        let mut empty = true;
+
+        for (idx, token) in tokens.iter().enumerate() {
+            let in_group = open.last_mut().and_then(|(_, group)| group.as_mut());
+            match token.kind {
+                TokenType::LP => {
+                    open.push((
+                        idx,
+                        Some(Group {
+                            multiline: false,
+                            is_call: prev == Some(TokenType::Identifier),
+                            commas: 0,
+                            last: None,
+                        }),
+                    ));
+                    empty = true;
+                }
+                TokenType::LCP | TokenType::LB => open.push((idx, None)),
+                TokenType::RP => {
+                    if let Some((start, Some(mut group))) = open.pop() {
+                        // `()` stays on one line
+                        group.multiline &= !empty;
+                        groups.insert(start, group);
+                    }
+                }
+                TokenType::RCP | TokenType::RB => {
+                    open.pop();
+                }
+                TokenType::Comma => {
+                    if let Some(group) = in_group {
+                        group.commas += 1;
+                    }
+                }
+                TokenType::Whitespace(_)
+                | TokenType::LineComment(_)
+                | TokenType::BlockComment(_)
+                | TokenType::DocComment => {
+                    if let Some(group) = in_group {
+                        group.multiline |= token.text.contains('\n') || is_line_comment(token);
+                    }
+                }
+                _ => {}
+            }
+
+            if !token.kind.is_trivia() {
+                if token.kind != TokenType::LP {
+                    empty = false;
+                }
+                if let Some((_, Some(group))) = open.last_mut() {
+                    group.last = Some(idx);
+                }
+                prev = Some(token.kind);
+            }
+        }
+
+        groups
+    }
+
+    /// Whether the group ends with a comma, a tuple of one item needs it
+    fn trailing_comma(&self) -> bool {
+        if self.multiline {
+            self.is_call || self.commas > 0
+        } else {
+            !self.is_call && self.commas == 1
+        }
+    }
+}
+
+/// Index of the next token that isn't trivia, for every token
+fn next_significant(tokens: &[&SyntaxToken]) -> Vec<Option<usize>> {
+    let mut next = vec![None; tokens.len()];
+    let mut following = None;
+    for idx in (0..tokens.len()).rev() {
+        next[idx] = following;
+        if !tokens[idx].kind.is_trivia() {
+            following = Some(idx);
+        }
+    }
+    next
+}
+
+fn is_line_comment(token: &SyntaxToken) -> bool {
+    match token.kind {
+        TokenType::LineComment(_) => true,
+        TokenType::DocComment => token.text.starts_with("---"),
+        _ => false,
+    }
+}
+
+/// Tokens after which a `-` is an infix operator
+fn ends_value(kind: TokenType) -> bool {
+    matches!(
+        kind,
+        TokenType::Identifier
+            | TokenType::Number
+            | TokenType::Float
+            | TokenType::String
+            | TokenType::Char
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Underscore
+            | TokenType::RP
+            | TokenType::RB
+            | TokenType::RCP
+    )
+}
+
+struct Formatter<'a> {
+    tokens: Vec<&'a SyntaxToken>,
+    groups: HashMap<usize, Group>,
+    /// Indices of the tokens a trailing comma is added after
+    commas: HashSet<usize>,
+    next: Vec<Option<usize>>,
+    out: String,
+    indent: usize,
+    /// Groups of the `(` that are still open, `None` for `{` and `[`
+    open_groups: Vec<Option<Group>>,
+    /// Last token that isn't trivia
+    prev: Option<TokenType>,
+    pending: Sep,
+}
+
+impl<'a> Formatter<'a> {
+    fn run(&mut self) {
+        // Newlines in the source since the last token or comment
+        let mut newlines = 0;
+
+        for idx in 0..self.tokens.len() {
+            let token = self.tokens[idx];
+            match token.kind {
+                TokenType::Whitespace(_) => newlines += token.text.matches('\n').count(),
+                TokenType::LineComment(_) | TokenType::BlockComment(_) | TokenType::DocComment => {
+                    self.comment(token, newlines);
+                    newlines = 0;
+                }
+                _ => {
+                    self.token(idx, newlines);
+                    newlines = 0;
+                }
+            }
+        }
+
+        // One newline at the end of the file
+        let len = self.out.trim_end().len();
+        self.out.truncate(len);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    fn comment(&mut self, token: &SyntaxToken, newlines: usize) {
+        if newlines > 0 {
+            // On its own line
+            self.write(Sep::Newline, newlines, token.text.trim_end());
+            self.pending = Sep::Source;
+        } else {
+            // After the code on the same line
+            let pending = self.pending;
+            self.write(Sep::Space, 0, token.text.trim_end());
+            self.pending = pending;
+        }
+
+        if is_line_comment(token) {
+            self.pending = Sep::Newline;
+        } else if self.pending != Sep::Newline {
+            self.pending = Sep::Source;
+        }
+    }
+
+    fn token(&mut self, idx: usize, newlines: usize) {
+        let token = self.tokens[idx];
+        let next = self.next[idx].map(|next| self.tokens[next].kind);
+        let group = self.open_groups.last().copied().flatten();
+
+        let mut sep = match self.pending {
+            Sep::Pair => self.pair(token.kind),
+            Sep::Source if newlines > 0 => Sep::Newline,
+            Sep::Source => Sep::Space,
+            sep => sep,
+        };
+
+        match token.kind {
+            TokenType::RCP => {
+                self.indent = self.indent.saturating_sub(1);
+                self.open_groups.pop();
+                // `{}` stays empty
+                if self.prev != Some(TokenType::LCP) || sep == Sep::Newline {
+                    sep = Sep::Newline;
+                }
+            }
+            TokenType::RP => {
+                self.open_groups.pop();
+                if let Some(group) = group {
+                    if group.multiline {
+                        self.indent = self.indent.saturating_sub(1);
+                        sep = Sep::Newline;
+                    }
+                }
+            }
+            TokenType::Comma => {
+                // Trailing commas are only kept where they are needed
+                if let (Some(group), Some(TokenType::RP)) = (group, next) {
+                    if !group.trailing_comma() {
+                        self.prev = Some(token.kind);
+                        self.pending = Sep::Nothing;
+                        return;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.write(sep, newlines, &token.text);
+
+        self.pending = match token.kind {
+            TokenType::LCP => {
+                self.indent += 1;
+                self.open_groups.push(None);
+                // `{}` stays empty, unless there are comments in it
+                let empty = next == Some(TokenType::RCP)
+                    && self.tokens[idx + 1..]
+                        .iter()
+                        .take_while(|token| token.kind != TokenType::RCP)
+                        .all(|token| matches!(token.kind, TokenType::Whitespace(_)));
+                if empty {
+                    Sep::Nothing
+                } else {
+                    Sep::Newline
+                }
+            }
+            TokenType::LP => {
+                let group = self.groups.get(&idx).copied();
+                self.open_groups.push(group);
+                match group {
+                    Some(group) if group.multiline => {
+                        self.indent += 1;
+                        Sep::Newline
+                    }
+                    _ => Sep::Nothing,
+                }
+            }
+            TokenType::LB => {
+                self.open_groups.push(None);
+                Sep::Nothing
+            }
+            TokenType::RB => {
+                self.open_groups.pop();
+                // Compiler tags are on their own line
+                match next {
+                    Some(TokenType::Semi) | Some(TokenType::Comma) | Some(TokenType::RP) => {
+                        Sep::Pair
+                    }
+                    _ => Sep::Newline,
+                }
+            }
+            TokenType::RCP => match next {
+                Some(TokenType::Semi)
+                | Some(TokenType::Comma)
+                | Some(TokenType::RP)
+                | Some(TokenType::Dot) => Sep::Nothing,
+                Some(TokenType::Else) => Sep::Space,
+                _ => Sep::Newline,
+            },
+            TokenType::Semi => Sep::Newline,
+            TokenType::Comma if group.map_or(false, |group| group.multiline) => Sep::Newline,
+            // Arms of a `match` are written one per line
+            TokenType::Comma if matches!(self.open_groups.last(), Some(None)) => Sep::Newline,
+            // Prefix operator, i.e. `-x`
+            TokenType::Sub if !self.prev.map_or(false, ends_value) => Sep::Nothing,
+            TokenType::Not | TokenType::BitNot => Sep::Nothing,
+            _ => Sep::Pair,
+        };
+        self.prev = Some(token.kind);
+
+        if self.commas.contains(&idx) {
+            self.write(Sep::Nothing, 0, ",");
+            self.prev = Some(TokenType::Comma);
+            self.pending = Sep::Newline;
+        }
+    }
+
+    /// Space between the previous token and `next`
+    fn pair(&self, next: TokenType) -> Sep {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => return Sep::Nothing,
+        };
+
+        match (prev, next) {
+            (_, TokenType::Comma)
+            | (_, TokenType::Semi)
+            | (_, TokenType::Colon)
+            | (_, TokenType::DoubleColon)
+            | (_, TokenType::Dot)
+            | (_, TokenType::DoubleDot)
+            | (_, TokenType::DoubleDotEq)
+            | (_, TokenType::RP)
+            | (_, TokenType::RB)
+            | (TokenType::LP, _)
+            | (TokenType::LB, _)
+            | (TokenType::At, _)
+            | (TokenType::Dollar, _)
+            | (TokenType::DoubleColon, _)
+            | (TokenType::Dot, _)
+            | (TokenType::DoubleDot, _)
+            | (TokenType::DoubleDotEq, _)
+            // Function call, i.e. `print(x)`
+            | (TokenType::Identifier, TokenType::LP) => Sep::Nothing,
+            _ => Sep::Space,
+        }
+    }
+
+    fn write(&mut self, sep: Sep, newlines: usize, text: &str) {
+        match sep {
+            Sep::Newline => {
+                let len = self.out.trim_end_matches(' ').len();
+                self.out.truncate(len);
+                if !self.out.is_empty() {
+                    self.out.push('\n');
+                    // Keep one blank line where the source has some, but not at the start or end
+                    // of a block
+                    let opens = matches!(self.prev, Some(TokenType::LCP) | Some(TokenType::LP));
+                    let closes = matches!(text, "}" | ")");
+                    if newlines > 1 && !opens && !closes {
+                        self.out.push('\n');
+                    }
+                }
+            }
+            Sep::Space if !self.out.is_empty() && !self.out.ends_with('\n') => self.out.push(' '),
+            _ => {}
+        }
+
+        if self.out.ends_with('\n') {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+        }
+        self.out.push_str(text);
+    }
+}
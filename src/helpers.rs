@@ -54,7 +54,11 @@ pub fn get_high_priority<'a>(errors: Vec<(ErrorValue, ErrorLevel)>) -> Vec<Error
 
 pub fn error_or_other<T>(value: Result<T, Vec<ErrorValue>>, logger: Logger) -> T {
     match value {
-        Ok(val) => val,
+        Ok(val) => {
+            // Only warnings are left to show
+            logger.as_ref().borrow_mut().raise();
+            val
+        }
         Err(errors) => {
             for error in errors {
                 logger.as_ref().borrow_mut().error(error);
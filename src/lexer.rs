@@ -2,9 +2,14 @@ use crate::helpers;
 use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
 use crate::segmentation::{Grapheme, GraphemeIdxs};
 use crate::sourcemap::SourceMap;
+use crate::typecheck::annotation::Prim;
 
 use std::borrow::Cow;
+use std::collections::hash_map::{Entry, HashMap};
 use std::rc::Rc;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_security::{skeleton, MixedScript};
+use unicode_xid::UnicodeXID;
 
 const EOF: char = '\0';
 const EOF_STR: &'static str = "\0";
@@ -13,6 +18,7 @@ const EOF_STR: &'static str = "\0";
 /// Type of tokens
 pub enum TokenType {
     String,
+    Char,
     CodeValue,
 
     Def,
@@ -23,6 +29,7 @@ pub enum TokenType {
     Unit,
 
     Let,
+    Mut,
 
     As,
     Is,
@@ -39,8 +46,19 @@ pub enum TokenType {
     If,
     Else,
 
+    Loop,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Match,
+    /// Name of a loop, i.e. `'outer`
+    Label,
+
     Identifier,
     Number,
+    Float,
 
     Div,
     Mod,
@@ -54,6 +72,24 @@ pub enum TokenType {
     GE, // >= Greater than or equal to
     LE, // <= Less than or equal to
     EQ, // == Equal to
+    NE, // != Not equal to
+
+    And, // && Logical and
+    Or,  // || Logical or
+    Not, // ! Logical not
+
+    BitAnd, // & Bitwise and
+    BitOr,  // | Bitwise or
+    BitXor, // ^ Bitwise exclusive or
+    BitNot, // ~ Bitwise not
+    Shl,    // << Shift left
+    Shr,    // >> Shift right
+
+    AddEq, // += Add and assign
+    SubEq, // -= Subtract and assign
+    MulEq, // *= Multiply and assign
+    DivEq, // /= Divide and assign
+    ModEq, // %= Remainder and assign
 
     Arrow,
     FatArrow,
@@ -67,6 +103,8 @@ pub enum TokenType {
 
     Question,
     Dot,
+    DoubleDot,
+    DoubleDotEq,
     Equals,
     Colon,
     DoubleColon,
@@ -81,6 +119,7 @@ pub enum TokenType {
     Underscore,
 
     Unknown,
+    DocComment,
     LineComment(usize),
     BlockComment(usize),
     Whitespace(usize),
@@ -97,6 +136,7 @@ impl TokenType {
             TokenType::Return => "keyword `return`",
             TokenType::Yield => "keyword `yield`",
             TokenType::Let => "keyword `let`",
+            TokenType::Mut => "keyword `mut`",
             TokenType::Impl => "keyword `impl`",
             TokenType::Pattern => "keyword `pattern`",
             TokenType::Type => "keyword `type`",
@@ -105,6 +145,13 @@ impl TokenType {
             TokenType::Extern => "keyword `extern`",
             TokenType::If => "keyword `if`",
             TokenType::Else => "keyword `else`",
+            TokenType::Loop => "keyword `loop`",
+            TokenType::While => "keyword `while`",
+            TokenType::For => "keyword `for`",
+            TokenType::In => "keyword `in`",
+            TokenType::Break => "keyword `break`",
+            TokenType::Continue => "keyword `continue`",
+            TokenType::Match => "keyword `match`",
 
             TokenType::As => "operator `as`",
             TokenType::Is => "operator`is`",
@@ -120,6 +167,21 @@ impl TokenType {
             TokenType::GE => "operator `>=`",
             TokenType::LE => "operator `<=`",
             TokenType::EQ => "operator `==`",
+            TokenType::NE => "operator `!=`",
+            TokenType::And => "operator `&&`",
+            TokenType::Or => "operator `||`",
+            TokenType::Not => "operator `!`",
+            TokenType::BitAnd => "operator `&`",
+            TokenType::BitOr => "operator `|`",
+            TokenType::BitXor => "operator `^`",
+            TokenType::BitNot => "operator `~`",
+            TokenType::Shl => "operator `<<`",
+            TokenType::Shr => "operator `>>`",
+            TokenType::AddEq => "operator `+=`",
+            TokenType::SubEq => "operator `-=`",
+            TokenType::MulEq => "operator `*=`",
+            TokenType::DivEq => "operator `/=`",
+            TokenType::ModEq => "operator `%=`",
 
             TokenType::Dollar => "token `$`",
             TokenType::At => "token `@`",
@@ -138,6 +200,8 @@ impl TokenType {
 
             TokenType::Question => "token `?`",
             TokenType::Dot => "token `.`",
+            TokenType::DoubleDot => "token `..`",
+            TokenType::DoubleDotEq => "token `..=`",
             TokenType::Equals => "token `=`",
             TokenType::Colon => "token `:`",
             TokenType::Semi => "terminator `;`",
@@ -145,17 +209,44 @@ impl TokenType {
             TokenType::EOF => "end of file",
             TokenType::Underscore => "underscore",
 
+            TokenType::DocComment => "doc comment",
             TokenType::LineComment(_) => "line comment",
             TokenType::BlockComment(_) => "block comment",
             TokenType::Whitespace(_) => "whitespace",
 
             TokenType::String => "string literal",
+            TokenType::Char => "char literal",
+            TokenType::Label => "label",
             TokenType::CodeValue => "code value",
             TokenType::Identifier => "identifier",
             TokenType::Number => "number",
+            TokenType::Float => "float",
             TokenType::Unknown => "unknown token",
         }
     }
+
+    /// Tokens that don't change the meaning of the code, i.e. whitespace and comments
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Whitespace(_)
+                | TokenType::LineComment(_)
+                | TokenType::BlockComment(_)
+                | TokenType::DocComment
+        )
+    }
+
+    /// Operator applied by a compound assignment, i.e. `+` for `+=`
+    pub fn assign_operator(&self) -> Option<TokenType> {
+        match self {
+            TokenType::AddEq => Some(TokenType::Add),
+            TokenType::SubEq => Some(TokenType::Sub),
+            TokenType::MulEq => Some(TokenType::Mul),
+            TokenType::DivEq => Some(TokenType::Div),
+            TokenType::ModEq => Some(TokenType::Mod),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -172,13 +263,21 @@ impl Token {
                 "string literal `{}`",
                 sourcemap.borrow().get_segment(self.pos)
             ),
+            TokenType::Char => {
+                format!(
+                    "char literal `{}`",
+                    sourcemap.borrow().get_segment(self.pos)
+                )
+            }
             TokenType::CodeValue => {
                 format!("code value `{}`", sourcemap.borrow().get_segment(self.pos))
             }
             TokenType::Identifier => {
                 format!("identifier `{}`", sourcemap.borrow().get_segment(self.pos))
             }
+            TokenType::Label => format!("label `{}`", sourcemap.borrow().get_segment(self.pos)),
             TokenType::Number => format!("number `{}`", sourcemap.borrow().get_segment(self.pos)),
+            TokenType::Float => format!("float `{}`", sourcemap.borrow().get_segment(self.pos)),
 
             TokenType::Unknown => format!(
                 "unknown token `{}`",
@@ -189,6 +288,162 @@ impl Token {
     }
 }
 
+/// Invalid escape sequence, `start` and `end` are byte offsets into the string's contents
+pub struct EscapeError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Decode the escape sequences in the contents of a string, i.e. `\n`, `\"` or `\u{4e16}`
+pub fn unescape(contents: &str) -> Result<String, EscapeError> {
+    let mut value = String::with_capacity(contents.len());
+    let mut chars = contents.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        let error = |message: String, end: usize| EscapeError {
+            message,
+            start,
+            end,
+        };
+
+        let (idx, escape) = chars.next().ok_or_else(|| {
+            error(
+                "expected an escape character after `\\`".to_string(),
+                start + 1,
+            )
+        })?;
+        let mut end = idx + escape.len_utf8();
+
+        value.push(match escape {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            '`' => '`',
+            'u' => {
+                if chars.next_if(|&(_, c)| c == '{').is_none() {
+                    return Err(error("expected `{` after `\\u`".to_string(), end));
+                }
+
+                let mut digits = String::new();
+                loop {
+                    match chars.next() {
+                        Some((idx, '}')) => {
+                            end = idx + 1;
+                            break;
+                        }
+                        Some((idx, c)) => {
+                            digits.push(c);
+                            end = idx + c.len_utf8();
+                        }
+                        None => {
+                            return Err(error(
+                                "unterminated unicode escape, expected `}`".to_string(),
+                                end,
+                            ))
+                        }
+                    }
+                }
+
+                match u32::from_str_radix(&digits, 16) {
+                    Ok(code) if digits.len() <= 6 => {
+                        std::char::from_u32(code).ok_or_else(|| {
+                            error(format!("`{}` is not a unicode character", digits), end)
+                        })?
+                    }
+                    _ => {
+                        return Err(error(
+                            format!("invalid unicode escape `\\u{{{}}}`", digits),
+                            end,
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(error(
+                    format!("unknown escape sequence `\\{}`", escape),
+                    end,
+                ))
+            }
+        });
+    }
+
+    Ok(value)
+}
+
+/// Text of a doc comment without its markers, i.e. `--- Add two numbers` or
+/// `/** Add two numbers */` are both `Add two numbers`
+///
+/// The leading `*` of each line in a block doc comment is removed
+pub fn doc_text(comment: &str) -> String {
+    if let Some(text) = comment.strip_prefix("---") {
+        return text
+            .strip_prefix(' ')
+            .unwrap_or(text)
+            .trim_end()
+            .to_string();
+    }
+
+    let text = comment.strip_prefix("/**").unwrap_or(comment);
+    let text = text.strip_suffix("*/").unwrap_or(text);
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+/// Number literal split into its parts, i.e. `0xFF_u8` has radix 16, digits `FF` and suffix `u8`
+pub struct NumberParts<'a> {
+    pub radix: u32,
+    /// Digits without the base prefix and separators
+    pub digits: String,
+    pub suffix: &'a str,
+}
+
+impl<'a> NumberParts<'a> {
+    pub fn new(text: &'a str) -> NumberParts<'a> {
+        let radix = match text.get(..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => 10,
+        };
+        let text = if radix == 10 { text } else { &text[2..] };
+
+        // `f` is a hex digit, so hex literals cannot have a float suffix
+        let suffix_start = text
+            .find(|c| c == 'i' || c == 'u' || (c == 'f' && radix != 16))
+            .unwrap_or_else(|| text.len());
+
+        NumberParts {
+            radix,
+            digits: text[..suffix_start].replace('_', ""),
+            suffix: &text[suffix_start..],
+        }
+    }
+
+    /// Value of an integer literal, `None` if it does not even fit in 128 bits
+    pub fn int_value(&self) -> Option<u128> {
+        u128::from_str_radix(&self.digits, self.radix).ok()
+    }
+}
+
 impl<'a> PartialEq for Token {
     fn eq(&self, other: &Token) -> bool {
         self.pos.e == other.pos.e && self.pos.s == other.pos.s && self.token == other.token
@@ -226,16 +481,30 @@ pub struct Lexer {
     pub filename: usize,
     pub position: usize,
     pub current_token: Token,
+    /// Return whitespace and comments as tokens instead of skipping them
+    pub keep_trivia: bool,
 
     unicode_iter: std::iter::Peekable<GraphemeIdxs>,
 
     change_peek: bool,
     sourcemap: SourceMap,
+
+    /// Normalized identifiers and where they were first seen, to find confusable ones
+    identifiers: HashMap<String, helpers::Pos>,
+    warnings: Vec<ErrorValue>,
 }
 
-/// Check if ID is continue
+/// Check if ID is continue (UAX #31 `XID_Continue`)
 fn is_id_continue(c: char) -> bool {
-    ('a' <= c && c <= 'z') || ('A' <= c && c <= 'Z') || ('0' <= c && c <= '9') || c == '_'
+    UnicodeXID::is_xid_continue(c)
+}
+
+/// Normalize an identifier to NFC, so identifiers that look the same are equal
+pub fn normalize_identifier(name: &str) -> Cow<'_, str> {
+    match is_nfc_quick(name.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(name),
+        _ => Cow::Owned(name.nfc().collect()),
+    }
 }
 
 impl Lexer {
@@ -248,12 +517,20 @@ impl Lexer {
                 token: TokenType::EOF,
                 pos: helpers::Pos::new(0, 0, filename),
             },
+            keep_trivia: false,
             unicode_iter: GraphemeIdxs::new(Rc::clone(&sourcemap), filename).peekable(),
             change_peek: true,
             sourcemap,
+            identifiers: HashMap::new(),
+            warnings: Vec::new(),
         }
     }
 
+    /// Take the warnings found so far, i.e. confusable identifiers
+    pub fn take_warnings(&mut self) -> Vec<ErrorValue> {
+        std::mem::take(&mut self.warnings)
+    }
+
     /// Generate a unit test for the lexer
     pub fn get_unit_test(&mut self) {
         loop {
@@ -281,6 +558,14 @@ impl Lexer {
         }
     }
 
+    /// Get the character `n` characters ahead without moving position
+    fn nth_char(&mut self, n: usize) -> Grapheme {
+        match self.unicode_iter.clone().nth(n) {
+            Some(grapheme) => grapheme,
+            None => Grapheme::new(EOF_STR, 0), // EOF is 0 length
+        }
+    }
+
     /// Move position forward
     fn bump(&mut self) -> Grapheme {
         let c = match self.unicode_iter.next() {
@@ -295,39 +580,84 @@ impl Lexer {
     /// Get next token in input stream's type
     fn get_next_tok_type(&mut self) -> Result<(TokenType, usize), ErrorValue> {
         let first_char = self.bump();
-        let pos = self.position;
+        // One past the start of the token, the first grapheme may be several bytes long
+        let pos = self.position + 1 - first_char.len().max(1);
 
         Ok((
             match first_char.front {
                 '-' => match self.peek_char().front {
-                    '-' => {
+                    // `---` starts a doc comment, but `----` is a regular comment
+                    '-' if self.nth_char(1).front == '-' && self.nth_char(2).front != '-' => {
                         self.line_comment()?;
-                        self.bump(); // Eat the \n
-                        return self.get_next_tok_type();
+                        TokenType::DocComment
+                    }
+                    '-' => {
+                        let comment = self.line_comment()?;
+                        if !self.keep_trivia {
+                            self.bump(); // Eat the \n
+                            return self.get_next_tok_type();
+                        }
+                        comment
                     }
                     '>' => {
                         self.bump();
                         TokenType::Arrow
                     }
+                    '=' => {
+                        self.bump();
+                        TokenType::SubEq
+                    }
                     _ => TokenType::Sub,
                 },
 
                 c if is_whitespace(c) => {
-                    self.whitespace()?;
-                    return self.get_next_tok_type();
+                    let whitespace = self.whitespace()?;
+                    if !self.keep_trivia {
+                        return self.get_next_tok_type();
+                    }
+                    whitespace
                 }
 
-                c if self.is_id_start(c) => self.identifier()?,
+                c if self.is_id_start(c) && first_char.chars().skip(1).all(is_id_continue) => {
+                    self.identifier(pos - 1)?
+                }
 
-                '0'..='9' => self.number()?,
+                c @ '0'..='9' => self.number(c)?,
 
-                '*' => TokenType::Mul,
-                '+' => TokenType::Add,
+                '*' => match self.peek_char().front {
+                    '=' => {
+                        self.bump();
+                        TokenType::MulEq
+                    }
+                    _ => TokenType::Mul,
+                },
+                '+' => match self.peek_char().front {
+                    '=' => {
+                        self.bump();
+                        TokenType::AddEq
+                    }
+                    _ => TokenType::Add,
+                },
                 '/' => match self.peek_char().front {
-                    '*' => {
+                    // `/**` starts a doc comment, but `/***` and `/**/` are regular comments
+                    '*' if self.nth_char(1).front == '*'
+                        && !matches!(self.nth_char(2).front, '*' | '/') =>
+                    {
                         self.bump();
                         self.block_comment()?;
-                        return self.get_next_tok_type();
+                        TokenType::DocComment
+                    }
+                    '*' => {
+                        self.bump();
+                        let comment = self.block_comment()?;
+                        if !self.keep_trivia {
+                            return self.get_next_tok_type();
+                        }
+                        comment
+                    }
+                    '=' => {
+                        self.bump();
+                        TokenType::DivEq
                     }
                     _ => TokenType::Div,
                 },
@@ -337,6 +667,10 @@ impl Lexer {
                         self.bump();
                         TokenType::DMod
                     }
+                    '=' => {
+                        self.bump();
+                        TokenType::ModEq
+                    }
                     _ => TokenType::Mod,
                 },
                 '$' => TokenType::Dollar,
@@ -345,6 +679,10 @@ impl Lexer {
                         self.bump();
                         TokenType::GE
                     }
+                    '>' => {
+                        self.bump();
+                        TokenType::Shr
+                    }
                     _ => TokenType::GT,
                 },
                 '<' => match self.peek_char().front {
@@ -352,10 +690,16 @@ impl Lexer {
                         self.bump();
                         TokenType::LE
                     }
+                    '<' => {
+                        self.bump();
+                        TokenType::Shl
+                    }
                     _ => TokenType::LT,
                 },
 
                 '`' => self.string('`', "code value")?,
+                '\'' if self.is_label() => self.label(),
+                '\'' => self.char_literal()?,
 
                 '(' => TokenType::LP,
                 ')' => TokenType::RP,
@@ -364,8 +708,43 @@ impl Lexer {
                 '[' => TokenType::LB,
                 ']' => TokenType::RB,
 
-                '.' => TokenType::Dot,
+                '.' => match self.peek_char().front {
+                    '.' => {
+                        self.bump();
+                        match self.peek_char().front {
+                            '=' => {
+                                self.bump();
+                                TokenType::DoubleDotEq
+                            }
+                            _ => TokenType::DoubleDot,
+                        }
+                    }
+                    _ => TokenType::Dot,
+                },
                 ';' => TokenType::Semi,
+                '!' => match self.peek_char().front {
+                    '=' => {
+                        self.bump();
+                        TokenType::NE
+                    }
+                    _ => TokenType::Not,
+                },
+                '&' => match self.peek_char().front {
+                    '&' => {
+                        self.bump();
+                        TokenType::And
+                    }
+                    _ => TokenType::BitAnd,
+                },
+                '|' => match self.peek_char().front {
+                    '|' => {
+                        self.bump();
+                        TokenType::Or
+                    }
+                    _ => TokenType::BitOr,
+                },
+                '^' => TokenType::BitXor,
+                '~' => TokenType::BitNot,
                 '=' => match self.peek_char().front {
                     '=' => {
                         self.bump();
@@ -390,11 +769,14 @@ impl Lexer {
                 '@' => TokenType::At,
 
                 '"' => self.string('"', "string")?,
-                EOF => TokenType::EOF,
+                EOF => {
+                    self.check_confusables();
+                    TokenType::EOF
+                }
 
                 unknown => {
                     let pos = helpers::Pos {
-                        s: self.position - 1,
+                        s: pos - 1,
                         e: self.position,
                         filename_id: self.filename,
                     };
@@ -441,47 +823,222 @@ impl Lexer {
         Ok(self.current_token)
     }
 
-    /// Tokenize integer
-    fn number(&mut self) -> Result<TokenType, ErrorValue> {
-        let position = self.position;
+    /// Tokenize integer or float, i.e. `12`, `0xFF`, `1_000u32`, `1.5` or `2e10`
+    fn number(&mut self, first: char) -> Result<TokenType, ErrorValue> {
+        let start = self.position - 1;
+        let mut token = TokenType::Number;
+
+        let radix = match (first, self.peek_char().front) {
+            ('0', 'x') => 16,
+            ('0', 'o') => 8,
+            ('0', 'b') => 2,
+            _ => 10,
+        };
+
+        if radix != 10 {
+            self.bump();
+            if self.eat_while(|c| c.is_digit(radix) || c == '_') == 0 {
+                return Err(self.literal_error(
+                    "missing digits after the integer base prefix".to_string(),
+                    start,
+                ));
+            }
+        } else {
+            self.eat_while(|c| c.is_ascii_digit() || c == '_');
+
+            // The dot must be followed by a digit, `1.` is not a float
+            if self.peek_char().front == '.' && self.nth_char(1).front.is_ascii_digit() {
+                self.bump();
+                self.eat_while(|c| c.is_ascii_digit() || c == '_');
+                token = TokenType::Float;
+            }
+
+            if let 'e' | 'E' = self.peek_char().front {
+                let sign = matches!(self.nth_char(1).front, '+' | '-') as usize;
+                if self.nth_char(1 + sign).front.is_ascii_digit() {
+                    for _ in 0..=sign {
+                        self.bump();
+                    }
+                    self.eat_while(|c| c.is_ascii_digit() || c == '_');
+                    token = TokenType::Float;
+                }
+            }
+        }
+
+        // Type suffix, i.e. `u8` or `f32`
+        let suffix_start = self.position;
+        if self.eat_while(is_id_continue) == 0 {
+            return Ok(token);
+        }
+
+        let suffix = self
+            .sourcemap
+            .borrow()
+            .get_segment(helpers::Pos::new(
+                suffix_start,
+                self.position,
+                self.filename,
+            ))
+            .to_string();
+
+        match Prim::from_name(&suffix) {
+            Some(prim) if prim.is_float() && radix == 10 => Ok(TokenType::Float),
+            Some(prim) if prim.is_integer() && token == TokenType::Number => Ok(token),
+            _ => Err(self.literal_error(
+                format!("invalid suffix `{}` for number literal", suffix),
+                suffix_start,
+            )),
+        }
+    }
 
-        let num = self.eat_while(|c| '0' <= c && c <= '9');
-        Ok(TokenType::Number)
+    fn literal_error(&self, message: String, start: usize) -> ErrorValue {
+        let pos = helpers::Pos::new(start, self.position, self.filename);
+        ErrorValue::new(
+            message,
+            ErrorType::InvalidLiteral,
+            pos,
+            ErrorDisplayType::Error,
+            vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
+        )
     }
 
-    /// Get start of ID (excluding number)
+    /// Get start of ID (UAX #31 `XID_Start` or `_`, excluding number)
     fn is_id_start(&mut self, c: char) -> bool {
-        ('a' <= c && c <= 'z') || ('A' <= c && c <= 'Z') || c == '_'
+        UnicodeXID::is_xid_start(c) || c == '_'
     }
 
-    /// Tokenize identifier and keywords
-    fn identifier(&mut self) -> Result<TokenType, ErrorValue> {
-        let position = self.position;
-        let id = self.eat_while(is_id_continue);
-
-        match self.sourcemap.borrow().get_segment(helpers::Pos::new(
-            position - 1,
-            position + id,
-            self.filename,
-        )) {
-            "def" => Ok(TokenType::Def),
-            "let" => Ok(TokenType::Let),
-            "impl" => Ok(TokenType::Impl),
-            "pattern" => Ok(TokenType::Pattern),
-            "return" => Ok(TokenType::Return),
-            "yield" => Ok(TokenType::Yield),
-            "as" => Ok(TokenType::As),
-            "type" => Ok(TokenType::Type),
-            "pub" => Ok(TokenType::Public),
-            "unit" => Ok(TokenType::Unit),
-            "true" => Ok(TokenType::True),
-            "false" => Ok(TokenType::False),
-            "extern" => Ok(TokenType::Extern),
-            "if" => Ok(TokenType::If),
-            "else" => Ok(TokenType::Else),
-            "_" => Ok(TokenType::Underscore),
-            "is" => Ok(TokenType::Is),
-            _ => Ok(TokenType::Identifier),
+    /// Tokenize identifier and keywords, the identifier starts at `start`
+    fn identifier(&mut self, start: usize) -> Result<TokenType, ErrorValue> {
+        // Whole graphemes, so combining marks stay with the character they modify
+        while self.peek_char().chars().all(is_id_continue) {
+            self.bump();
+        }
+
+        let pos = helpers::Pos::new(start, self.position, self.filename);
+        let token = match self.sourcemap.borrow().get_segment(pos) {
+            "def" => TokenType::Def,
+            "let" => TokenType::Let,
+            "mut" => TokenType::Mut,
+            "impl" => TokenType::Impl,
+            "pattern" => TokenType::Pattern,
+            "return" => TokenType::Return,
+            "yield" => TokenType::Yield,
+            "as" => TokenType::As,
+            "type" => TokenType::Type,
+            "pub" => TokenType::Public,
+            "unit" => TokenType::Unit,
+            "true" => TokenType::True,
+            "false" => TokenType::False,
+            "extern" => TokenType::Extern,
+            "if" => TokenType::If,
+            "else" => TokenType::Else,
+            "loop" => TokenType::Loop,
+            "while" => TokenType::While,
+            "for" => TokenType::For,
+            "in" => TokenType::In,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
+            "match" => TokenType::Match,
+            "_" => TokenType::Underscore,
+            "is" => TokenType::Is,
+            _ => TokenType::Identifier,
+        };
+
+        if token == TokenType::Identifier {
+            self.check_identifier(pos);
+        }
+        Ok(token)
+    }
+
+    /// Whether the quote that was just eaten starts a label, i.e. `'outer:`
+    ///
+    /// It is a char if the name is followed by a quote, i.e. `'a'`, or if nothing follows it on
+    /// the line, i.e. the unterminated `'a`
+    fn is_label(&mut self) -> bool {
+        let first = self.peek_char();
+        if !self.is_id_start(first.front) || !first.chars().skip(1).all(is_id_continue) {
+            return false;
+        }
+
+        let mut n = 1;
+        while self.nth_char(n).chars().all(is_id_continue) {
+            n += 1;
+        }
+        !matches!(self.nth_char(n).front, '\'' | '\n' | EOF)
+    }
+
+    /// Tokenize a label, the quote has been eaten
+    fn label(&mut self) -> TokenType {
+        while self.peek_char().chars().all(is_id_continue) {
+            self.bump();
+        }
+        TokenType::Label
+    }
+
+    /// Warn if the identifier mixes scripts, and remember it to find confusable identifiers
+    fn check_identifier(&mut self, pos: helpers::Pos) {
+        let sourcemap = Rc::clone(&self.sourcemap);
+        let sourcemap = sourcemap.borrow();
+        let name = normalize_identifier(sourcemap.get_segment(pos));
+
+        if !name.is_ascii() && !name.is_single_script() {
+            self.warnings.push(ErrorValue::new(
+                format!(
+                    "identifier `{}` mixes characters from different scripts",
+                    name
+                ),
+                ErrorType::MixedScriptIdentifier,
+                pos,
+                ErrorDisplayType::Warning,
+                vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Warning)],
+            ));
+        }
+
+        if !self.identifiers.contains_key(name.as_ref()) {
+            self.identifiers.insert(name.into_owned(), pos);
+        }
+    }
+
+    /// Warn about different identifiers that look the same, i.e. `a` and the cyrillic `а`
+    ///
+    /// Only identifiers with a non-ASCII character are reported, as ASCII ones like `l` and `I`
+    /// can be told apart by anyone reading the code
+    fn check_confusables(&mut self) {
+        if self.identifiers.keys().all(|name| name.is_ascii()) {
+            return;
+        }
+
+        let mut identifiers = self.identifiers.drain().collect::<Vec<_>>();
+        identifiers.sort_by_key(|(_, pos)| pos.s);
+
+        let mut skeletons: HashMap<String, (String, helpers::Pos)> = HashMap::new();
+        for (name, pos) in identifiers {
+            match skeletons.entry(skeleton(&name).collect()) {
+                Entry::Occupied(entry) => {
+                    let (other, other_pos) = entry.get();
+                    if name.is_ascii() && other.is_ascii() {
+                        continue;
+                    }
+
+                    self.warnings.push(ErrorValue::new(
+                        format!("identifier `{}` is confusable with `{}`", name, other),
+                        ErrorType::ConfusableIdentifier,
+                        pos,
+                        ErrorDisplayType::Warning,
+                        vec![
+                            ErrorAnnotation::new(None, pos, ErrorDisplayType::Warning),
+                            ErrorAnnotation::new(
+                                Some(format!("`{}` is used here", other)),
+                                *other_pos,
+                                ErrorDisplayType::Info,
+                            ),
+                        ],
+                    ));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((name, pos));
+                }
+            }
         }
     }
 
@@ -493,16 +1050,14 @@ impl Lexer {
         while c != EOF {
             match c {
                 _ if c == marker => {
+                    self.unescape_contents(pos)?;
                     return Ok(TokenType::String);
                 }
-                val => {
-                    let first = self.peek_char().front;
-                    if (first == '\\' || first == marker) && val == '\\' {
-                        // Bump again to skip escaped character
-                        self.bump();
-                        self.bump();
-                    }
+                '\\' => {
+                    // Skip the escaped character, escapes are checked once the string ends
+                    self.bump();
                 }
+                _ => {}
             }
             c = self.bump().front;
         }
@@ -526,6 +1081,80 @@ impl Lexer {
         ))
     }
 
+    /// Decode the contents of the string or char that start at `start` and end before its closing
+    /// marker
+    fn unescape_contents(&self, start: usize) -> Result<String, ErrorValue> {
+        let contents = self
+            .sourcemap
+            .borrow()
+            .get_segment(helpers::Pos::new(start, self.position - 1, self.filename))
+            .to_string();
+
+        unescape(&contents).map_err(|err| {
+            let pos = helpers::Pos::new(start + err.start, start + err.end, self.filename);
+            ErrorValue::new(
+                err.message,
+                ErrorType::InvalidEscape,
+                pos,
+                ErrorDisplayType::Error,
+                vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
+            )
+        })
+    }
+
+    /// Tokenize char literal, i.e. `'a'`, `'\n'` or `'\u{1F30A}'`
+    ///
+    /// A char holds a single unicode scalar value, so a grapheme made of several code points,
+    /// i.e. an `e` followed by a combining accent, is rejected
+    fn char_literal(&mut self) -> Result<TokenType, ErrorValue> {
+        let start = self.position;
+        let mut graphemes = 0;
+        let mut last = None;
+
+        loop {
+            let grapheme = self.bump();
+            match grapheme.front {
+                '\'' => break,
+                EOF | '\n' => {
+                    let pos = helpers::Pos::new(start - 1, self.position - 1, self.filename);
+                    return Err(ErrorValue::new(
+                        "Unterminated char literal".to_string(),
+                        ErrorType::UnterminatedString,
+                        pos,
+                        ErrorDisplayType::Error,
+                        vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
+                    ));
+                }
+                '\\' => {
+                    // Skip the escaped character, escapes are checked once the literal ends
+                    self.bump();
+                }
+                _ => {}
+            }
+            graphemes += 1;
+            last = Some(grapheme);
+        }
+
+        let value = self.unescape_contents(start)?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next(), last) {
+            (Some(_), None, _) => Ok(TokenType::Char),
+            (None, _, _) => Err(self.literal_error("empty char literal".to_string(), start - 1)),
+            (_, _, Some(grapheme)) if graphemes == 1 => Err(self.literal_error(
+                format!(
+                    "char literal must be a single code point, but `{}` is made of {} code points",
+                    grapheme,
+                    grapheme.char_count()
+                ),
+                start - 1,
+            )),
+            _ => Err(self.literal_error(
+                "char literal must contain exactly one character".to_string(),
+                start - 1,
+            )),
+        }
+    }
+
     /// Tokenize block comment
     fn block_comment(&mut self) -> Result<TokenType, ErrorValue> {
         let position = self.position;
@@ -7,6 +7,8 @@ extern crate lazy_static;
 pub mod sourcemap;
 
 pub mod codegen;
+pub mod doc;
+pub mod formatter;
 pub mod helpers;
 pub mod lexer;
 pub mod logger;
@@ -29,6 +29,10 @@ pub enum ErrorType {
     UnexpectedToken,
     UnterminatedString,
     UnknownCharacter,
+    InvalidLiteral,
+    InvalidEscape,
+    MixedScriptIdentifier,
+    ConfusableIdentifier,
 
     Syntax,
     UndefinedSyntax,
@@ -38,11 +42,18 @@ pub enum ErrorType {
     UndefinedSymbol,
     TypeMismatch,
     TypeCast,
+    LiteralOutOfRange,
 
     PossibleUninitVal,
+    OutsideOfLoop,
+    ImmutableAssign,
+    NonExhaustive,
+    UnreachablePattern,
     Infer,
     Visibility,
     Import,
+
+    Unsupported,
 }
 
 impl ErrorType {
@@ -52,16 +63,26 @@ impl ErrorType {
             ErrorType::UnexpectedToken => "unexpected_token",
             ErrorType::UnterminatedString => "unterminated_string",
             ErrorType::UnknownCharacter => "unknown_character",
+            ErrorType::InvalidLiteral => "invalid_literal",
+            ErrorType::InvalidEscape => "invalid_escape",
+            ErrorType::MixedScriptIdentifier => "mixed_script_identifier",
+            ErrorType::ConfusableIdentifier => "confusable_identifier",
             ErrorType::UndefinedSyntax => "undefined_syntax",
             ErrorType::SyntaxType => "syntax_type",
             ErrorType::UndefinedType => "undefined_type",
             ErrorType::TypeMismatch => "type_mismatch",
             ErrorType::UndefinedSymbol => "undefined_symbol",
             ErrorType::TypeCast => "type_cast",
+            ErrorType::LiteralOutOfRange => "literal_out_of_range",
             ErrorType::PossibleUninitVal => "possible_uninitialized",
+            ErrorType::OutsideOfLoop => "outside_of_loop",
+            ErrorType::ImmutableAssign => "immutable_assign",
+            ErrorType::NonExhaustive => "non_exhaustive",
+            ErrorType::UnreachablePattern => "unreachable_pattern",
             ErrorType::Visibility => "visibility",
             ErrorType::Import => "import",
             ErrorType::Infer => "infer",
+            ErrorType::Unsupported => "unsupported",
         }
     }
 }
@@ -50,6 +50,11 @@ impl LoggerInner {
         self.errors.append(&mut errors);
     }
 
+    /// Errors and warnings waiting to be raised
+    pub fn errors(&self) -> &[ErrorValue] {
+        &self.errors
+    }
+
     pub fn log(&self, logged_val: String) {
         println!(
             "{}> {}{}{}",
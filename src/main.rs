@@ -8,6 +8,8 @@ extern crate lazy_static;
 pub mod sourcemap;
 
 pub mod codegen;
+pub mod doc;
+pub mod formatter;
 pub mod helpers;
 pub mod lexer;
 pub mod logger;
@@ -23,7 +25,7 @@ pub mod typecheck;
 #[macro_use]
 extern crate clap;
 
-use clap::App;
+use clap::{App, ArgMatches};
 use inkwell::context::Context;
 
 use logger::{Color, Font};
@@ -60,23 +62,21 @@ fn main() {
 
     let context = Context::create();
 
-    let read_file_start = Instant::now();
-    let (source, filename) = if matches.is_present("entry") {
-        let filename = paths::process_str(matches.value_of("entry").unwrap());
-        (paths::read_file(filename.as_path()), filename)
-    } else {
-        (matches.value_of("code").unwrap().to_string(), path::PathBuf::from("<string>.fl"))
-    };
-
-    // Load prelude
-    let mut prelude_path: path::PathBuf = helpers::CORE_LOC.to_owned();
-    prelude_path.pop();
-    prelude_path.pop();
-    prelude_path.push("prelude.fl");
+    if let Some(fmt_matches) = matches.subcommand_matches("fmt") {
+        let mut master = master::Master::new(&context, matches.is_present("verbose"));
+        let check = fmt_matches.is_present("check");
+        // Format every file, even after finding one that is not formatted
+        let formatted = fmt_matches
+            .values_of("files")
+            .unwrap()
+            .map(|file| master.format_file(paths::process_str(file), check))
+            .fold(true, |formatted, file_formatted| formatted && file_formatted);
+        process::exit(if formatted { 0 } else { 1 });
+    }
 
-    let mut contents = paths::read_file(&prelude_path);
-    // Append prelude into contents
-    contents += &source[..];
+    let read_file_start = Instant::now();
+    let doc_matches = matches.subcommand_matches("doc");
+    let (contents, filename) = read_source(doc_matches.unwrap_or(&matches));
 
     let mut master = master::Master::new(&context, matches.is_present("verbose"));
     master.logger.borrow().log_verbose(&|| {
@@ -86,15 +86,38 @@ fn main() {
         )
     }); // Lazily run it so no impact on performance
 
-    master.generate_file(
-        filename,
-        contents,
-        path::Path::new("out.ll"),
-        path::Path::new("out.o"),
-    );
+    if let Some(doc_matches) = doc_matches {
+        master.document_file(
+            filename,
+            contents,
+            path::Path::new(doc_matches.value_of("output").unwrap_or("doc")),
+        );
+    } else {
+        master.generate_file(
+            filename,
+            contents,
+            path::Path::new("out.ll"),
+            path::Path::new("out.o"),
+        );
+    }
 
     master.logger.borrow().log(format!(
         "{}: All Done",
         helpers::display_duration(master_start.elapsed())
     ));
 }
+
+/// Declarations of the core functions every program can use, i.e. `core::fmt::print_long`
+const PRELUDE: &str = include_str!("prelude.fl");
+
+/// Read the entry file or the input string, with the prelude prepended
+fn read_source(matches: &ArgMatches<'_>) -> (String, path::PathBuf) {
+    let (source, filename) = if matches.is_present("entry") {
+        let filename = paths::process_str(matches.value_of("entry").unwrap());
+        (paths::read_file(filename.as_path()), filename)
+    } else {
+        (matches.value_of("code").unwrap().to_string(), path::PathBuf::from("<string>.fl"))
+    };
+
+    (PRELUDE.to_string() + &source[..], filename)
+}
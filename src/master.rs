@@ -1,8 +1,11 @@
 use crate::codegen::CodeGenModule;
+use crate::doc::Documentation;
+use crate::formatter;
 use crate::helpers;
 use crate::logger::{Color, Font, Logger, LoggerInner};
 use crate::paths;
 use crate::sourcemap::{SourceMap, SourceMapInner};
+use crate::typecheck::TypeCheckModule;
 
 use std::collections::HashMap;
 use std::ffi::OsStr;
@@ -80,6 +83,71 @@ impl<'a> Master<'a> {
         self.link_objs(filename_id);
     }
 
+    /// Typecheck a file and write the documentation of its units to `output_dir`
+    pub fn document_file(
+        &mut self,
+        filename: path::PathBuf,
+        contents: String,
+        output_dir: &path::Path,
+    ) {
+        let filename_id = insert_file!(self.sourcemap, filename, contents);
+
+        let mut typecheck = TypeCheckModule::new(
+            filename_id,
+            Rc::clone(&self.logger),
+            Rc::clone(&self.sourcemap),
+        );
+        let typed_ast = helpers::error_or_other(typecheck.type_check(), Rc::clone(&self.logger));
+
+        let doc_start = Instant::now();
+        if let Err(e) = Documentation::new(&typed_ast).write(output_dir) {
+            paths::file_error(e, output_dir.display());
+        }
+
+        self.logger.borrow().log_verbose(&|| {
+            format!(
+                "{}: Documentation written",
+                helpers::display_duration(doc_start.elapsed())
+            )
+        });
+    }
+
+    /// Format a file in place, with `check` the file is left as it is and `false` is returned
+    /// if it is not formatted
+    pub fn format_file(&mut self, filename: path::PathBuf, check: bool) -> bool {
+        let contents = paths::read_file(&filename);
+        let filename_id = insert_file!(self.sourcemap, filename.clone(), contents.clone());
+
+        let formatted = helpers::error_or_other(
+            formatter::format_file(
+                filename_id,
+                Rc::clone(&self.logger),
+                Rc::clone(&self.sourcemap),
+            ),
+            Rc::clone(&self.logger),
+        );
+
+        if formatted == contents {
+            return true;
+        }
+
+        if check {
+            eprintln!(
+                "{}{}Not formatted{}: {}",
+                Color::Red,
+                Font::Bold,
+                Font::Reset,
+                filename.display()
+            );
+            return false;
+        }
+
+        if let Err(e) = fs::write(&filename, formatted) {
+            paths::file_error(e, filename.display());
+        }
+        true
+    }
+
     fn init_passes(&self) -> PassManager<module::Module<'a>> {
         let fpm: PassManager<module::Module<'_>> = PassManager::create(());
 
@@ -1,16 +1,22 @@
-use super::{MirExpr, MirExprEnum, MirStmt, MirType};
+use super::*;
 
-use crate::logger::ErrorValue;
+use crate::helpers;
+use crate::lexer;
+use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
+use crate::parser::ast::LiteralType;
+use crate::sourcemap::SourceMap;
 use crate::typecheck::annotation::*;
 
-pub fn lower_to_mir(typed_ast: Vec<TypedStmt>) -> Result<Vec<MirStmt>, Vec<ErrorValue>> {
+pub fn lower_to_mir(
+    typed_ast: Vec<TypedStmt>,
+    sourcemap: SourceMap,
+) -> Result<Vec<MirStmt>, Vec<ErrorValue>> {
     let mut mir = Vec::with_capacity(typed_ast.len());
     let mut errors = Vec::new();
 
     for typed_stmt in typed_ast.into_iter() {
-        match typed_stmt.into_mir() {
-            Ok(mir_stmt) => mir.push(mir_stmt),
-            Err(e) => errors.push(e),
+        if let Err(e) = typed_stmt.into_mir(&mut mir, &sourcemap) {
+            errors.push(e);
         }
     }
 
@@ -21,44 +27,492 @@ pub fn lower_to_mir(typed_ast: Vec<TypedStmt>) -> Result<Vec<MirStmt>, Vec<Error
     }
 }
 
+fn unsupported(name: &str, pos: helpers::Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("{} is not supported yet", name),
+        ErrorType::Unsupported,
+        pos,
+        ErrorDisplayType::Error,
+        vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
+    )
+}
+
 impl TypedStmt {
-    fn into_mir(self) -> Result<MirStmt, ErrorValue> {
+    /// Lower into `mir`, units are flattened as their symbols are already prefixed, and type
+    /// aliases are already resolved
+    fn into_mir(self, mir: &mut Vec<MirStmt>, sourcemap: &SourceMap) -> Result<(), ErrorValue> {
         match self.stmt {
-            TypedStmtEnum::Expression(expr) => Ok(MirStmt::Expression(expr.into_mir()?)),
-            _ => unimplemented!(),
+            TypedStmtEnum::Expression(expr) => {
+                mir.push(MirStmt::Expression(expr.into_mir(sourcemap)?))
+            }
+            TypedStmtEnum::VariableDeclaration(declaration) => {
+                mir.push(MirStmt::VariableDeclaration(VariableDeclaration {
+                    binding: declaration.binder.into_mir()?,
+                    is_extern: declaration.is_extern,
+                    pos: self.pos,
+                }))
+            }
+            TypedStmtEnum::Unit(unit) => {
+                for stmt in unit.stmts {
+                    stmt.into_mir(mir, sourcemap)?;
+                }
+            }
+            TypedStmtEnum::TypeAssign(_) => {}
+            TypedStmtEnum::Tag(_) => mir.push(MirStmt::Tag(MirTag {})),
+        }
+        Ok(())
+    }
+}
+
+impl TypedBinder {
+    fn into_mir(self) -> Result<Binding, ErrorValue> {
+        match self.name {
+            Some(name) => Ok(Binding {
+                name,
+                ty: self.ty.into_mir()?,
+            }),
+            None => Err(unsupported("anonymous binding", self.pos)),
+        }
+    }
+}
+
+impl TypedBlock {
+    fn into_mir(self, pos: helpers::Pos, sourcemap: &SourceMap) -> Result<Block, ErrorValue> {
+        let returns = self.diverges();
+
+        let mut nodes = Vec::with_capacity(self.stmts.len());
+        for stmt in self.stmts {
+            stmt.into_mir(&mut nodes, sourcemap)?;
+        }
+
+        Ok(Block {
+            nodes,
+            metadata: BlockMetadata { returns },
+            pos,
+        })
+    }
+}
+
+impl TypedConditional {
+    /// Else-if branches are nested in the else branch of the previous branch
+    fn into_mir(
+        self,
+        ty: MirType,
+        pos: helpers::Pos,
+        sourcemap: &SourceMap,
+    ) -> Result<Conditional, ErrorValue> {
+        let mut else_b = match self.else_branch {
+            Some(else_branch) => ElseBranch {
+                block: else_branch.block.into_mir(else_branch.pos, sourcemap)?,
+                pos: else_branch.pos,
+            },
+            None => ElseBranch {
+                block: Block {
+                    nodes: Vec::new(),
+                    metadata: BlockMetadata { returns: false },
+                    pos,
+                },
+                pos,
+            },
+        };
+
+        let mut if_branches = self.if_branches.into_iter().rev();
+        loop {
+            let branch = if_branches.next().unwrap();
+            let conditional = Conditional {
+                if_b: IfBranch {
+                    cond: branch.cond.into_mir(sourcemap)?,
+                    block: branch.block.into_mir(branch.pos, sourcemap)?,
+                    pos: branch.pos,
+                },
+                else_b,
+                pos,
+            };
+
+            if if_branches.len() == 0 {
+                return Ok(conditional);
+            }
+
+            // The nested conditional is the value of the else branch
+            let branch_pos = conditional.if_b.pos;
+            let returns = conditional.if_b.block.metadata.returns
+                && conditional.else_b.block.metadata.returns;
+            else_b = ElseBranch {
+                block: Block {
+                    nodes: vec![MirStmt::Expression(MirExpr {
+                        value: MirExprEnum::Yield(Box::new(MirExpr {
+                            value: MirExprEnum::Conditional(Box::new(conditional)),
+                            pos: branch_pos,
+                            ty: ty.clone(),
+                        })),
+                        pos: branch_pos,
+                        ty: MirType::Never(branch_pos),
+                    })],
+                    metadata: BlockMetadata { returns },
+                    pos: branch_pos,
+                },
+                pos: branch_pos,
+            };
         }
     }
 }
 
+impl TypedLoop {
+    fn into_mir(self, pos: helpers::Pos, sourcemap: &SourceMap) -> Result<Loop, ErrorValue> {
+        let kind = match self.kind {
+            TypedLoopKind::Loop => LoopKind::Loop,
+            TypedLoopKind::While(cond) => LoopKind::While(cond.into_mir(sourcemap)?),
+            TypedLoopKind::For { binder, range } => LoopKind::For {
+                name: binder.into_mir()?.name,
+                range: range.into_mir(sourcemap)?,
+            },
+        };
+
+        Ok(Loop {
+            id: self.id,
+            kind,
+            block_ty: self.block.ty.clone().into_mir()?,
+            block: self.block.into_mir(pos, sourcemap)?,
+            pos,
+        })
+    }
+}
+
+impl TypedPattern {
+    fn into_mir(self, sourcemap: &SourceMap) -> Result<Pattern, ErrorValue> {
+        let kind = match self.kind {
+            TypedPatternKind::Wildcard => PatternKind::Wildcard,
+            TypedPatternKind::Literal(literal) => {
+                PatternKind::Literal(literal.into_mir(sourcemap)?)
+            }
+            TypedPatternKind::Binding(binder) => PatternKind::Binding(binder.into_mir()?.name),
+            TypedPatternKind::Tuple(patterns) => PatternKind::Tuple(
+                patterns
+                    .into_iter()
+                    .map(|pattern| pattern.into_mir(sourcemap))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            TypedPatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => PatternKind::Range {
+                start: start.into_mir(sourcemap)?,
+                end: end.into_mir(sourcemap)?,
+                inclusive,
+            },
+        };
+
+        Ok(Pattern {
+            kind,
+            ty: self.ty.into_mir()?,
+            pos: self.pos,
+        })
+    }
+}
+
+impl TypedMatch {
+    fn into_mir(self, pos: helpers::Pos, sourcemap: &SourceMap) -> Result<Match, ErrorValue> {
+        let arms = self
+            .arms
+            .into_iter()
+            .map(|arm| {
+                Ok(MatchArm {
+                    pattern: arm.pattern.into_mir(sourcemap)?,
+                    guard: match arm.guard {
+                        Some(guard) => Some(guard.into_mir(sourcemap)?),
+                        None => None,
+                    },
+                    expr: arm.expr.into_mir(sourcemap)?,
+                    pos: arm.pos,
+                })
+            })
+            .collect::<Result<Vec<_>, ErrorValue>>()?;
+
+        Ok(Match {
+            expr: self.expr.into_mir(sourcemap)?,
+            arms,
+            pos,
+        })
+    }
+}
+
+impl TypedLiteral {
+    /// Numbers and chars are written as plain decimals and strings are decoded, integers were
+    /// checked to fit in their type when substituted
+    fn into_mir(self, sourcemap: &SourceMap) -> Result<Literal, ErrorValue> {
+        let pos = self.value.pos;
+        let text = get_segment!(sourcemap, pos).to_string();
+        let ty = self.ty.into_mir()?;
+
+        let value = match (self.value.literal_type, &ty) {
+            (LiteralType::Number, MirType::Primitive(_, _)) => lexer::NumberParts::new(&text)
+                .int_value()
+                .unwrap()
+                .to_string(),
+            (LiteralType::Float, _) => lexer::NumberParts::new(&text).digits,
+            (LiteralType::String, _) => self.value.value.unwrap_or_default(),
+            (LiteralType::Char, _) => {
+                let value = self.value.value.unwrap_or_default();
+                (value.chars().next().unwrap_or_default() as u32).to_string()
+            }
+            _ => text,
+        };
+
+        Ok(Literal { value, ty, pos })
+    }
+}
+
+impl TypedFunction {
+    fn into_mir(self, sourcemap: &SourceMap) -> Result<FunctionExpr, ErrorValue> {
+        let pos = self.ty.pos();
+        let signature = match self.ty.into_mir()? {
+            MirType::FunctionSig(signature, _) => signature,
+            _ => unreachable!(),
+        };
+
+        let block = match *self.block {
+            TypedExpr {
+                expr: TypedExprEnum::Block(block),
+                pos,
+            } => block.into_mir(pos, sourcemap)?,
+            // Bodies that aren't blocks yield their value
+            body => {
+                let body = body.into_mir(sourcemap)?;
+                Block {
+                    nodes: vec![MirStmt::Expression(MirExpr {
+                        pos: body.pos,
+                        ty: MirType::Never(body.pos),
+                        value: MirExprEnum::Yield(Box::new(body)),
+                    })],
+                    metadata: BlockMetadata { returns: false },
+                    pos,
+                }
+            }
+        };
+
+        Ok(FunctionExpr {
+            signature,
+            arg_names: self.arg_names,
+            block,
+        })
+    }
+}
+
 impl TypedExpr {
-    fn into_mir(self) -> Result<MirExpr, ErrorValue> {
-        match self.expr {
+    fn into_mir(self, sourcemap: &SourceMap) -> Result<MirExpr, ErrorValue> {
+        let pos = self.pos;
+        let (value, ty) = match self.expr {
             TypedExprEnum::Is(is) => {
-                let mut expr = is.expr.into_mir()?;
+                let mut expr = is.expr.into_mir(sourcemap)?;
                 expr.ty = is.ty.into_mir()?;
-                Ok(expr)
+                return Ok(expr);
             }
-            _ => unimplemented!(),
-        }
+            TypedExprEnum::As(as_expr) => (
+                MirExprEnum::Cast(Box::new(as_expr.expr.into_mir(sourcemap)?)),
+                as_expr.ty.into_mir()?,
+            ),
+            TypedExprEnum::Block(block) => {
+                let ty = block.ty.clone().into_mir()?;
+                (MirExprEnum::Block(block.into_mir(pos, sourcemap)?), ty)
+            }
+            TypedExprEnum::Function(func) => {
+                let ty = func.ty.clone().into_mir()?;
+                (
+                    MirExprEnum::Function(Box::new(func.into_mir(sourcemap)?)),
+                    ty,
+                )
+            }
+            TypedExprEnum::FunctionCall(call) => (
+                MirExprEnum::FunctionCall(Box::new(FunctionCall {
+                    name: call.name,
+                    arguments: call
+                        .arguments
+                        .into_iter()
+                        .map(|arg| arg.into_mir(sourcemap))
+                        .collect::<Result<Vec<_>, _>>()?,
+                })),
+                call.ty.into_mir()?,
+            ),
+            TypedExprEnum::Literal(literal) => {
+                let literal = literal.into_mir(sourcemap)?;
+                let ty = literal.ty.clone();
+                (MirExprEnum::Literal(literal), ty)
+            }
+            TypedExprEnum::Tuple(tuple) => (
+                MirExprEnum::Tuple(
+                    tuple
+                        .exprs
+                        .into_iter()
+                        .map(|expr| expr.into_mir(sourcemap))
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                tuple.ty.into_mir()?,
+            ),
+            TypedExprEnum::RefID(ref_id) => {
+                (MirExprEnum::Variable(ref_id.name), ref_id.ty.into_mir()?)
+            }
+            TypedExprEnum::VariableAssign(assign) => {
+                let ty = assign.binder.ty.into_mir()?;
+                (
+                    MirExprEnum::VariableAssign(Box::new(VariableAssign {
+                        var_name: assign
+                            .binder
+                            .name
+                            .ok_or_else(|| unsupported("anonymous binding", pos))?,
+                        expr: assign.expr.into_mir(sourcemap)?,
+                        pos,
+                    })),
+                    ty,
+                )
+            }
+            TypedExprEnum::VariableAssignDeclaration(assign) => {
+                let ty = assign.binder.ty.into_mir()?;
+                (
+                    MirExprEnum::VariableAssignDeclaration(Box::new(VariableAssignDeclaration {
+                        var_name: assign
+                            .binder
+                            .name
+                            .ok_or_else(|| unsupported("anonymous binding", pos))?,
+                        expr: assign.expr.into_mir(sourcemap)?,
+                        visibility: assign.visibility,
+                        pos,
+                    })),
+                    ty,
+                )
+            }
+            TypedExprEnum::Yield(yield_val) => (
+                MirExprEnum::Yield(Box::new(yield_val.expr.into_mir(sourcemap)?)),
+                yield_val.ty.into_mir()?,
+            ),
+            TypedExprEnum::Return(return_val) => (
+                MirExprEnum::Return(Box::new(return_val.expr.into_mir(sourcemap)?)),
+                return_val.ty.into_mir()?,
+            ),
+            TypedExprEnum::Infix(infix) if infix.is_logical() => (
+                MirExprEnum::Logical(Box::new(Logical {
+                    left: infix.left.into_mir(sourcemap)?,
+                    right: infix.right.into_mir(sourcemap)?,
+                    operator: infix.operator,
+                })),
+                infix.ty.into_mir()?,
+            ),
+            TypedExprEnum::Infix(infix) => (
+                MirExprEnum::Infix(Box::new(Infix {
+                    left: infix.left.into_mir(sourcemap)?,
+                    right: infix.right.into_mir(sourcemap)?,
+                    operator: infix.operator,
+                })),
+                infix.ty.into_mir()?,
+            ),
+            // Ranges are tuples of their start, their end and whether the end is included
+            TypedExprEnum::Range(range) => (
+                MirExprEnum::Tuple(vec![
+                    range.start.into_mir(sourcemap)?,
+                    range.end.into_mir(sourcemap)?,
+                    MirExpr {
+                        value: MirExprEnum::Literal(Literal {
+                            value: range.inclusive.to_string(),
+                            ty: MirType::Primitive(Prim::Bool, pos),
+                            pos,
+                        }),
+                        ty: MirType::Primitive(Prim::Bool, pos),
+                        pos,
+                    },
+                ]),
+                range.ty.into_mir()?,
+            ),
+            TypedExprEnum::Conditional(conditional) => {
+                let ty = conditional.ty.clone().into_mir()?;
+                (
+                    MirExprEnum::Conditional(Box::new(conditional.into_mir(
+                        ty.clone(),
+                        pos,
+                        sourcemap,
+                    )?)),
+                    ty,
+                )
+            }
+            TypedExprEnum::Loop(loop_expr) => {
+                let ty = loop_expr.ty.clone().into_mir()?;
+                (
+                    MirExprEnum::Loop(Box::new(loop_expr.into_mir(pos, sourcemap)?)),
+                    ty,
+                )
+            }
+            TypedExprEnum::Break(break_expr) => (
+                MirExprEnum::Break(Box::new(Break {
+                    loop_id: break_expr.loop_id,
+                    value: match break_expr.expr {
+                        Some(value) => Some(value.into_mir(sourcemap)?),
+                        None => None,
+                    },
+                })),
+                break_expr.ty.into_mir()?,
+            ),
+            TypedExprEnum::Match(match_expr) => {
+                let ty = match_expr.ty.clone().into_mir()?;
+                (
+                    MirExprEnum::Match(Box::new(match_expr.into_mir(pos, sourcemap)?)),
+                    ty,
+                )
+            }
+            TypedExprEnum::Continue(continue_expr) => (
+                MirExprEnum::Continue(continue_expr.loop_id),
+                continue_expr.ty.into_mir()?,
+            ),
+            TypedExprEnum::Prefix(prefix) => {
+                let val = prefix.val.into_mir(sourcemap)?;
+
+                (
+                    MirExprEnum::Prefix(Box::new(Prefix {
+                        val,
+                        operator: prefix.operator,
+                    })),
+                    prefix.ty.into_mir()?,
+                )
+            }
+        };
+
+        Ok(MirExpr { value, pos, ty })
     }
 }
 
 impl AnnotationType {
     fn into_mir(self) -> Result<MirType, ErrorValue> {
-        if let Some(prim) = self.is_primitive() {
-            return Ok(MirType::Primitive(prim, self.pos()));
-        }
-
         match self {
-            AnnotationType::Type(_, _) => panic!("Custom types are not implemented yet"),
+            AnnotationType::Prim(prim, pos) => Ok(MirType::Primitive(prim, pos)),
+            AnnotationType::Type(_, pos) => Err(unsupported("custom type", pos)),
             AnnotationType::Tuple(tup, pos) => Ok(MirType::Tuple(
-                (*tup).clone()
+                (*tup)
+                    .clone()
                     .into_iter()
                     .map(|ty| ty.into_mir())
                     .collect::<Result<Vec<_>, _>>()?,
                 pos,
             )),
-            _ => unimplemented!(),
+            AnnotationType::Function(args, ret, pos) => Ok(MirType::FunctionSig(
+                FunctionSig {
+                    pos_args: (*args)
+                        .clone()
+                        .into_iter()
+                        .map(|ty| ty.into_mir())
+                        .collect::<Result<Vec<_>, _>>()?,
+                    return_type: Box::new((*ret).clone().into_mir()?),
+                    pos,
+                },
+                pos,
+            )),
+            AnnotationType::Range(ty, pos) => {
+                let ty = (*ty).clone().into_mir()?;
+                Ok(MirType::Tuple(
+                    vec![ty.clone(), ty, MirType::Primitive(Prim::Bool, pos)],
+                    pos,
+                ))
+            }
+            AnnotationType::Never(pos) => Ok(MirType::Never(pos)),
+            // Every type should be known after substitution
+            AnnotationType::Infer(_, pos) => Err(unsupported("uninferred type", pos)),
         }
     }
 }
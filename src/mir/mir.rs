@@ -3,19 +3,22 @@
 //! This is a lower lever representation of code.
 
 use crate::helpers;
+use crate::lexer;
 use crate::parser::ast;
 use crate::typecheck::annotation::Prim;
 
+use std::rc::Rc;
+
 #[derive(Debug, Clone)]
 pub struct Binding {
-    pub name: ast::Namespace,
+    pub name: Rc<ast::Namespace>,
     pub ty: MirType,
 }
 
 #[derive(Debug, Clone)]
 /// (i32, i32, ()) -> ()
 pub struct FunctionSig {
-    pub pos_args: Vec<Binding>,
+    pub pos_args: Vec<MirType>,
     pub return_type: Box<MirType>,
     pub pos: helpers::Pos,
 }
@@ -30,6 +33,9 @@ pub enum MirType {
 
     /// Function Signatures
     FunctionSig(FunctionSig, helpers::Pos),
+
+    /// Type of an expression that never evaluates to a value, E.g., `return`
+    Never(helpers::Pos),
 }
 
 #[derive(Debug, Clone)]
@@ -54,6 +60,73 @@ pub struct Conditional {
     pub pos: helpers::Pos,
 }
 
+#[derive(Debug, Clone)]
+/// What stops a loop
+pub enum LoopKind {
+    Loop,
+    /// Checked before every iteration
+    While(MirExpr),
+    /// Counts through the values of a range, `name` is the current count
+    For {
+        name: Rc<ast::Namespace>,
+        range: MirExpr,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Loop {
+    /// Id that `break` and `continue` refer to the loop with
+    pub id: usize,
+    pub kind: LoopKind,
+    pub block: Block,
+    pub block_ty: MirType,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone)]
+pub struct Break {
+    pub loop_id: usize,
+    pub value: Option<MirExpr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatternKind {
+    Wildcard,
+    Literal(MirExpr),
+    Binding(Rc<ast::Namespace>),
+    Tuple(Vec<Pattern>),
+    /// Values from the start up to the end, which is only matched if the range is inclusive
+    Range {
+        start: MirExpr,
+        end: MirExpr,
+        inclusive: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    /// Type of the values the pattern is tested against
+    pub ty: MirType,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<MirExpr>,
+    pub expr: MirExpr,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone)]
+/// Arms are tried in order, the typechecker made sure one of them matches
+pub struct Match {
+    pub expr: MirExpr,
+    pub arms: Vec<MatchArm>,
+    pub pos: helpers::Pos,
+}
+
 #[derive(Debug, Clone)]
 pub struct BlockMetadata {
     pub returns: bool,
@@ -69,41 +142,95 @@ pub struct Block {
 #[derive(Debug, Clone)]
 pub struct FunctionExpr {
     pub signature: FunctionSig,
+    pub arg_names: Vec<Rc<ast::Namespace>>,
     pub block: Block,
-    pub mangled_name: String,
 }
 
 #[derive(Debug, Clone)]
+pub struct FunctionCall {
+    pub name: Rc<ast::Namespace>,
+    pub arguments: Vec<MirExpr>,
+}
+
+#[derive(Debug, Clone)]
+/// Store of a new value into a mutable binding, `x += 1` stores `x + 1`
 pub struct VariableAssign {
-    pub var_name: ast::Namespace,
-    pub ty: MirExpr,
+    pub var_name: Rc<ast::Namespace>,
+    pub expr: MirExpr,
     pub pos: helpers::Pos,
 }
+
 #[derive(Debug, Clone)]
 pub struct VariableAssignDeclaration {
-    pub var_name: ast::Namespace,
-    pub ty: MirExpr,
+    pub var_name: Rc<ast::Namespace>,
+    pub expr: MirExpr,
+    pub visibility: ast::Visibility,
     pub pos: helpers::Pos,
 }
+
 #[derive(Debug, Clone)]
-pub struct VariableDeclaration {}
+/// Declaration without a value, external ones are symbols defined in another object
+pub struct VariableDeclaration {
+    pub binding: Binding,
+    pub is_extern: bool,
+    pub pos: helpers::Pos,
+}
 
 #[derive(Debug, Clone)]
 pub struct Literal {
+    /// Numbers and chars as plain decimals, strings with their escapes decoded
+    pub value: String,
     pub ty: MirType,
     pub pos: helpers::Pos,
 }
 
+#[derive(Debug, Clone)]
+pub struct Infix {
+    pub left: MirExpr,
+    pub right: MirExpr,
+    pub operator: lexer::TokenType,
+}
+
+#[derive(Debug, Clone)]
+/// `&&` or `||`, the right operand is only evaluated if the left one doesn't decide the result
+pub struct Logical {
+    pub left: MirExpr,
+    pub right: MirExpr,
+    pub operator: lexer::TokenType,
+}
+
+#[derive(Debug, Clone)]
+pub struct Prefix {
+    pub val: MirExpr,
+    pub operator: lexer::TokenType,
+}
+
 #[derive(Debug, Clone)]
 pub struct MirTag {}
 
 #[derive(Debug, Clone)]
 pub enum MirExprEnum {
-    Variable(ast::Namespace),
+    Variable(Rc<ast::Namespace>),
     Literal(Literal),
+    Tuple(Vec<MirExpr>),
+    Block(Block),
     Function(Box<FunctionExpr>),
+    FunctionCall(Box<FunctionCall>),
     VariableAssign(Box<VariableAssign>),
     VariableAssignDeclaration(Box<VariableAssignDeclaration>),
+    Return(Box<MirExpr>),
+    Yield(Box<MirExpr>),
+    Infix(Box<Infix>),
+    Logical(Box<Logical>),
+    Prefix(Box<Prefix>),
+    /// Conversion of the value into the type of the expression
+    Cast(Box<MirExpr>),
+    Conditional(Box<Conditional>),
+    Loop(Box<Loop>),
+    Break(Box<Break>),
+    /// Start the next iteration of the loop with this id
+    Continue(usize),
+    Match(Box<Match>),
 }
 
 #[derive(Debug, Clone)]
@@ -116,7 +243,6 @@ pub struct MirExpr {
 #[derive(Debug, Clone)]
 pub enum MirStmt {
     VariableDeclaration(VariableDeclaration),
-    Conditional(Conditional),
     Tag(MirTag),
     Expression(MirExpr),
 }
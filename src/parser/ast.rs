@@ -1,7 +1,7 @@
-use crate::typecheck::annotation::AnnotationType;
+use crate::typecheck::annotation::{AnnotationType, Prim};
 
 use crate::helpers;
-use crate::lexer::Token;
+use crate::lexer::{self, Token};
 use crate::sourcemap::SourceMap;
 use crate::tags::UnitTags;
 
@@ -30,13 +30,19 @@ pub struct Tag {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LiteralType {
     Number,
+    Float,
     String,
+    Char,
     Bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Literal {
     pub literal_type: LiteralType,
+    /// Type suffix of a number, i.e. `u8` in `255u8`
+    pub suffix: Option<Prim>,
+    /// Value of a string or char with its escapes decoded
+    pub value: Option<String>,
     pub pos: helpers::Pos,
 }
 
@@ -103,6 +109,15 @@ pub struct IsExpr {
     pub pos: helpers::Pos,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// `start..end` up to `end` excluded, or `start..=end` up to `end` included
+pub struct Range {
+    pub start: Box<Expr>,
+    pub end: Box<Expr>,
+    pub inclusive: bool,
+    pub pos: helpers::Pos,
+}
+
 // NODES ---------------------------------------
 
 #[derive(Debug)]
@@ -144,18 +159,38 @@ mod name_id_test {
         assert_eq!(first, second);
         assert_eq!(second, first);
     }
+
+    #[test]
+    fn eq_normalized() {
+        let sourcemap = SourceMapInner::new();
+        sourcemap.borrow_mut().insert_file(
+            PathBuf::from("teset.fl"),
+            "caf\u{e9} cafe\u{301}".to_string(),
+        );
+        let composed = NameID {
+            sourcemap: Rc::clone(&sourcemap),
+            pos: helpers::Pos::new(0, 5, 0),
+        };
+        let decomposed = NameID {
+            sourcemap,
+            pos: helpers::Pos::new(6, 12, 0),
+        };
+        assert_eq!(composed, decomposed);
+        assert_eq!(composed.into_namespace().to_string(), "caf\u{e9}");
+    }
 }
 
+// Names are compared in NFC, so names that look the same are the same
 impl Hash for NameID {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.sourcemap.borrow().get_segment(self.pos).hash(state);
+        lexer::normalize_identifier(self.sourcemap.borrow().get_segment(self.pos)).hash(state);
     }
 }
 
 impl PartialEq for NameID {
     fn eq(&self, other: &Self) -> bool {
-        self.sourcemap.borrow().get_segment(self.pos)
-            == self.sourcemap.borrow().get_segment(other.pos)
+        lexer::normalize_identifier(self.sourcemap.borrow().get_segment(self.pos))
+            == lexer::normalize_identifier(self.sourcemap.borrow().get_segment(other.pos))
     }
 }
 
@@ -176,6 +211,8 @@ impl NameID {
 /// x = 10;
 pub struct VariableAssign {
     pub name: Rc<Namespace>,
+    /// Operator of a compound assignment, i.e. `+` for `x += 10`
+    pub operator: Option<lexer::TokenType>,
     pub expr: Box<Expr>,
     pub pos: helpers::Pos,
 }
@@ -188,6 +225,8 @@ pub struct TypeAssign {
     pub name: Rc<Namespace>,
     pub value: Type,
     pub visibility: Visibility,
+    /// Text of the doc comments before the declaration
+    pub docs: Option<String>,
     pub pos: helpers::Pos,
 }
 
@@ -199,7 +238,11 @@ pub struct VariableAssignDeclaration {
     pub ty: Type,
     pub name: Rc<Namespace>,
     pub expr: Box<Expr>,
+    /// Declared with `let mut`, only then can it be assigned again
+    pub mutable: bool,
     pub visibility: Visibility,
+    /// Text of the doc comments before the declaration
+    pub docs: Option<String>,
     pub pos: helpers::Pos,
 }
 
@@ -212,6 +255,8 @@ pub struct VariableDeclaration {
     pub name: Rc<Namespace>,
     pub is_extern: bool,
     pub visibility: Visibility,
+    /// Text of the doc comments before the declaration
+    pub docs: Option<String>,
     pub pos: helpers::Pos,
 }
 
@@ -220,6 +265,8 @@ pub struct Unit {
     pub name: Namespace,
     pub pos: helpers::Pos,
     pub block: Vec<Statement>,
+    /// Text of the doc comments before the unit
+    pub docs: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -251,6 +298,88 @@ pub struct Conditional {
     pub pos: helpers::Pos,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// What stops a loop
+pub enum LoopKind {
+    /// `loop`, only stopped by a `break`
+    Loop,
+    /// `while cond`
+    While(Box<Expr>),
+    /// `for name in range`, counts through the values of a range, i.e. `0..n`
+    For {
+        name: Rc<Namespace>,
+        range: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loop {
+    /// Name of the loop for `break` and `continue`, i.e. `'outer`
+    pub label: Option<NameID>,
+    pub kind: LoopKind,
+    pub block: Block,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Leave a loop, `loop` evaluates to the value of its `break`
+pub struct Break {
+    pub label: Option<NameID>,
+    pub expression: Option<Box<Expr>>,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Start the next iteration of a loop
+pub struct Continue {
+    pub label: Option<NameID>,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// What a pattern matches
+pub enum PatternKind {
+    /// `_`, matches any value
+    Wildcard,
+    /// Integer, char or bool literal, integers may be negated, i.e. `-1`
+    Literal(Box<Expr>),
+    /// Matches any value and binds it to the name
+    Binding(Rc<Namespace>),
+    Tuple(Vec<Pattern>),
+    /// `start..end` or `start..=end`, matches the values of the range
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Arm of a match, i.e. `(x, _) if x > 0 => x`
+pub struct MatchArm {
+    pub pattern: Pattern,
+    /// Only take the arm when this is true
+    pub guard: Option<Expr>,
+    pub expr: Expr,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Match i.e.:
+///
+/// match n { 0 => 1, _ => n * 2 }
+pub struct Match {
+    pub expr: Box<Expr>,
+    pub arms: Vec<MatchArm>,
+    pub pos: helpers::Pos,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 /// Block of code
 pub struct Block {
@@ -433,9 +562,17 @@ impl Hash for Namespace {
 
 impl Display for Namespace {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(scope) = self.scopes.first() {
-            let sourcemap_borrowed = scope.sourcemap.borrow();
-            write!(f, "{}", sourcemap_borrowed.get_segment(self.pos))?;
+        // Write each scope on its own, so prefixed namespaces (i.e. names inside units) print
+        // their full path
+        for (idx, scope) in self.scopes.iter().enumerate() {
+            if idx != 0 {
+                write!(f, "::")?;
+            }
+            write!(
+                f,
+                "{}",
+                lexer::normalize_identifier(get_segment!(scope.sourcemap, scope.pos))
+            )?;
         }
         Ok(())
     }
@@ -578,6 +715,8 @@ pub enum Expr {
     As(AsExpr),
     Is(IsExpr),
 
+    Range(Range),
+
     Tuple(Tuple),
 
     DollarID(DollarID),
@@ -586,6 +725,12 @@ pub enum Expr {
 
     Conditional(Conditional),
     Block(Block),
+
+    Loop(Loop),
+    Break(Break),
+    Continue(Continue),
+
+    Match(Match),
 }
 
 impl Expr {
@@ -613,9 +758,17 @@ impl Expr {
             Expr::As(val) => val.pos,
             Expr::Is(val) => val.pos,
 
+            Expr::Range(val) => val.pos,
+
             Expr::Empty(val) => val.pos,
 
             Expr::Block(val) => val.pos,
+
+            Expr::Loop(val) => val.pos,
+            Expr::Break(val) => val.pos,
+            Expr::Continue(val) => val.pos,
+
+            Expr::Match(val) => val.pos,
         }
     }
 
@@ -645,9 +798,17 @@ impl Expr {
             Expr::As(_) => "as cast",
             Expr::Is(_) => "is cast",
 
+            Expr::Range(_) => "range",
+
             Expr::Empty(_) => "empty",
 
             Expr::Block(_) => "block",
+
+            Expr::Loop(_) => "loop",
+            Expr::Break(_) => "break statement",
+            Expr::Continue(_) => "continue statement",
+
+            Expr::Match(_) => "match",
         }
     }
 
@@ -0,0 +1,435 @@
+//! Lossless concrete syntax tree.
+//!
+//! The parser doesn't build this tree, it is laid over the tokens of a parsed file using the
+//! positions of the ast, and the ast of a node is derived by parsing its tokens again. A file
+//! with syntax errors has no ast, so its tree only has tokens directly under the root node.
+
+use super::ast::{self, Expr, Statement};
+use super::Parser;
+
+use crate::helpers::Pos;
+use crate::lexer::{Token, TokenType};
+use crate::logger::{ErrorValue, Logger};
+use crate::sourcemap::SourceMap;
+
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Kind of a node, every statement and expression of the ast has its own kind
+pub enum SyntaxKind {
+    /// Whole file
+    Root,
+
+    ExpressionStatement,
+    VariableDeclaration,
+    Unit,
+    TypeAssign,
+    Import,
+    EmptyStatement,
+    Tag,
+
+    Literal,
+    RefID,
+    Reference,
+    VariableAssign,
+    VariableAssignDeclaration,
+    FunctionCall,
+    Function,
+    Infix,
+    Prefix,
+    Return,
+    Yield,
+    As,
+    Is,
+    Range,
+    Tuple,
+    DollarID,
+    Empty,
+    Conditional,
+    Block,
+    Loop,
+    Break,
+    Continue,
+    Match,
+    MatchArm,
+    Pattern,
+
+    /// Type annotation, i.e. `i32` in `let x: i32;`
+    Type,
+}
+
+#[derive(Debug, Clone)]
+/// Token with its text, trivia (i.e. whitespace and comments) included
+pub struct SyntaxToken {
+    pub kind: TokenType,
+    pub text: String,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Debug, Clone)]
+/// Node of the lossless concrete syntax tree
+///
+/// Printing the root node gives back the source it was parsed from byte for byte
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+    pub pos: Pos,
+}
+
+/// Start and end of a node, as found in the ast
+struct Span {
+    kind: SyntaxKind,
+    pos: Pos,
+}
+
+impl SyntaxNode {
+    /// Build the tree of a file from all of its tokens in source order, trivia included, and
+    /// the statements parsed from them
+    pub fn new(tokens: Vec<Token>, statements: &[Statement], sourcemap: &SourceMap) -> SyntaxNode {
+        let tokens = tokens
+            .into_iter()
+            .map(|token| match token.token {
+                // The end of the file has no text
+                TokenType::EOF => SyntaxToken {
+                    kind: token.token,
+                    text: String::new(),
+                    pos: Pos::new(token.pos.e, token.pos.e, token.pos.filename_id),
+                },
+                _ => SyntaxToken {
+                    kind: token.token,
+                    text: get_segment!(sourcemap, token.pos).to_string(),
+                    pos: token.pos,
+                },
+            })
+            .collect::<Vec<_>>();
+        let filename_id = tokens.first().map_or(0, |token| token.pos.filename_id);
+        let end = tokens.last().map_or(0, |token| token.pos.e);
+
+        let mut spans = Vec::new();
+        for stmt in statements {
+            statement_spans(stmt, &mut spans);
+        }
+        // Outer nodes before the nodes inside of them
+        spans.sort_by_key(|span| (span.pos.s, std::cmp::Reverse(span.pos.e)));
+
+        let mut builder = Builder {
+            tokens,
+            spans,
+            token_idx: 0,
+            span_idx: 0,
+        };
+        builder.node(SyntaxKind::Root, Pos::new(0, end, filename_id))
+    }
+
+    /// Tokens of the node in source order, trivia included
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// Nodes directly inside of this node
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Statements of the root node, parsed again from its tokens
+    pub fn statements(
+        &self,
+        logger: Logger,
+        sourcemap: SourceMap,
+    ) -> Result<Vec<Statement>, Vec<ErrorValue>> {
+        let mut parser = self.parser(logger, sourcemap);
+        parser.parse()?;
+        Ok(parser.ast.unwrap())
+    }
+
+    /// Statement of a statement node, parsed again from its tokens
+    pub fn statement(&self, logger: Logger, sourcemap: SourceMap) -> Result<Statement, ErrorValue> {
+        self.parser(logger, sourcemap).statement()
+    }
+
+    /// Expression of an expression node, parsed again from its tokens
+    pub fn expr(&self, logger: Logger, sourcemap: SourceMap) -> Result<Expr, ErrorValue> {
+        self.parser(logger, sourcemap).expression()
+    }
+
+    fn parser(&self, logger: Logger, sourcemap: SourceMap) -> Parser {
+        let tokens = self
+            .tokens()
+            .into_iter()
+            .map(|token| Token {
+                token: token.kind,
+                pos: token.pos,
+            })
+            .collect::<Vec<_>>();
+
+        let mut parser = Parser::from_tokens(tokens, self.pos, logger, Rc::clone(&sourcemap));
+        parser.initialize_expr();
+        parser
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.tokens() {
+            write!(f, "{}", token.text)?;
+        }
+        Ok(())
+    }
+}
+
+struct Builder {
+    tokens: Vec<SyntaxToken>,
+    spans: Vec<Span>,
+    token_idx: usize,
+    span_idx: usize,
+}
+
+impl Builder {
+    /// Start of the next token, doc comments are part of the declaration they document so
+    /// they start where it starts
+    fn token_start(&self) -> Option<usize> {
+        let token = self.tokens.get(self.token_idx)?;
+        if token.kind == TokenType::DocComment {
+            let declaration = self.tokens[self.token_idx..]
+                .iter()
+                .find(|token| !token.kind.is_trivia());
+            if let Some(declaration) = declaration {
+                return Some(declaration.pos.s);
+            }
+        }
+        Some(token.pos.s)
+    }
+
+    /// Build a node out of the tokens and nodes that start before `pos` ends
+    fn node(&mut self, kind: SyntaxKind, pos: Pos) -> SyntaxNode {
+        let mut children = Vec::new();
+
+        loop {
+            let token_start = self.token_start();
+            let span_start = self.spans.get(self.span_idx).map(|span| span.pos.s);
+
+            match (span_start, token_start) {
+                // A node starts before the next token
+                (Some(span_start), Some(token_start))
+                    if span_start <= token_start && span_start < pos.e =>
+                {
+                    let span = &self.spans[self.span_idx];
+                    self.span_idx += 1;
+                    // Nodes without tokens, i.e. omitted types, are left out
+                    if span.pos.s < span.pos.e {
+                        // Starts at its doc comments, and can't end after the node it is
+                        // inside of
+                        let start = span.pos.s.min(self.tokens[self.token_idx].pos.s);
+                        let end = span.pos.e.min(pos.e);
+                        let span_pos = Pos::new(start, end, span.pos.filename_id);
+                        let span_kind = span.kind;
+                        children.push(SyntaxElement::Node(self.node(span_kind, span_pos)));
+                    }
+                }
+                (_, Some(token_start)) if token_start < pos.e || kind == SyntaxKind::Root => {
+                    children.push(SyntaxElement::Token(self.tokens[self.token_idx].clone()));
+                    self.token_idx += 1;
+                }
+                _ => break,
+            }
+        }
+
+        SyntaxNode {
+            kind,
+            children,
+            pos,
+        }
+    }
+}
+
+fn span(kind: SyntaxKind, pos: Pos, spans: &mut Vec<Span>) {
+    spans.push(Span { kind, pos });
+}
+
+fn statement_spans(stmt: &Statement, spans: &mut Vec<Span>) {
+    match stmt {
+        Statement::ExpressionStatement(expr_stmt) => {
+            span(SyntaxKind::ExpressionStatement, expr_stmt.pos, spans);
+            expr_spans(&expr_stmt.expression, spans);
+        }
+        Statement::VariableDeclaration(var_dec) => {
+            span(SyntaxKind::VariableDeclaration, var_dec.pos, spans);
+            type_spans(&var_dec.ty, spans);
+        }
+        Statement::Unit(unit) => {
+            span(SyntaxKind::Unit, unit.pos, spans);
+            for stmt in &unit.block {
+                statement_spans(stmt, spans);
+            }
+        }
+        Statement::TypeAssign(type_assign) => {
+            span(SyntaxKind::TypeAssign, type_assign.pos, spans);
+            type_spans(&type_assign.value, spans);
+        }
+        Statement::Import(import) => span(SyntaxKind::Import, import.pos, spans),
+        Statement::Empty(empty) => span(SyntaxKind::EmptyStatement, empty.pos, spans),
+        Statement::Tag(tag) => span(SyntaxKind::Tag, tag.pos, spans),
+    }
+}
+
+fn expr_spans(expr: &Expr, spans: &mut Vec<Span>) {
+    match expr {
+        Expr::Literal(literal) => span(SyntaxKind::Literal, literal.pos, spans),
+        Expr::RefID(ref_id) => span(SyntaxKind::RefID, ref_id.pos, spans),
+        Expr::Reference(reference) => span(SyntaxKind::Reference, reference.pos, spans),
+        Expr::DollarID(dollar_id) => span(SyntaxKind::DollarID, dollar_id.pos, spans),
+        Expr::Empty(empty) => span(SyntaxKind::Empty, empty.pos, spans),
+        Expr::VariableAssign(var_assign) => {
+            span(SyntaxKind::VariableAssign, var_assign.pos, spans);
+            expr_spans(&var_assign.expr, spans);
+        }
+        Expr::VariableAssignDeclaration(var_assign) => {
+            span(SyntaxKind::VariableAssignDeclaration, var_assign.pos, spans);
+            type_spans(&var_assign.ty, spans);
+            expr_spans(&var_assign.expr, spans);
+        }
+        Expr::FunctionCall(call) => {
+            span(SyntaxKind::FunctionCall, call.pos, spans);
+            for arg in &call.arguments.positional {
+                expr_spans(arg, spans);
+            }
+        }
+        Expr::Function(function) => {
+            span(SyntaxKind::Function, function.pos, spans);
+            for (_, ty) in &function.arguments.positional {
+                type_spans(ty, spans);
+            }
+            type_spans(&function.return_type, spans);
+            expr_spans(&function.block, spans);
+        }
+        Expr::Infix(infix) => {
+            span(SyntaxKind::Infix, infix.pos, spans);
+            expr_spans(&infix.left, spans);
+            expr_spans(&infix.right, spans);
+        }
+        Expr::Prefix(prefix) => {
+            span(SyntaxKind::Prefix, prefix.pos, spans);
+            expr_spans(&prefix.val, spans);
+        }
+        Expr::Return(ret) => {
+            span(SyntaxKind::Return, ret.pos, spans);
+            expr_spans(&ret.expression, spans);
+        }
+        Expr::Yield(yield_expr) => {
+            span(SyntaxKind::Yield, yield_expr.pos, spans);
+            expr_spans(&yield_expr.expression, spans);
+        }
+        Expr::As(as_expr) => {
+            span(SyntaxKind::As, as_expr.pos, spans);
+            expr_spans(&as_expr.expr, spans);
+            type_spans(&as_expr.ty, spans);
+        }
+        Expr::Is(is_expr) => {
+            span(SyntaxKind::Is, is_expr.pos, spans);
+            expr_spans(&is_expr.expr, spans);
+            type_spans(&is_expr.ty, spans);
+        }
+        Expr::Range(range) => {
+            span(SyntaxKind::Range, range.pos, spans);
+            expr_spans(&range.start, spans);
+            expr_spans(&range.end, spans);
+        }
+        Expr::Tuple(tuple) => {
+            span(SyntaxKind::Tuple, tuple.pos, spans);
+            for value in &tuple.values {
+                expr_spans(value, spans);
+            }
+        }
+        Expr::Conditional(conditional) => {
+            span(SyntaxKind::Conditional, conditional.pos, spans);
+            for branch in &conditional.if_branches {
+                expr_spans(&branch.cond, spans);
+                block_spans(&branch.block, spans);
+            }
+            if let Some(else_branch) = &conditional.else_branch {
+                block_spans(&else_branch.block, spans);
+            }
+        }
+        Expr::Block(block) => block_spans(block, spans),
+        Expr::Loop(loop_expr) => {
+            span(SyntaxKind::Loop, loop_expr.pos, spans);
+            match &loop_expr.kind {
+                ast::LoopKind::Loop => {}
+                ast::LoopKind::While(cond) => expr_spans(cond, spans),
+                ast::LoopKind::For { range, .. } => expr_spans(range, spans),
+            }
+            block_spans(&loop_expr.block, spans);
+        }
+        Expr::Break(break_expr) => {
+            span(SyntaxKind::Break, break_expr.pos, spans);
+            if let Some(expression) = &break_expr.expression {
+                expr_spans(expression, spans);
+            }
+        }
+        Expr::Continue(continue_expr) => span(SyntaxKind::Continue, continue_expr.pos, spans),
+        Expr::Match(match_expr) => {
+            span(SyntaxKind::Match, match_expr.pos, spans);
+            expr_spans(&match_expr.expr, spans);
+            for arm in &match_expr.arms {
+                span(SyntaxKind::MatchArm, arm.pos, spans);
+                pattern_spans(&arm.pattern, spans);
+                if let Some(guard) = &arm.guard {
+                    expr_spans(guard, spans);
+                }
+                expr_spans(&arm.expr, spans);
+            }
+        }
+    }
+}
+
+fn pattern_spans(pattern: &ast::Pattern, spans: &mut Vec<Span>) {
+    span(SyntaxKind::Pattern, pattern.pos, spans);
+    match &pattern.kind {
+        ast::PatternKind::Wildcard | ast::PatternKind::Binding(_) => {}
+        ast::PatternKind::Literal(literal) => expr_spans(literal, spans),
+        ast::PatternKind::Tuple(patterns) => {
+            for pattern in patterns {
+                pattern_spans(pattern, spans);
+            }
+        }
+        ast::PatternKind::Range { start, end, .. } => {
+            expr_spans(start, spans);
+            expr_spans(end, spans);
+        }
+    }
+}
+
+fn block_spans(block: &ast::Block, spans: &mut Vec<Span>) {
+    span(SyntaxKind::Block, block.pos, spans);
+    for stmt in &block.nodes {
+        statement_spans(stmt, spans);
+    }
+}
+
+fn type_spans(ty: &ast::Type, spans: &mut Vec<Span>) {
+    // Omitted types have the position of what they belong to
+    if ty.value != ast::TypeType::Unknown {
+        span(SyntaxKind::Type, ty.pos, spans);
+    }
+}
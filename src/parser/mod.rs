@@ -1,3 +1,4 @@
 pub mod ast;
+pub mod cst;
 pub mod parser;
 pub use parser::Parser;
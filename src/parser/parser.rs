@@ -1,56 +1,64 @@
 use super::ast;
 use super::ast::{Expr, LiteralType, Scope, Statement};
+use super::cst;
 
 use crate::helpers;
 use crate::lexer;
-use crate::logger::{
-    ErrorAnnotation, ErrorDisplayType, ErrorGen, ErrorType, ErrorValue, Logger, LoggerInner,
-};
+use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorGen, ErrorType, ErrorValue, Logger};
 use crate::paths;
 use crate::sourcemap::SourceMap;
 use crate::tags::UnitTags;
+use crate::typecheck::annotation::Prim;
 
 use std::collections::HashMap;
 use std::path;
 use std::rc::Rc;
 
-macro_rules! run_all {
-    ( $self: expr, $($e:expr),+ ) => {
-        $(ignore_or_return!($e($self));)+
-    };
-}
+/// Default nesting limit of the parser
+pub const MAX_DEPTH: usize = 128;
 
-macro_rules! ignore_or_return {
-    ( $e:expr ) => {
-        match $e {
-            Ok(x) => return Ok(x),
-            Err(e) => {
-                if e.urgent {
-                    return Err(e);
-                }
-            }
-        }
-    };
-}
+/// Production of a statement
+type StatementProduction = fn(&mut Parser) -> Result<Statement, ErrorGen>;
 
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
 pub enum Prec {
     LOWEST = 0,
 
+    /// `..` and `..=`
+    RANGE = 1,
+
+    /// `||`
+    OR = 2,
+
+    /// `&&`
+    AND = 3,
+
+    /// `|`, below comparisons as in C
+    BITOR = 4,
+
+    /// `^`
+    BITXOR = 5,
+
+    /// `&`
+    BITAND = 6,
+
     /// Comparison operators
-    COMP = 1,
+    COMP = 7,
+
+    /// `<<` and `>>`
+    SHIFT = 8,
 
     /// `+` and `-`
-    TERM = 2,
+    TERM = 9,
 
     /// `*` and `/` and `%`
-    FACTOR = 3,
+    FACTOR = 10,
 
     /// `as` and `is` operator (CONVersion operator)
-    CONV = 4,
+    CONV = 11,
 
     /// `-` (negate) and others (i.e. `!` logical negate)
-    PREFIX = 5,
+    PREFIX = 12,
 }
 
 /// Recursive descent parser
@@ -59,36 +67,44 @@ pub struct Parser {
     pub lexer: lexer::Lexer,
     /// Abstract syntax tree
     pub ast: Option<Vec<ast::Statement>>,
+    /// Deepest nesting of expressions, types and units, the parser recurses for each level so
+    /// going any deeper is a syntax error instead of a stack overflow
+    pub max_depth: usize,
     logger: Logger,
 
-    statements: [fn(&mut Self) -> Result<Statement, ErrorGen>; 6],
     prefix_op: HashMap<lexer::TokenType, Prec>,
     infix_op: HashMap<lexer::TokenType, Prec>,
     tokens: Vec<lexer::Token>,
+    /// Doc comments by the index of the token they document
+    docs: HashMap<usize, Vec<helpers::Pos>>,
+    /// Whitespace and comments, kept aside for the concrete syntax tree
+    trivia: Vec<lexer::Token>,
+    /// Errors of the statements that were skipped to keep parsing
+    errors: Vec<ErrorGen>,
     token_pos: usize,
+    /// Current nesting of expressions, types and units
+    depth: usize,
     sourcemap: SourceMap,
 }
 
 impl Parser {
     /// Return a new parser object.
     pub fn new(filename: usize, logger: Logger, sourcemap: SourceMap) -> Parser {
-        let l = lexer::Lexer::new(filename, Rc::clone(&sourcemap));
+        let mut l = lexer::Lexer::new(filename, Rc::clone(&sourcemap));
+        l.keep_trivia = true;
         Parser {
             lexer: l,
             ast: None,
-            statements: [
-                Parser::import,
-                Parser::unit,
-                Parser::expression_statement,
-                Parser::variable_declaration,
-                Parser::type_assign,
-                Parser::compiler_tag,
-            ],
+            max_depth: MAX_DEPTH,
             prefix_op: HashMap::new(),
             infix_op: HashMap::new(),
             tokens: Vec::new(),
+            docs: HashMap::new(),
+            trivia: Vec::new(),
+            errors: Vec::new(),
             logger,
             token_pos: 0,
+            depth: 0,
             sourcemap,
         }
     }
@@ -101,6 +117,7 @@ impl Parser {
             .insert_file(source_path.to_path_buf(), contents);
 
         let mut parser = Parser::new(file_id, Rc::clone(&self.logger), Rc::clone(&self.sourcemap));
+        parser.max_depth = self.max_depth;
         parser.initialize_expr();
         helpers::error_or_other(parser.parse(), Rc::clone(&self.logger));
 
@@ -181,6 +198,10 @@ impl Parser {
     pub fn initialize_expr(&mut self) {
         // `-`
         self.register_prefix(lexer::TokenType::Sub, Prec::PREFIX);
+        // `!`
+        self.register_prefix(lexer::TokenType::Not, Prec::PREFIX);
+        // `~`
+        self.register_prefix(lexer::TokenType::BitNot, Prec::PREFIX);
 
         // `-`
         self.register_infix(lexer::TokenType::Sub, Prec::TERM);
@@ -211,6 +232,29 @@ impl Parser {
         self.register_infix(lexer::TokenType::LE, Prec::COMP);
         // `==`
         self.register_infix(lexer::TokenType::EQ, Prec::COMP);
+        // `!=`
+        self.register_infix(lexer::TokenType::NE, Prec::COMP);
+
+        // `&&`
+        self.register_infix(lexer::TokenType::And, Prec::AND);
+        // `||`
+        self.register_infix(lexer::TokenType::Or, Prec::OR);
+
+        // `&`
+        self.register_infix(lexer::TokenType::BitAnd, Prec::BITAND);
+        // `^`
+        self.register_infix(lexer::TokenType::BitXor, Prec::BITXOR);
+        // `|`
+        self.register_infix(lexer::TokenType::BitOr, Prec::BITOR);
+        // `<<`
+        self.register_infix(lexer::TokenType::Shl, Prec::SHIFT);
+        // `>>`
+        self.register_infix(lexer::TokenType::Shr, Prec::SHIFT);
+
+        // `..`
+        self.register_infix(lexer::TokenType::DoubleDot, Prec::RANGE);
+        // `..=`
+        self.register_infix(lexer::TokenType::DoubleDotEq, Prec::RANGE);
     }
 
     #[inline]
@@ -223,27 +267,101 @@ impl Parser {
         self.infix_op.insert(token, prec);
     }
 
+    /// Parser over tokens that have already been lexed, i.e. the tokens of a syntax tree node
+    /// spanning `pos`
+    pub fn from_tokens(
+        tokens: Vec<lexer::Token>,
+        pos: helpers::Pos,
+        logger: Logger,
+        sourcemap: SourceMap,
+    ) -> Parser {
+        let mut parser = Parser::new(pos.filename_id, logger, sourcemap);
+        for token in tokens {
+            if token.token != lexer::TokenType::EOF {
+                parser.push_token(token);
+            }
+        }
+        parser.push_token(lexer::Token {
+            token: lexer::TokenType::EOF,
+            pos: helpers::Pos::new(pos.e, pos.e, pos.filename_id),
+        });
+        parser
+    }
+
     fn fill_token_stream(&mut self) -> Result<(), ErrorValue> {
+        // Already filled from tokens
+        if !self.tokens.is_empty() {
+            return Ok(());
+        }
+
         loop {
-            let next = self.lexer.advance();
-            match next {
-                Ok(val) => {
-                    self.tokens.push(val);
-                    if val.token == lexer::TokenType::EOF {
-                        break;
-                    }
-                }
-                Err(e) => return Err(e),
+            let val = self.lexer.advance()?;
+            self.push_token(val);
+            if val.token == lexer::TokenType::EOF {
+                let warnings = self.lexer.take_warnings();
+                self.logger.borrow_mut().append_errors(warnings);
+                break;
             }
         }
         Ok(())
     }
 
+    fn push_token(&mut self, token: lexer::Token) {
+        if token.token == lexer::TokenType::DocComment {
+            // Doc comments are kept aside for the declaration after them
+            self.docs
+                .entry(self.tokens.len())
+                .or_insert_with(Vec::new)
+                .push(token.pos);
+        }
+
+        if token.token.is_trivia() {
+            self.trivia.push(token);
+        } else {
+            self.tokens.push(token);
+        }
+    }
+
+    /// Lossless concrete syntax tree of the parsed file, with its whitespace and comments
+    pub fn syntax_tree(&self) -> cst::SyntaxNode {
+        let mut tokens = self.tokens.clone();
+        tokens.extend(self.trivia.iter().copied());
+        // The end of the file is after the trivia at the end
+        tokens.sort_by_key(|token| (token.token == lexer::TokenType::EOF, token.pos.s));
+
+        // Statements of imported files are in other files
+        let statements = self
+            .ast
+            .iter()
+            .flatten()
+            .filter(|stmt| stmt.pos().filename_id == self.lexer.filename)
+            .cloned()
+            .collect::<Vec<_>>();
+        cst::SyntaxNode::new(tokens, &statements, &self.sourcemap)
+    }
+
+    /// Text of the doc comments before the token at `token_pos`, one line per comment
+    fn docs(&self, token_pos: usize) -> Option<String> {
+        self.docs.get(&token_pos).map(|comments| {
+            comments
+                .iter()
+                .map(|pos| lexer::doc_text(get_segment!(self.sourcemap, *pos)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+
     #[inline]
     fn peek(&self) -> lexer::Token {
         self.tokens[self.token_pos]
     }
 
+    /// Token `n` tokens after the next one, the end of the file if there are no more
+    #[inline]
+    fn peek_nth(&self, n: usize) -> lexer::Token {
+        self.tokens[(self.token_pos + n).min(self.tokens.len() - 1)]
+    }
+
     #[inline]
     fn forward(&mut self) -> lexer::Token {
         let temp = self.tokens[self.token_pos];
@@ -256,7 +374,8 @@ impl Parser {
         self.token_pos = pos;
     }
 
-    /// Parse from lexer tokens
+    /// Parse from lexer tokens, a statement that fails to parse is skipped so every syntax
+    /// error of the file is reported
     ///
     /// Returns nothing
     pub fn parse(&mut self) -> Result<(), Vec<ErrorValue>> {
@@ -268,65 +387,227 @@ impl Parser {
         let scope = Scope::Outer;
 
         let mut ast_list: Vec<Statement> = Vec::new();
-        let next = self.peek();
-        if next.token != lexer::TokenType::EOF {
-            loop {
-                let mut errors: Vec<ErrorGen> = Vec::new();
-                let mut fail = true;
-                for i in 0..self.statements.len() {
-                    let statement_ast = self.statements[i](self);
-                    match statement_ast {
-                        Ok(ast_production) => {
-                            if ast_production.in_scope(&scope) {
-                                fail = false;
-                                errors.clear();
-                                ast_list.push(ast_production);
-                                break;
-                            } else {
-                                let ast_pos = ast_production.pos();
-                                errors.push(ErrorGen::new(
-                                    Box::new(move || {
-                                        ErrorValue::new(
-                                            "unexpected statement in outer scope".to_string(),
-                                            ErrorType::Syntax,
-                                            ast_pos,
-                                            ErrorDisplayType::Error,
-                                            vec![ErrorAnnotation::new(
-                                                Some("unexpected statement".to_string()),
-                                                ast_pos,
-                                                ErrorDisplayType::Error,
-                                            )],
-                                        )
-                                    }),
-                                    ast_pos,
-                                    true,
-                                ));
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            if e.urgent {
-                                return Err(vec![e.into()]);
-                            }
-                            errors.push(e);
-                        }
+        while self.peek().token != lexer::TokenType::EOF {
+            let position = self.token_pos;
+            match self.scoped_statement(&scope) {
+                Ok(ast_production) => ast_list.push(ast_production),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.set_pos(position);
+                    self.synchronize();
+                    // A stray `}`
+                    if self.token_pos == position {
+                        self.forward();
                     }
                 }
-                let temp_peek = self.peek();
-                if temp_peek.token == lexer::TokenType::EOF && !fail {
-                    // We've successfully parsed, break
-                    break;
-                } else if !errors.is_empty() && fail {
-                    // We've found an error, raise the error
-                    return Err(vec![LoggerInner::longest(errors).into()]);
-                }
             }
         }
 
+        if !self.errors.is_empty() {
+            let mut errors = std::mem::take(&mut self.errors);
+            errors.sort_by_key(|e| e.position.s);
+            return Err(errors.into_iter().map(ErrorValue::from).collect());
+        }
+
         self.ast = Some(ast_list);
         Ok(())
     }
 
+    /// Parse a statement allowed in `scope`
+    fn scoped_statement(&mut self, scope: &Scope) -> Result<Statement, ErrorGen> {
+        let recovered = self.errors.len();
+        let ast_production = match self.statement_production()(self) {
+            Ok(ast_production) => ast_production,
+            Err(e) => {
+                // The whole statement is skipped, along with the errors recovered inside of it
+                self.errors.truncate(recovered);
+                return Err(e);
+            }
+        };
+
+        if ast_production.in_scope(scope) {
+            return Ok(ast_production);
+        }
+
+        let ast_pos = ast_production.pos();
+        let message = format!(
+            "unexpected statement in {} scope",
+            if let Scope::Outer = scope {
+                "outer"
+            } else {
+                "inner"
+            }
+        );
+        Err(ErrorGen::new(
+            Box::new(move || {
+                ErrorValue::new(
+                    message.clone(),
+                    ErrorType::Syntax,
+                    ast_pos,
+                    ErrorDisplayType::Error,
+                    vec![ErrorAnnotation::new(
+                        Some("unexpected statement".to_string()),
+                        ast_pos,
+                        ErrorDisplayType::Error,
+                    )],
+                )
+            }),
+            ast_pos,
+            true,
+        ))
+    }
+
+    /// Production of the statement that starts with the next token, so each statement is parsed
+    /// once and a syntax error is only built for the production that fails
+    fn statement_production(&self) -> StatementProduction {
+        match self.peek().token {
+            // `unit core::fmt;` or `unit fmt { ... }`
+            lexer::TokenType::Unit if self.after_namespace(1) == lexer::TokenType::Semi => {
+                Parser::import
+            }
+            lexer::TokenType::Unit => Parser::unit,
+            lexer::TokenType::At => Parser::compiler_tag,
+            lexer::TokenType::Type => Parser::type_assign,
+            lexer::TokenType::Extern => Parser::variable_declaration,
+            lexer::TokenType::Public => match self.peek_nth(1).token {
+                lexer::TokenType::Type => Parser::type_assign,
+                lexer::TokenType::Extern => Parser::variable_declaration,
+                lexer::TokenType::Let if self.is_declaration() => Parser::variable_declaration,
+                _ => Parser::expression_statement,
+            },
+            lexer::TokenType::Let if self.is_declaration() => Parser::variable_declaration,
+            _ => Parser::expression_statement,
+        }
+    }
+
+    /// Whether the `let` statement starting with the next token is a declaration without a
+    /// value, i.e. `let x: (i32) -> i32;`
+    ///
+    /// Types have neither `=` nor `;` inside of them, so a declaration reaches its `;` before
+    /// any `=`
+    fn is_declaration(&self) -> bool {
+        self.tokens[self.token_pos..]
+            .iter()
+            .map(|token| token.token)
+            .find(|token| {
+                matches!(
+                    token,
+                    lexer::TokenType::Semi | lexer::TokenType::Equals | lexer::TokenType::EOF
+                )
+            })
+            == Some(lexer::TokenType::Semi)
+    }
+
+    /// Token after the namespace that starts `n` tokens after the next one, i.e. the `(` of
+    /// `core::fmt::print(`
+    fn after_namespace(&self, n: usize) -> lexer::TokenType {
+        let mut idx = n;
+        while self.peek_nth(idx + 1).token == lexer::TokenType::DoubleColon
+            && self.peek_nth(idx + 2).token == lexer::TokenType::Identifier
+        {
+            idx += 2;
+        }
+        self.peek_nth(idx + 1).token
+    }
+
+    /// Token after the parentheses that start at the next token, i.e. the `->` of
+    /// `(i32, (i32,)) -> i32`
+    fn after_parens(&self) -> lexer::TokenType {
+        let mut depth: usize = 0;
+        for (idx, token) in self.tokens[self.token_pos..].iter().enumerate() {
+            match token.token {
+                lexer::TokenType::LP => depth += 1,
+                lexer::TokenType::RP if depth <= 1 => return self.peek_nth(idx + 1).token,
+                lexer::TokenType::RP => depth -= 1,
+                _ => {}
+            }
+        }
+        lexer::TokenType::EOF
+    }
+
+    /// Whether the `(` of the next token starts a function, i.e. `(x: i64) -> i64 {` or `() {`
+    fn is_function(&self) -> bool {
+        match self.peek_nth(1).token {
+            lexer::TokenType::RP => matches!(
+                self.peek_nth(2).token,
+                lexer::TokenType::Arrow | lexer::TokenType::LCP
+            ),
+            lexer::TokenType::Identifier => self.after_namespace(1) == lexer::TokenType::Colon,
+            _ => false,
+        }
+    }
+
+    /// Skip the rest of a statement that failed to parse, up to the `;` that ends it or the
+    /// `}` of the block around it (panic mode)
+    fn synchronize(&mut self) {
+        // Blocks opened inside of the statement, parentheses are left out as a missing `)`
+        // shouldn't hide the end of a block
+        let mut depth: usize = 0;
+
+        loop {
+            let token = self.peek().token;
+            match token {
+                lexer::TokenType::EOF => return,
+                lexer::TokenType::RCP if depth == 0 => return,
+                lexer::TokenType::Semi if depth == 0 => {
+                    self.forward();
+                    return;
+                }
+                lexer::TokenType::LCP => depth += 1,
+                lexer::TokenType::RCP => depth -= 1,
+                _ => {}
+            }
+            self.forward();
+
+            // A block ends the statement, unless the statement goes on after it (i.e. `} else {`)
+            if token == lexer::TokenType::RCP && depth == 0 {
+                match self.peek().token {
+                    lexer::TokenType::Semi => {
+                        self.forward();
+                        return;
+                    }
+                    lexer::TokenType::Else => {}
+                    _ => return,
+                }
+            }
+        }
+    }
+
+    /// First error recovered from while parsing a single statement or expression
+    fn recovered_error(&mut self) -> Result<(), ErrorValue> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors.remove(0).into())
+        }
+    }
+
+    /// Parse a single statement in any scope, all the tokens have to be used
+    pub fn statement(&mut self) -> Result<Statement, ErrorValue> {
+        self.fill_token_stream()?;
+
+        let stmt = self.scoped_statement(&Scope::All)?;
+        self.expect_eof()?;
+        self.recovered_error()?;
+        Ok(stmt)
+    }
+
+    /// Parse a single expression, all the tokens have to be used
+    pub fn expression(&mut self) -> Result<Expr, ErrorValue> {
+        self.fill_token_stream()?;
+
+        let expr = self.expr(Prec::LOWEST)?;
+        self.expect_eof()?;
+        self.recovered_error()?;
+        Ok(expr)
+    }
+
+    fn expect_eof(&mut self) -> Result<(), ErrorValue> {
+        let position = self.token_pos;
+        self.next(lexer::TokenType::EOF, position, false)?;
+        Ok(())
+    }
+
     /// Parse basic block
     pub fn block(&mut self, scope: Scope) -> Result<ast::Block, ErrorGen> {
         let position = self.token_pos;
@@ -334,54 +615,21 @@ impl Parser {
 
         let mut ast_list: Vec<Statement> = Vec::new();
         loop {
-            let mut errors: Vec<ErrorGen> = Vec::new();
-            let mut fail = true;
-            for i in 0..self.statements.len() {
-                let statement_ast = self.statements[i](self);
-                match statement_ast {
-                    Ok(ast_production) => {
-                        if ast_production.in_scope(&scope) {
-                            fail = false;
-                            errors.clear();
-                            ast_list.push(ast_production);
-                            break;
-                        } else {
-                            let ast_pos = ast_production.pos();
-                            errors.push(ErrorGen::new(
-                                Box::new(move || {
-                                    ErrorValue::new(
-                                        "unexpected statement in inner scope".to_string(),
-                                        ErrorType::Syntax,
-                                        ast_pos,
-                                        ErrorDisplayType::Error,
-                                        vec![ErrorAnnotation::new(
-                                            Some("unexpected statement".to_string()),
-                                            ast_pos,
-                                            ErrorDisplayType::Error,
-                                        )],
-                                    )
-                                }),
-                                ast_pos,
-                                true,
-                            ));
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        errors.push(e);
-                    }
-                }
+            match self.peek().token {
+                // The missing `}` is raised below
+                lexer::TokenType::RCP | lexer::TokenType::EOF => break,
+                _ => {}
             }
 
-            if self.peek().token == lexer::TokenType::RCP {
-                // We've successfully parsed, break
-                break;
-            } else if !errors.is_empty() && fail {
-                // We've found an error, return the error
-                return Err(LoggerInner::longest(errors));
-            } else if self.peek().token != lexer::TokenType::RCP && fail {
-                // We've forgotten closing brace
-                break;
+            let statement_pos = self.token_pos;
+            match self.scoped_statement(&scope) {
+                Ok(ast_production) => ast_list.push(ast_production),
+                Err(e) => {
+                    // Keep parsing the block after the statement
+                    self.errors.push(e);
+                    self.set_pos(statement_pos);
+                    self.synchronize();
+                }
             }
         }
 
@@ -419,6 +667,7 @@ impl Parser {
             value,
             name: Rc::new(name),
             visibility,
+            docs: self.docs(position),
             pos: self.get_relative_pos(position),
         }))
     }
@@ -428,11 +677,12 @@ impl Parser {
         self.next(lexer::TokenType::Unit, position, true)?;
         let name = self.namespace()?;
 
-        let block = self.block(Scope::Outer)?;
+        let block = self.nested(|parser| parser.block(Scope::Outer))?;
 
         Ok(Statement::Unit(ast::Unit {
             name,
             block: block.nodes,
+            docs: self.docs(position),
             pos: self.get_relative_pos(position),
         }))
     }
@@ -505,6 +755,7 @@ impl Parser {
                 .insert_file(import_path, file_contents);
             let mut parser =
                 Parser::new(file_id, Rc::clone(&self.logger), Rc::clone(&self.sourcemap));
+            parser.max_depth = self.max_depth;
             parser.initialize_expr();
             match parser.parse() {
                 Ok(_) => {}
@@ -519,6 +770,7 @@ impl Parser {
                 pos: last.pos,
                 name: last.into_namespace(),
                 block: parser.ast.unwrap(),
+                docs: None,
             }))
         } else {
             let name_pos = name.pos;
@@ -761,6 +1013,11 @@ impl Parser {
 
         self.next(lexer::TokenType::Let, position, true)?;
 
+        let mutable = self.peek().token == lexer::TokenType::Mut;
+        if mutable {
+            self.forward();
+        }
+
         let namespace = self.namespace()?;
 
         let var_type = if lexer::TokenType::Colon == self.peek().token {
@@ -781,7 +1038,9 @@ impl Parser {
                 ty: var_type,
                 name: Rc::new(namespace),
                 expr: Box::new(expr),
+                mutable,
                 visibility,
+                docs: self.docs(position),
                 pos: self.get_relative_pos(position),
             },
         ))
@@ -825,23 +1084,30 @@ impl Parser {
                 is_extern,
                 ty: var_type,
                 name: Rc::new(namespace),
+                docs: self.docs(position),
                 pos: self.get_relative_pos(position),
             },
         ))
     }
 
-    /// Variable assign with only expression
+    /// Variable assign with only expression, i.e. `x = 10` or `x += 10`
     fn variable_assign(&mut self) -> Result<Expr, ErrorGen> {
         let position = self.token_pos;
 
         let namespace = self.namespace()?;
 
-        self.next(lexer::TokenType::Equals, position, false)?;
+        let operator = self.peek().token.assign_operator();
+        if operator.is_some() {
+            self.forward();
+        } else {
+            self.next(lexer::TokenType::Equals, position, false)?;
+        }
 
         let expr = self.expr(Prec::LOWEST)?;
 
         Ok(Expr::VariableAssign(ast::VariableAssign {
             name: Rc::new(namespace),
+            operator,
             expr: Box::new(expr),
             pos: self.get_relative_pos(position),
         }))
@@ -917,22 +1183,294 @@ impl Parser {
         })
     }
 
-    /// Top level expression
-    fn expr(&mut self, prec: Prec) -> Result<Expr, ErrorGen> {
-        let mut left = self.item()?;
-        let mut operator;
+    /// `loop`, `while` or `for` loop, with an optional label
+    fn loop_expr(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        let label = match self.peek().token {
+            lexer::TokenType::Label => {
+                let label = self.label()?;
+                self.next(lexer::TokenType::Colon, position, false)?;
+                Some(label)
+            }
+            _ => None,
+        };
+
+        let kind = match self.peek().token {
+            lexer::TokenType::Loop => {
+                self.forward();
+                ast::LoopKind::Loop
+            }
+            lexer::TokenType::While => {
+                self.forward();
+                ast::LoopKind::While(Box::new(self.expr(Prec::LOWEST)?))
+            }
+            _ => {
+                self.next(lexer::TokenType::For, position, true)?;
+                let name = ast::Namespace::from_name_id(self.name_id()?);
+                self.next(lexer::TokenType::In, position, false)?;
+                let range = self.expr(Prec::LOWEST)?;
+
+                ast::LoopKind::For {
+                    name,
+                    range: Box::new(range),
+                }
+            }
+        };
+
+        let block = self.block(Scope::Block)?;
+
+        Ok(Expr::Loop(ast::Loop {
+            label,
+            kind,
+            block,
+            pos: self.get_relative_pos(position),
+        }))
+    }
+
+    fn break_expr(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        self.next(lexer::TokenType::Break, position, true)?;
+        let label = self.optional_label()?;
+
+        // The value is optional, i.e. `break;`
+        let expression = match self.peek().token {
+            lexer::TokenType::Semi
+            | lexer::TokenType::RCP
+            | lexer::TokenType::RP
+            | lexer::TokenType::Comma
+            | lexer::TokenType::EOF => None,
+            _ => Some(Box::new(self.expr(Prec::LOWEST)?)),
+        };
+
+        Ok(Expr::Break(ast::Break {
+            label,
+            expression,
+            pos: self.get_relative_pos(position),
+        }))
+    }
+
+    fn continue_expr(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        self.next(lexer::TokenType::Continue, position, true)?;
+
+        Ok(Expr::Continue(ast::Continue {
+            label: self.optional_label()?,
+            pos: self.get_relative_pos(position),
+        }))
+    }
 
+    fn optional_label(&mut self) -> Result<Option<ast::NameID>, ErrorGen> {
+        match self.peek().token {
+            lexer::TokenType::Label => Ok(Some(self.label()?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn label(&mut self) -> Result<ast::NameID, ErrorGen> {
+        let position = self.token_pos;
+        let pos = self.next(lexer::TokenType::Label, position, false)?;
+        Ok(ast::NameID {
+            sourcemap: Rc::clone(&self.sourcemap),
+            pos,
+        })
+    }
+
+    /// `match` expression, the comma after an arm is optional when it ends with a block
+    fn match_expr(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        self.next(lexer::TokenType::Match, position, true)?;
+        let expr = self.expr(Prec::LOWEST)?;
+        self.next(lexer::TokenType::LCP, position, false)?;
+
+        let mut arms = Vec::new();
         loop {
-            match self.get_operator_infix() {
-                Ok(op) => {
-                    operator = op;
+            match self.peek().token {
+                // The missing `}` is raised below
+                lexer::TokenType::RCP | lexer::TokenType::EOF => break,
+                _ => {}
+            }
+
+            let arm = self.match_arm()?;
+            match self.peek().token {
+                lexer::TokenType::Comma => {
+                    self.forward();
+                }
+                lexer::TokenType::RCP => {}
+                _ if matches!(arm.expr, Expr::Block(_)) => {}
+                _ => {
+                    self.next(lexer::TokenType::Comma, position, false)?;
                 }
-                Err(_) => {
-                    return Ok(left);
+            }
+            arms.push(arm);
+        }
+
+        self.next(lexer::TokenType::RCP, position, false)?;
+
+        Ok(Expr::Match(ast::Match {
+            expr: Box::new(expr),
+            arms,
+            pos: self.get_relative_pos(position),
+        }))
+    }
+
+    /// `pattern => expr` with an optional guard, i.e. `x if x > 0 => x`
+    fn match_arm(&mut self) -> Result<ast::MatchArm, ErrorGen> {
+        let position = self.token_pos;
+
+        let pattern = self.pattern()?;
+        let guard = match self.peek().token {
+            lexer::TokenType::If => {
+                self.forward();
+                Some(self.expr(Prec::LOWEST)?)
+            }
+            _ => None,
+        };
+        self.next(lexer::TokenType::FatArrow, position, false)?;
+        let expr = self.expr(Prec::LOWEST)?;
+
+        Ok(ast::MatchArm {
+            pattern,
+            guard,
+            expr,
+            pos: self.get_relative_pos(position),
+        })
+    }
+
+    fn pattern(&mut self) -> Result<ast::Pattern, ErrorGen> {
+        self.nested(|parser| parser.pattern_single())
+    }
+
+    fn pattern_single(&mut self) -> Result<ast::Pattern, ErrorGen> {
+        let position = self.token_pos;
+
+        let kind = match self.peek().token {
+            lexer::TokenType::Underscore => {
+                self.forward();
+                ast::PatternKind::Wildcard
+            }
+            lexer::TokenType::Identifier => {
+                ast::PatternKind::Binding(ast::Namespace::from_name_id(self.name_id()?))
+            }
+            lexer::TokenType::LP => return self.tuple_pattern(),
+            _ => {
+                let start = self.pattern_literal()?;
+                match self.peek().token {
+                    token @ (lexer::TokenType::DoubleDot | lexer::TokenType::DoubleDotEq) => {
+                        self.forward();
+                        ast::PatternKind::Range {
+                            start: Box::new(start),
+                            end: Box::new(self.pattern_literal()?),
+                            inclusive: token == lexer::TokenType::DoubleDotEq,
+                        }
+                    }
+                    _ => ast::PatternKind::Literal(Box::new(start)),
                 }
             }
-            let binding_power = self.binding_power(&operator.token) as u8;
+        };
+
+        Ok(ast::Pattern {
+            kind,
+            pos: self.get_relative_pos(position),
+        })
+    }
+
+    /// Pattern in parentheses or a tuple pattern, i.e. `(x)` or `(x, _)`
+    fn tuple_pattern(&mut self) -> Result<ast::Pattern, ErrorGen> {
+        let position = self.token_pos;
+        self.next(lexer::TokenType::LP, position, false)?;
 
+        let mut patterns = Vec::new();
+        let mut trailing_comma = false;
+        while self.peek().token != lexer::TokenType::RP {
+            patterns.push(self.pattern()?);
+            trailing_comma = self.peek().token == lexer::TokenType::Comma;
+            if trailing_comma {
+                self.forward();
+            } else {
+                break;
+            }
+        }
+        self.next(lexer::TokenType::RP, position, false)?;
+
+        if patterns.len() == 1 && !trailing_comma {
+            return Ok(patterns.pop().unwrap());
+        }
+
+        Ok(ast::Pattern {
+            kind: ast::PatternKind::Tuple(patterns),
+            pos: self.get_relative_pos(position),
+        })
+    }
+
+    /// Literal of a pattern, only integers can be negated
+    fn pattern_literal(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        match self.peek().token {
+            lexer::TokenType::Char => self.char_literal(),
+            lexer::TokenType::True | lexer::TokenType::False => self.bool_expr(),
+            lexer::TokenType::Sub => {
+                let operator = self.forward();
+                let val = self.integer()?;
+                Ok(Expr::Prefix(ast::Prefix {
+                    val: Box::new(val),
+                    operator,
+                    pos: self.get_relative_pos(position),
+                }))
+            }
+            _ => self.integer(),
+        }
+    }
+
+    /// Top level expression
+    fn expr(&mut self, prec: Prec) -> Result<Expr, ErrorGen> {
+        self.nested(|parser| parser.infix_expr(prec))
+    }
+
+    /// Parse with one more level of nesting
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ErrorGen>,
+    ) -> Result<T, ErrorGen> {
+        if self.depth >= self.max_depth {
+            let pos = self.peek().pos;
+            let max_depth = self.max_depth;
+            return Err(ErrorGen::new(
+                Box::new(move || {
+                    ErrorValue::new(
+                        "nesting is too deep".to_string(),
+                        ErrorType::Syntax,
+                        pos,
+                        ErrorDisplayType::Error,
+                        vec![ErrorAnnotation::new(
+                            Some(format!("more than {} levels of nesting", max_depth)),
+                            pos,
+                            ErrorDisplayType::Error,
+                        )],
+                    )
+                }),
+                pos,
+                true,
+            ));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Expression with its infix operators
+    fn infix_expr(&mut self, prec: Prec) -> Result<Expr, ErrorGen> {
+        let mut left = self.item()?;
+
+        // Operators binding no tighter than `prec` are left to the outer expression, so that
+        // `a - b - c` is `(a - b) - c`
+        while let Some(operator) = self.get_operator_infix(prec) {
             match self.led(left, operator) {
                 Ok(val) => {
                     left = val;
@@ -945,66 +1483,32 @@ impl Parser {
                     return Err(e);
                 }
             }
-            if binding_power > prec as u8 {
-                break;
-            }
         }
 
         Ok(left)
     }
 
-    fn get_operator_infix(&mut self) -> Result<lexer::Token, ErrorGen> {
-        let position = self.token_pos;
-        let potential_op = self.forward();
-
-        if self.infix_op.get(&potential_op.token).is_some() {
-            return Ok(potential_op);
+    /// Next token if it is an infix operator binding tighter than `prec`
+    fn get_operator_infix(&mut self, prec: Prec) -> Option<lexer::Token> {
+        let potential_op = self.peek();
+        match self.infix_op.get(&potential_op.token) {
+            Some(binding_power) if *binding_power > prec => {
+                self.forward();
+                Some(potential_op)
+            }
+            _ => None,
         }
-
-        let pos = self.get_relative_pos(position);
-        let temp = Err(ErrorGen::new(
-            Box::new(move || {
-                ErrorValue::new(
-                    "Expected an operator".to_string(),
-                    ErrorType::Syntax,
-                    pos,
-                    ErrorDisplayType::Error,
-                    vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
-                )
-            }),
-            pos,
-            false,
-        ));
-
-        self.set_pos(position);
-        temp
     }
 
-    fn get_operator_prefix(&mut self) -> Result<lexer::Token, ErrorGen> {
-        let position = self.token_pos;
-        let potential_op = self.forward();
-        if self.prefix_op.get(&potential_op.token).is_some() {
-            return Ok(potential_op);
+    /// Next token if it is a prefix operator
+    fn get_operator_prefix(&mut self) -> Option<lexer::Token> {
+        let potential_op = self.peek();
+        if self.prefix_op.contains_key(&potential_op.token) {
+            self.forward();
+            Some(potential_op)
+        } else {
+            None
         }
-
-        let pos = self.get_relative_pos(position);
-
-        let temp = Err(ErrorGen::new(
-            Box::new(move || {
-                ErrorValue::new(
-                    "Expected a prefix".to_string(),
-                    ErrorType::Syntax,
-                    pos,
-                    ErrorDisplayType::Error,
-                    vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
-                )
-            }),
-            pos,
-            false,
-        ));
-
-        self.set_pos(position);
-        temp
     }
 
     #[inline]
@@ -1014,35 +1518,52 @@ impl Parser {
 
     fn led(&mut self, left: Expr, operator: lexer::Token) -> Result<Expr, ErrorGen> {
         let position = self.token_pos;
+        let start = left.pos().s;
 
         match operator.token {
             lexer::TokenType::As => Ok(Expr::As(ast::AsExpr {
                 expr: Box::new(left),
                 ty: self.type_expr()?,
-                pos: self.get_relative_pos(position),
+                pos: self.infix_pos(start, position),
             })),
             lexer::TokenType::Is => Ok(Expr::Is(ast::IsExpr {
                 expr: Box::new(left),
                 ty: self.type_expr()?,
-                pos: self.get_relative_pos(position),
+                pos: self.infix_pos(start, position),
             })),
+            lexer::TokenType::DoubleDot | lexer::TokenType::DoubleDotEq => {
+                Ok(Expr::Range(ast::Range {
+                    start: Box::new(left),
+                    end: Box::new(self.expr(Prec::RANGE)?),
+                    inclusive: operator.token == lexer::TokenType::DoubleDotEq,
+                    pos: self.infix_pos(start, position),
+                }))
+            }
             _ => {
                 let bp = self.binding_power(&operator.token);
                 Ok(Expr::Infix(ast::Infix {
                     left: Box::new(left),
                     operator,
                     right: Box::new(self.expr(bp)?),
-                    pos: self.get_relative_pos(position),
+                    pos: self.infix_pos(start, position),
                 }))
             }
         }
     }
 
+    /// Position from the start of the left operand to the last token
+    fn infix_pos(&mut self, start: usize, position: usize) -> helpers::Pos {
+        helpers::Pos {
+            s: start,
+            ..self.get_relative_pos(position)
+        }
+    }
+
     fn item(&mut self) -> Result<Expr, ErrorGen> {
         let position = self.token_pos;
 
-        if let Ok(prefix) = self.get_operator_prefix() {
-            let bp = self.binding_power(&prefix.token);
+        if let Some(prefix) = self.get_operator_prefix() {
+            let bp = self.prefix_op[&prefix.token];
             let item = self.expr(bp)?;
             return Ok(Expr::Prefix(ast::Prefix {
                 operator: prefix,
@@ -1055,35 +1576,40 @@ impl Parser {
     }
 
     fn item_single(&mut self, position: usize) -> Result<Expr, ErrorGen> {
-        run_all! {
-            self,
-            Parser::integer,
-            Parser::string_literal,
-            Parser::bool_expr,
-            Parser::function_call,
-            Parser::variable_assign_full,
-            Parser::variable_assign,
-            Parser::function_expr,
-            Parser::tuple_expr,
-            Parser::dollar_expr,
-            Parser::ref_expr,
-            Parser::conditional,
-            Parser::return_expr,
-            Parser::yield_expr
-        };
-
-        match self.block(Scope::Block) {
-            Ok(block) => return Ok(Expr::Block(block)),
-            Err(_) => {}
-        }
-
-        if let lexer::TokenType::LP = self.forward().token {
-            let expr = self.expr(Prec::LOWEST)?;
-            if let lexer::TokenType::RP = self.forward().token {
-                return Ok(expr);
+        match self.peek().token {
+            lexer::TokenType::Number => return self.integer(),
+            lexer::TokenType::Float => return self.float(),
+            lexer::TokenType::String => return self.string_literal(),
+            lexer::TokenType::Char => return self.char_literal(),
+            lexer::TokenType::True | lexer::TokenType::False => return self.bool_expr(),
+            lexer::TokenType::Identifier => {
+                return match self.after_namespace(0) {
+                    lexer::TokenType::LP => self.function_call(),
+                    lexer::TokenType::Equals
+                    | lexer::TokenType::AddEq
+                    | lexer::TokenType::SubEq
+                    | lexer::TokenType::MulEq
+                    | lexer::TokenType::DivEq
+                    | lexer::TokenType::ModEq => self.variable_assign(),
+                    _ => self.ref_expr(),
+                }
             }
-            let next_tok = self.peek();
-            return Err(self.syntax_error(next_tok, lexer::TokenType::RP, false, false));
+            lexer::TokenType::Public | lexer::TokenType::Let => return self.variable_assign_full(),
+            lexer::TokenType::LP if self.is_function() => return self.function_expr(),
+            lexer::TokenType::LP => return self.paren_expr(),
+            lexer::TokenType::Dollar => return self.dollar_expr(),
+            lexer::TokenType::If => return self.conditional(),
+            lexer::TokenType::Loop
+            | lexer::TokenType::While
+            | lexer::TokenType::For
+            | lexer::TokenType::Label => return self.loop_expr(),
+            lexer::TokenType::Break => return self.break_expr(),
+            lexer::TokenType::Continue => return self.continue_expr(),
+            lexer::TokenType::Match => return self.match_expr(),
+            lexer::TokenType::Return => return self.return_expr(),
+            lexer::TokenType::Yield => return self.yield_expr(),
+            lexer::TokenType::LCP => return Ok(Expr::Block(self.block(Scope::Block)?)),
+            _ => {}
         }
 
         let next = self.peek();
@@ -1124,6 +1650,8 @@ impl Parser {
         {
             Ok(Expr::Literal(ast::Literal {
                 literal_type: LiteralType::Bool,
+                suffix: None,
+                value: None,
                 pos: possible_bool.pos,
             }))
         } else {
@@ -1140,6 +1668,8 @@ impl Parser {
         if lexer::TokenType::Number == int.token {
             Ok(Expr::Literal(ast::Literal {
                 literal_type: LiteralType::Number,
+                suffix: self.number_suffix(int.pos),
+                value: None,
                 pos: int.pos,
             }))
         } else {
@@ -1149,6 +1679,36 @@ impl Parser {
         }
     }
 
+    fn float(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        let float = self.forward();
+        if lexer::TokenType::Float == float.token {
+            Ok(Expr::Literal(ast::Literal {
+                literal_type: LiteralType::Float,
+                suffix: self.number_suffix(float.pos),
+                value: None,
+                pos: float.pos,
+            }))
+        } else {
+            let temp = Err(self.syntax_error(float, lexer::TokenType::Float, false, false));
+            self.set_pos(position);
+            temp
+        }
+    }
+
+    /// Type suffix of a number token, the lexer already checked it is valid
+    fn number_suffix(&self, pos: helpers::Pos) -> Option<Prim> {
+        let number = get_segment!(self.sourcemap, pos).to_string();
+        Prim::from_name(lexer::NumberParts::new(&number).suffix)
+    }
+
+    /// Decoded contents of a string or char token, the lexer already checked its escapes
+    fn string_value(&self, pos: helpers::Pos) -> String {
+        let string = get_segment!(self.sourcemap, pos).to_string();
+        lexer::unescape(&string[1..string.len() - 1]).unwrap_or_default()
+    }
+
     fn string_literal(&mut self) -> Result<Expr, ErrorGen> {
         let position = self.token_pos;
 
@@ -1157,6 +1717,8 @@ impl Parser {
             Ok(Expr::Literal(ast::Literal {
                 pos: string.pos,
                 literal_type: LiteralType::String,
+                suffix: None,
+                value: Some(self.string_value(string.pos)),
             }))
         } else {
             let temp = Err(self.syntax_error(string, lexer::TokenType::String, false, false));
@@ -1165,6 +1727,24 @@ impl Parser {
         }
     }
 
+    fn char_literal(&mut self) -> Result<Expr, ErrorGen> {
+        let position = self.token_pos;
+
+        let character = self.forward();
+        if lexer::TokenType::Char == character.token {
+            Ok(Expr::Literal(ast::Literal {
+                pos: character.pos,
+                literal_type: LiteralType::Char,
+                suffix: None,
+                value: Some(self.string_value(character.pos)),
+            }))
+        } else {
+            let temp = Err(self.syntax_error(character, lexer::TokenType::Char, false, false));
+            self.set_pos(position);
+            temp
+        }
+    }
+
     fn namespace(&mut self) -> Result<ast::Namespace, ErrorGen> {
         let position = self.token_pos;
         let mut ids: Vec<ast::NameID> = Vec::new();
@@ -1225,16 +1805,33 @@ impl Parser {
         })
     }
 
-    fn tuple_expr(&mut self) -> Result<Expr, ErrorGen> {
-        Ok(Expr::Tuple(self.tuple()?))
-    }
-
-    fn tuple(&mut self) -> Result<ast::Tuple, ErrorGen> {
+    /// Expression in parentheses or a tuple, i.e. `(1 + 2)` or `(1, 2)`
+    fn paren_expr(&mut self) -> Result<Expr, ErrorGen> {
         let position = self.token_pos;
         self.next(lexer::TokenType::LP, position, false)?;
 
-        let values = match self.items() {
-            Ok(items) => {
+        match self.first_item()? {
+            Some(expr) if self.peek().token == lexer::TokenType::RP => {
+                self.forward();
+                Ok(expr)
+            }
+            first => Ok(Expr::Tuple(self.tuple_rest(position, first)?)),
+        }
+    }
+
+    /// First item after a `(`, there is none in `()` and `(,)`
+    fn first_item(&mut self) -> Result<Option<Expr>, ErrorGen> {
+        match self.peek().token {
+            lexer::TokenType::RP | lexer::TokenType::Comma => Ok(None),
+            _ => Ok(Some(self.expr(Prec::LOWEST)?)),
+        }
+    }
+
+    /// Items of a tuple after the first one
+    fn tuple_rest(&mut self, position: usize, first: Option<Expr>) -> Result<ast::Tuple, ErrorGen> {
+        let values = match first {
+            Some(first) => {
+                let items = self.items(first);
                 if items.len() == 1 {
                     // Required trailing comma
                     self.next(lexer::TokenType::Comma, position, false)?;
@@ -1246,7 +1843,7 @@ impl Parser {
                 }
                 items
             }
-            Err(_) => {
+            None => {
                 // Optional trailing comma
                 if let lexer::TokenType::Comma = self.peek().token {
                     self.forward();
@@ -1263,11 +1860,8 @@ impl Parser {
         })
     }
 
-    fn items(&mut self) -> Result<Vec<Expr>, ErrorGen> {
-        let mut items: Vec<Expr> = Vec::new();
-
-        let expr = self.expr(Prec::LOWEST)?;
-        items.push(expr);
+    fn items(&mut self, first: Expr) -> Vec<Expr> {
+        let mut items: Vec<Expr> = vec![first];
 
         loop {
             let position = self.token_pos;
@@ -1284,7 +1878,7 @@ impl Parser {
             }
         }
 
-        Ok(items)
+        items
     }
 
     fn function_type(&mut self) -> Result<ast::Type, ErrorGen> {
@@ -1375,11 +1969,16 @@ impl Parser {
                     // Required trailing comma
                     if let Err(why) = self.next(lexer::TokenType::Comma, position, false) {
                         let pos = *&why.position;
-                        return Err(ErrorGen::new(Box::new(move || {
-                            why.mk_err().with_note(
-                                "help: if you meant to make a tuple type, add a comma".to_string(),
-                            )
-                        }), pos, true));
+                        return Err(ErrorGen::new(
+                            Box::new(move || {
+                                why.mk_err().with_note(
+                                    "help: if you meant to make a tuple type, add a comma"
+                                        .to_string(),
+                                )
+                            }),
+                            pos,
+                            true,
+                        ));
                     }
                 } else {
                     // Optional trailing comma
@@ -1431,14 +2030,20 @@ impl Parser {
 
     /// Parse type expression
     fn type_expr(&mut self) -> Result<ast::Type, ErrorGen> {
+        self.nested(Parser::type_single)
+    }
+
+    fn type_single(&mut self) -> Result<ast::Type, ErrorGen> {
         let position = self.token_pos;
 
-        run_all! {
-            self,
-            Parser::namespace_type,
-            Parser::underscore_type,
-            Parser::function_type,
-            Parser::tuple_type
+        match self.peek().token {
+            lexer::TokenType::Identifier => return self.namespace_type(),
+            lexer::TokenType::Underscore => return self.underscore_type(),
+            lexer::TokenType::LP if self.after_parens() == lexer::TokenType::Arrow => {
+                return self.function_type()
+            }
+            lexer::TokenType::LP => return self.tuple_type(),
+            _ => {}
         }
 
         let pos = self.get_relative_pos(position);
@@ -1491,6 +2096,7 @@ impl Parser {
 #[cfg(test)]
 pub mod parser_tests {
     use super::*;
+    use crate::logger::LoggerInner;
     use crate::sourcemap::SourceMapInner;
     const FILENAME: &str = "a_really_long_parser_filename_for_this_test.fl";
 
@@ -1532,9 +2138,9 @@ pub mod parser_tests {
     );
     parser_run!("awd::a12_12a", Parser::namespace_type, namespace_type_2);
 
-    parser_run!("()", Parser::tuple, tuple_1);
-    parser_run!("(,)", Parser::tuple, tuple_2);
-    parser_run!("(120, 123, \"ad\")", Parser::tuple, tuple_3);
+    parser_run!("()", Parser::paren_expr, tuple_1);
+    parser_run!("(,)", Parser::paren_expr, tuple_2);
+    parser_run!("(120, 123, \"ad\")", Parser::paren_expr, tuple_3);
 
     parser_run!("test12_1249", Parser::ref_id, ref_id);
     parser_run!("a123ajd_test_test", Parser::ref_expr, ref_expr);
@@ -1558,6 +2164,9 @@ pub mod parser_tests {
     parser_run!("12930", Parser::integer, integer_literal_1);
     parser_run!("1", Parser::integer, integer_literal_2);
 
+    parser_run!("1.5", Parser::float, float_literal_1);
+    parser_run!("2e10", Parser::float, float_literal_2);
+
     parser_run!("true", Parser::bool_expr, bool_true);
     parser_run!("false", Parser::bool_expr, bool_false);
 
@@ -1569,6 +2178,48 @@ pub mod parser_tests {
     );
     parser_run!("if  true  { }", Parser::conditional, conditional_3);
 
+    parser_run!("loop { break; }", Parser::loop_expr, loop_1);
+    parser_run!(
+        "'outer: while x < 10 { continue 'outer; }",
+        Parser::loop_expr,
+        loop_2
+    );
+    parser_run!(
+        "for i in 0..n + 1 { 'inner: loop { break 'inner i; }; }",
+        Parser::expression,
+        loop_3
+    );
+    parser_run!(
+        "for i in start..=end { }",
+        Parser::expression,
+        loop_inclusive
+    );
+    parser_run!("let r = (a + 1)..b * 2;", Parser::statement, range_expr);
+    parser_run!("let mut x = 10;", Parser::statement, let_mut);
+    parser_run!("x -= y * 2;", Parser::statement, compound_assign);
+
+    parser_run!(
+        "match n { 0 => 1, -1 => 2, 'a'..'z' => 3, 'z'..='~' => 4, _ => 5 }",
+        Parser::match_expr,
+        match_1
+    );
+    parser_run!(
+        "match (x, (y,)) { (true, _) if y > 0 => { yield 1; } (false, (z,)) => z, () => 0, }",
+        Parser::expression,
+        match_2
+    );
+    parser_run!("return ()", Parser::expression, return_unit);
+    parser_run!(
+        "((1 + 2) * 3, (x,), () {}, (x: i64, y: (i64,)) -> (i64) -> i64 { return f; })",
+        Parser::expression,
+        paren_tuple_function
+    );
+    parser_run!(
+        "a::b(c) + a::b + (a::b = c)",
+        Parser::expression,
+        namespace_dispatch
+    );
+
     parser_run!("x = 10", Parser::variable_assign, variable_assign);
     parser_run!(
         "let x: (int, int) = 10",
@@ -1643,6 +2294,8 @@ pub mod parser_tests {
     parser_run!("\"\"", Parser::item, string_literal_2_item);
     parser_run!("(\"dab\")", Parser::item, string_literal_1_paren);
     parser_run!("(\"\")", Parser::item, string_literal_2_paren);
+    parser_run!("'a'", Parser::item, char_literal_1_item);
+    parser_run!("('\\n')", Parser::item, char_literal_2_paren);
 
     parser_run!("$hello", Parser::item, dollar_expr_item);
     parser_run!("($hello)", Parser::item, dollar_expr_paren);
@@ -1801,6 +2454,364 @@ pub mod parser_tests {
         mssing_comma_function_call_2
     );
 
+    parser_err!("match x { _ 1 }", Parser::match_expr, match_missing_arrow);
+
+    parser_err!(
+        "match x { 1.5 => 1 }",
+        Parser::match_expr,
+        match_float_pattern
+    );
+
+    #[test]
+    fn doc_comments() -> Result<(), ErrorValue> {
+        let sourcemap = SourceMapInner::new();
+        let filename_code = insert_file!(
+            sourcemap,
+            path::PathBuf::from(FILENAME),
+            "--- Distance\n/** in kilometres */\ntype km = i32;".to_string()
+        );
+        let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+        let mut parser = Parser::new(filename_code, logger, sourcemap);
+        parser.initialize_expr();
+        parser.fill_token_stream()?;
+        match parser.type_assign()? {
+            Statement::TypeAssign(type_assign) => {
+                assert_eq!(type_assign.docs.as_deref(), Some("Distance\nin kilometres"))
+            }
+            _ => panic!("Not a type assign"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn operator_precedence() -> Result<(), ErrorValue> {
+        // Each operation wrapped in parentheses
+        fn group(expr: &Expr, code: &str) -> String {
+            let text = |pos: helpers::Pos| code[pos.s..pos.e].to_string();
+            match expr {
+                Expr::Infix(infix) => format!(
+                    "({} {} {})",
+                    group(&infix.left, code),
+                    text(infix.operator.pos),
+                    group(&infix.right, code)
+                ),
+                Expr::Prefix(prefix) => {
+                    format!(
+                        "({}{})",
+                        text(prefix.operator.pos),
+                        group(&prefix.val, code)
+                    )
+                }
+                Expr::Range(range) => format!(
+                    "({}{}{})",
+                    group(&range.start, code),
+                    if range.inclusive { "..=" } else { ".." },
+                    group(&range.end, code)
+                ),
+                expr => text(expr.pos()),
+            }
+        }
+
+        for (code, grouped) in vec![
+            ("1 * 2 + 3", "((1 * 2) + 3)"),
+            ("1 - 2 - 3", "((1 - 2) - 3)"),
+            ("n - 1..m", "((n - 1)..m)"),
+            ("-x * y as i64", "((-x) * y as i64)"),
+            ("a || b && !c == d", "(a || (b && ((!c) == d)))"),
+            ("x != 0 && y < 10 || z", "(((x != 0) && (y < 10)) || z)"),
+            ("a | b ^ c & d", "(a | (b ^ (c & d)))"),
+            ("a & 1 << n + 1 == b", "(a & ((1 << (n + 1)) == b))"),
+            ("~a >> 2", "((~a) >> 2)"),
+        ] {
+            let sourcemap = SourceMapInner::new();
+            let filename_code =
+                insert_file!(sourcemap, path::PathBuf::from(FILENAME), code.to_string());
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+            let mut parser = Parser::new(filename_code, logger, sourcemap);
+            parser.initialize_expr();
+            parser.fill_token_stream()?;
+            assert_eq!(group(&parser.expression()?, code), grouped);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn error_recovery() {
+        let sourcemap = SourceMapInner::new();
+        let filename_code = insert_file!(
+            sourcemap,
+            path::PathBuf::from(FILENAME),
+            "type = i32;
+let entry = () {
+    let x = 1 +;
+    if x { let y = (1, ; } else { x = 2 };
+    let z = 3;
+    return z *;
+};
+unit math {
+    let double = (x: i64) -> i64 { return x * ; };
+    pub pub let half = 1;
+}
+let ok = 1;"
+                .to_string()
+        );
+        let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+        let mut parser = Parser::new(filename_code, logger, sourcemap);
+        parser.initialize_expr();
+
+        // One error for each statement with a typo
+        let errors = parser.parse().expect_err("Parsed with syntax errors");
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.get_error_type())
+                .collect::<Vec<_>>(),
+            vec![ErrorType::Syntax; 7]
+        );
+    }
+
+    #[test]
+    fn statement_productions() -> Result<(), ErrorValue> {
+        for (code, production) in vec![
+            ("unit fmt { }", "unit"),
+            ("@[no_std]", "compiler tag"),
+            ("type km = i32;", "type assignment"),
+            ("pub type km = i32;", "type assignment"),
+            ("extern let f: (i32) -> ();", "variable declaration"),
+            ("pub extern let f: (i32) -> ();", "variable declaration"),
+            ("let f: (i32, (i32,)) -> i32;", "variable declaration"),
+            ("pub let x: i32;", "variable declaration"),
+            ("let x: i32 = 1;", "variable assignment declaration"),
+            (
+                "pub let f = (x: i32) -> i32 { return x; };",
+                "variable assignment declaration",
+            ),
+            ("x = 1;", "variable assign"),
+            ("core::fmt::print(1);", "function call"),
+            ("(1, 2);", "tuple"),
+        ] {
+            let sourcemap = SourceMapInner::new();
+            let filename_code =
+                insert_file!(sourcemap, path::PathBuf::from(FILENAME), code.to_string());
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+            let mut parser = Parser::new(filename_code, logger, sourcemap);
+            parser.initialize_expr();
+            assert_eq!(parser.statement()?.as_str(), production, "{}", code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn single_production() -> Result<(), ErrorValue> {
+        // A statement is only parsed by the production picked from its first tokens, so its
+        // error is the one of that production
+        let productions: Vec<(&str, StatementProduction)> = vec![
+            ("let x: i32 +;", Parser::variable_declaration),
+            ("pub let x: i32 i32;", Parser::variable_declaration),
+            ("extern let f: ;", Parser::variable_declaration),
+            ("let x: i32 = ;", Parser::expression_statement),
+            ("let f = (x: i32) -> { };", Parser::expression_statement),
+            ("x = ;", Parser::expression_statement),
+            ("pub type = i32;", Parser::type_assign),
+            ("unit { }", Parser::unit),
+            ("@[]", Parser::compiler_tag),
+        ];
+        for (code, production) in productions {
+            let parser = || {
+                let sourcemap = SourceMapInner::new();
+                let filename_code =
+                    insert_file!(sourcemap, path::PathBuf::from(FILENAME), code.to_string());
+                let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+                let mut parser = Parser::new(filename_code, logger, sourcemap);
+                parser.initialize_expr();
+                parser
+            };
+
+            let error = parser().statement().expect_err("Parsed a syntax error");
+            let mut production_parser = parser();
+            production_parser.fill_token_stream()?;
+            let expected = production(&mut production_parser).expect_err("Parsed a syntax error");
+            assert_eq!(error, ErrorValue::from(expected), "{}", code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn file_recovery() {
+        // Number of errors raised in each file, every statement with a typo has one
+        for (code, errors) in vec![
+            ("let x = 1", 1),
+            ("let x = 1; } let y = 2;", 1),
+            ("let x = ; let y = ; let z = ;", 3),
+            ("unit a { unit b { let x = ; } let y = ; }\nlet z = ;", 3),
+            (
+                "let f = () { loop { let a = ; }; };\nlet g = () { return 1 *; };",
+                2,
+            ),
+            (
+                "let f = () { return match x { 0 => { let a = ; }, _ => 2 }; };\nlet g = ;",
+                2,
+            ),
+            ("let f = () { let x = (1; };\nlet y = (2;\nlet z = 3;", 2),
+        ] {
+            let sourcemap = SourceMapInner::new();
+            let filename_code =
+                insert_file!(sourcemap, path::PathBuf::from(FILENAME), code.to_string());
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+            let mut parser = Parser::new(filename_code, logger, sourcemap);
+            parser.initialize_expr();
+
+            let raised = parser.parse().expect_err("Parsed with syntax errors");
+            assert_eq!(raised.len(), errors, "{}", code);
+        }
+    }
+
+    #[test]
+    fn synchronize_recovery_points() -> Result<(), ErrorValue> {
+        // Token the next statement starts at once the statement at the start is skipped
+        for (code, next) in vec![
+            ("1 + ; let", lexer::TokenType::Let),
+            ("x } let", lexer::TokenType::RCP),
+            ("f(1, ; } let", lexer::TokenType::RCP),
+            ("f(1 { a; } let", lexer::TokenType::Let),
+            ("{ a; }; let", lexer::TokenType::Let),
+            ("{ a; } let", lexer::TokenType::Let),
+            ("if x { a; } else { b; } let", lexer::TokenType::Let),
+            ("match x { _ => { a; } } let", lexer::TokenType::Let),
+            ("loop { a; ", lexer::TokenType::EOF),
+            ("x", lexer::TokenType::EOF),
+        ] {
+            let sourcemap = SourceMapInner::new();
+            let filename_code =
+                insert_file!(sourcemap, path::PathBuf::from(FILENAME), code.to_string());
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+            let mut parser = Parser::new(filename_code, logger, sourcemap);
+            parser.initialize_expr();
+            parser.fill_token_stream()?;
+            parser.synchronize();
+            assert_eq!(parser.peek().token, next, "{}", code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn block_recovery() -> Result<(), ErrorValue> {
+        // Statements kept from the block and the code each error is raised at
+        for (code, kept, errors) in vec![
+            (
+                "{ let x = 1 +; let y = 2; }",
+                vec!["variable assignment declaration"],
+                vec!["; let y"],
+            ),
+            (
+                "{ let x = ; let y = ; x; }",
+                vec!["ID"],
+                vec!["; let y", "; x;"],
+            ),
+            (
+                "{ if x { let y = (1, ; } else { x = 2; }; z; }",
+                vec!["conditional", "ID"],
+                vec!["; } else"],
+            ),
+            (
+                "{ loop { let a = ; let b = 1; }; let c = 2 +; d; }",
+                vec!["loop", "ID"],
+                vec!["; let b", "; d;"],
+            ),
+            (
+                "{ return match x { 0 => { let a = ; }, _ => 2 }; }",
+                vec!["return statement"],
+                vec!["; }, _"],
+            ),
+            (
+                "{ let f = () { let x = (1; }; f; }",
+                vec!["variable assignment declaration", "ID"],
+                vec!["; }; f"],
+            ),
+        ] {
+            let sourcemap = SourceMapInner::new();
+            let filename_code =
+                insert_file!(sourcemap, path::PathBuf::from(FILENAME), code.to_string());
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+            let mut parser = Parser::new(filename_code, logger, sourcemap);
+            parser.initialize_expr();
+            parser.fill_token_stream()?;
+
+            let block = parser.block(Scope::Block)?;
+            assert_eq!(
+                block
+                    .nodes
+                    .iter()
+                    .map(|stmt| stmt.as_str())
+                    .collect::<Vec<_>>(),
+                kept,
+                "{}",
+                code
+            );
+            let raised = parser
+                .errors
+                .iter()
+                .map(|error| &code[error.position.s..])
+                .collect::<Vec<_>>();
+            assert_eq!(raised.len(), errors.len(), "{}", code);
+            for (rest, error) in raised.iter().zip(errors) {
+                assert!(rest.starts_with(error), "{} raised at {}", code, rest);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let parse = |code: String, max_depth: usize| {
+            let sourcemap = SourceMapInner::new();
+            let filename_code = insert_file!(sourcemap, path::PathBuf::from(FILENAME), code);
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+            let mut parser = Parser::new(filename_code, logger, sourcemap);
+            parser.max_depth = max_depth;
+            parser.initialize_expr();
+            parser.parse()
+        };
+
+        assert!(parse("let x = ((1));".to_string(), 4).is_ok());
+        assert!(parse("let x = (((1)));".to_string(), 4).is_err());
+
+        // Nesting of each kind, written `depth` levels deep
+        let nestings: Vec<fn(usize) -> String> = vec![
+            |depth| format!("let x = {}1{};", "(".repeat(depth), ")".repeat(depth)),
+            |depth| format!("let x = {}1;", "- ".repeat(depth)),
+            |depth| format!("let x = {}true;", "! ".repeat(depth)),
+            |depth| format!("let x = {}1;", "~".repeat(depth)),
+            |depth| format!("let x = {}{}", "{ ".repeat(depth), "};".repeat(depth)),
+            |depth| format!("let x: {}i64{};", "(".repeat(depth), ",)".repeat(depth)),
+            |depth| format!("let x: {}i64;", "() -> ".repeat(depth)),
+            |depth| {
+                format!(
+                    "let x = match y {{ {}_{} => 1 }};",
+                    "(".repeat(depth),
+                    ",)".repeat(depth)
+                )
+            },
+            |depth| format!("{}{}", "unit a { ".repeat(depth), "}".repeat(depth)),
+        ];
+
+        for nesting in nestings {
+            // Code nested deeper than it is ever written is still parsed
+            assert!(parse(nesting(32), MAX_DEPTH).is_ok(), "{}", nesting(32));
+
+            // One error instead of a stack overflow
+            let code = nesting(10000);
+            let errors = parse(code, MAX_DEPTH).expect_err("Parsed past the nesting limit");
+            assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.get_error_type())
+                    .collect::<Vec<_>>(),
+                vec![ErrorType::Syntax]
+            );
+        }
+    }
+
     //#[test]
     fn print_vals() {
         // Utility function for printing ast's
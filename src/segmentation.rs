@@ -88,6 +88,16 @@ impl Grapheme {
     pub fn len(&self) -> usize {
         self.len
     }
+
+    /// Get the unicode code points of the grapheme
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        std::iter::once(self.front).chain(self.next.iter().copied())
+    }
+
+    /// Get the number of unicode code points in the grapheme
+    pub fn char_count(&self) -> usize {
+        1 + self.next.len()
+    }
 }
 
 impl fmt::Display for Grapheme {
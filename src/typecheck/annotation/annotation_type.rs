@@ -14,8 +14,11 @@ use std::rc::Rc;
 /// NOTE: This type can be cloned relatively cheaply. (Because of Rc's)
 pub enum AnnotationType {
     Type(Rc<ast::Namespace>, helpers::Pos),
+    Prim(Prim, helpers::Pos),
     Tuple(Rc<Vec<AnnotationType>>, helpers::Pos),
     Function(Rc<Vec<AnnotationType>>, Rc<AnnotationType>, helpers::Pos),
+    /// Core range value with endpoints of the inner type, i.e. `0..10`
+    Range(Rc<AnnotationType>, helpers::Pos),
     Never(helpers::Pos),
     Infer(usize, helpers::Pos),
 }
@@ -24,11 +27,13 @@ impl PartialEq for AnnotationType {
     fn eq(&self, other: &Self) -> bool {
         match (&self, other) {
             (AnnotationType::Type(name1, _), AnnotationType::Type(name2, _)) => name1 == name2,
+            (AnnotationType::Prim(prim1, _), AnnotationType::Prim(prim2, _)) => prim1 == prim2,
             (AnnotationType::Tuple(tys1, _), AnnotationType::Tuple(tys2, _)) => tys1 == tys2,
             (
                 AnnotationType::Function(arg_tys1, ret_ty1, _),
                 AnnotationType::Function(arg_tys2, ret_ty2, _),
             ) => arg_tys1 == arg_tys2 && ret_ty1 == ret_ty2,
+            (AnnotationType::Range(ty1, _), AnnotationType::Range(ty2, _)) => ty1 == ty2,
             (AnnotationType::Never(_), AnnotationType::Never(_)) => true,
             (AnnotationType::Infer(infer_num1, _), AnnotationType::Infer(infer_num2, _)) => {
                 infer_num1 == infer_num2
@@ -38,30 +43,135 @@ impl PartialEq for AnnotationType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Prim {
     Bool,
     I64,
     I32,
     I16,
     I8,
+    Isize,
+    U64,
+    U32,
+    U16,
+    U8,
+    Usize,
+    F64,
+    F32,
+    Str,
+    /// Unicode scalar value
+    Char,
+}
+
+impl Prim {
+    /// Primitive named by a namespace, i.e. `i32`
+    pub fn from_namespace(ty: &ast::Namespace) -> Option<Prim> {
+        if ty.scopes.len() != 1 {
+            return None;
+        }
+
+        let first_name = &ty.scopes[0];
+        Prim::from_name(get_segment!(first_name.sourcemap, first_name.pos))
+    }
+
+    /// Primitive with the name `name`, also used for literal suffixes
+    pub fn from_name(name: &str) -> Option<Prim> {
+        let prim = match name {
+            "bool" => Prim::Bool,
+            "i64" => Prim::I64,
+            "i32" => Prim::I32,
+            "i16" => Prim::I16,
+            "i8" => Prim::I8,
+            "isize" => Prim::Isize,
+            "u64" => Prim::U64,
+            "u32" => Prim::U32,
+            "u16" => Prim::U16,
+            "u8" => Prim::U8,
+            "usize" => Prim::Usize,
+            "f64" => Prim::F64,
+            "f32" => Prim::F32,
+            "str" => Prim::Str,
+            "char" => Prim::Char,
+            _ => return None,
+        };
+        Some(prim)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        !matches!(self, Prim::Bool | Prim::Str | Prim::Char)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_numeric() && !self.is_float()
+    }
+
+    /// Whether values of this type can be negative, decides the signedness of integer operations
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Prim::I64 | Prim::I32 | Prim::I16 | Prim::I8 | Prim::Isize | Prim::F64 | Prim::F32
+        )
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Prim::F64 | Prim::F32)
+    }
+
+    /// Whether `as` can convert a value of this type into `to`
+    pub fn can_cast(&self, to: Prim) -> bool {
+        match (self, to) {
+            (from, to) if *from == to => true,
+            (from, to) if from.is_numeric() && to.is_numeric() => true,
+            // Every `u8` is a unicode scalar value, wider integers may not be
+            (Prim::U8, Prim::Char) => true,
+            (Prim::Bool, to) | (Prim::Char, to) => to.is_integer(),
+            _ => false,
+        }
+    }
+
+    /// Largest value of an integer type
+    pub fn max_value(&self) -> Option<u128> {
+        let max = match self {
+            Prim::I64 => i64::MAX as u128,
+            Prim::I32 => i32::MAX as u128,
+            Prim::I16 => i16::MAX as u128,
+            Prim::I8 => i8::MAX as u128,
+            Prim::Isize => isize::MAX as u128,
+            Prim::U64 => u64::MAX as u128,
+            Prim::U32 => u32::MAX as u128,
+            Prim::U16 => u16::MAX as u128,
+            Prim::U8 => u8::MAX as u128,
+            Prim::Usize => usize::MAX as u128,
+            Prim::Bool | Prim::F64 | Prim::F32 | Prim::Str | Prim::Char => return None,
+        };
+        Some(max)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Prim::Bool => "bool",
+            Prim::I64 => "i64",
+            Prim::I32 => "i32",
+            Prim::I16 => "i16",
+            Prim::I8 => "i8",
+            Prim::Isize => "isize",
+            Prim::U64 => "u64",
+            Prim::U32 => "u32",
+            Prim::U16 => "u16",
+            Prim::U8 => "u8",
+            Prim::Usize => "usize",
+            Prim::F64 => "f64",
+            Prim::F32 => "f32",
+            Prim::Str => "str",
+            Prim::Char => "char",
+        }
+    }
 }
 
 impl AnnotationType {
     pub fn is_primitive(&self) -> Option<Prim> {
         match self {
-            AnnotationType::Type(ty, _) if ty.scopes.len() == 1 => {
-                let first_name = ty.scopes[0].clone();
-                let is_prim = match get_segment!(first_name.sourcemap, first_name.pos) {
-                    "bool" => Prim::Bool,
-                    "i64" => Prim::I64,
-                    "i32" => Prim::I32,
-                    "i16" => Prim::I16,
-                    "i8" => Prim::I8,
-                    _ => return None,
-                };
-                Some(is_prim)
-            }
+            AnnotationType::Prim(prim, _) => Some(*prim),
             _ => None,
         }
     }
@@ -69,8 +179,10 @@ impl AnnotationType {
     pub fn pos(&self) -> helpers::Pos {
         match self {
             AnnotationType::Type(_, pos) => *pos,
+            AnnotationType::Prim(_, pos) => *pos,
             AnnotationType::Tuple(_, pos) => *pos,
             AnnotationType::Function(_, _, pos) => *pos,
+            AnnotationType::Range(_, pos) => *pos,
             AnnotationType::Never(pos) => *pos,
             AnnotationType::Infer(_, pos) => *pos,
         }
@@ -81,10 +193,12 @@ impl Clone for AnnotationType {
     fn clone(&self) -> Self {
         match self {
             AnnotationType::Type(name, pos) => AnnotationType::Type(Rc::clone(name), *pos),
+            AnnotationType::Prim(prim, pos) => AnnotationType::Prim(*prim, *pos),
             AnnotationType::Tuple(types, pos) => AnnotationType::Tuple(Rc::clone(types), *pos),
             AnnotationType::Function(arg_ty, ret_ty, pos) => {
                 AnnotationType::Function(Rc::clone(arg_ty), Rc::clone(ret_ty), *pos)
             }
+            AnnotationType::Range(ty, pos) => AnnotationType::Range(Rc::clone(ty), *pos),
             AnnotationType::Never(pos) => AnnotationType::Never(*pos),
             AnnotationType::Infer(ty_var, pos) => AnnotationType::Infer(*ty_var, *pos),
         }
@@ -96,6 +210,7 @@ impl fmt::Display for AnnotationType {
         match self {
             AnnotationType::Infer(val, _) => write!(f, "T{}", val),
             AnnotationType::Type(ty, _) => write!(f, "{}", ty),
+            AnnotationType::Prim(prim, _) => write!(f, "{}", prim.as_str()),
             AnnotationType::Tuple(tup, _) => write!(
                 f,
                 "({})",
@@ -104,6 +219,7 @@ impl fmt::Display for AnnotationType {
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
+            AnnotationType::Range(ty, _) => write!(f, "Range<{}>", ty),
             AnnotationType::Never(_) => write!(f, "<never>"),
             AnnotationType::Function(args, ret, _) => write!(
                 f,
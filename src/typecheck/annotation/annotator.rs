@@ -1,35 +1,88 @@
-use super::{typed_ast, AnnotationType};
+use super::{typed_ast, AnnotationType, Prim};
 
 use crate::helpers::Pos;
-use crate::logger::ErrorValue;
+use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
 use crate::parser::{ast, ast::Statement};
 use crate::typecheck::context::Context;
 
+use std::collections::HashSet;
 use std::rc::Rc;
 
 pub struct Annotator {
     /// Assign unique unknown types to be solved
     type_counter: usize,
+    /// Names of the units we are currently in, prepended to every declared symbol
+    prefix: Vec<ast::NameID>,
+    /// Type namespace, i.e. type aliases
+    types: Context<AnnotationType>,
+    /// Type aliases that have been collected, resolved or not
+    aliases: Context<Alias>,
+    /// Full names of the aliases waiting to be resolved, in source order
+    pending: Vec<Rc<ast::Namespace>>,
+    /// Types of literals with a default type when nothing else constrains them
+    literal_defaults: Vec<(AnnotationType, Prim)>,
+    /// Loops we are currently in, the innermost one is last
+    loops: Vec<LoopScope>,
+    loop_counter: usize,
+    /// Positions of the declarations without a value, assignments give them one later on
+    deferred: HashSet<Pos>,
+    /// Functions tagged `@[printer]`, `print` calls the one taking the type of its argument
+    printers: Vec<typed_ast::TypedBinder>,
+    /// Whether the statement being annotated follows a `@[printer]` tag
+    printer_tag: bool,
+}
+
+/// Loop that `break` and `continue` can leave
+pub struct LoopScope {
+    pub id: usize,
+    pub label: Option<ast::NameID>,
+    pub ty: AnnotationType,
+    pub breaks: bool,
+}
+
+/// Type alias as it was declared
+struct Alias {
+    value: ast::Type,
+    /// Units the alias was declared in
+    prefix: Vec<ast::NameID>,
+    pos: Pos,
 }
 
 impl Annotator {
     pub fn new() -> Self {
-        Annotator { type_counter: 0 }
+        Annotator {
+            type_counter: 0,
+            prefix: Vec::new(),
+            types: Context::new(),
+            aliases: Context::new(),
+            pending: Vec::new(),
+            literal_defaults: Vec::new(),
+            loops: Vec::new(),
+            loop_counter: 0,
+            deferred: HashSet::new(),
+            printers: Vec::new(),
+            printer_tag: false,
+        }
     }
 
     pub fn annotate(
         &mut self,
         mut ast: Vec<ast::Statement>,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<typed_ast::TypedBinder>,
     ) -> Result<Vec<super::TypedStmt>, ErrorValue> {
         let mut statements = Vec::with_capacity(ast.len());
 
+        self.collect_aliases(&ast);
+        self.resolve_aliases()?;
+
         for stmt in ast.iter_mut() {
             self.annotate_stmt_1(stmt, context)?;
         }
 
         for stmt in ast.into_iter() {
-            statements.push(self.annotate_stmt_2(stmt, context)?);
+            if let Some(stmt) = self.annotate_stmt_2(stmt, context)? {
+                statements.push(stmt);
+            }
         }
 
         Ok(statements)
@@ -38,35 +91,317 @@ impl Annotator {
     fn annotate_stmt_1(
         &mut self,
         stmt: &mut Statement,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<typed_ast::TypedBinder>,
     ) -> Result<(), ErrorValue> {
-        match stmt {
-            Statement::ExpressionStatement(expr_stmt) => expr_stmt.pass_1(self, context),
-            _ => Ok(()),
-        }
+        stmt.pass_1(self, context)
     }
 
     fn annotate_stmt_2(
         &mut self,
         stmt: Statement,
-        context: &mut Context<AnnotationType>,
-    ) -> Result<typed_ast::TypedStmt, ErrorValue> {
-        match stmt {
-            Statement::ExpressionStatement(expr_stmt) => expr_stmt.pass_2(self, context),
-            Statement::Tag(tag) => Ok(typed_ast::TypedStmt {
-                pos: tag.pos,
-                stmt: typed_ast::TypedStmtEnum::Tag(tag),
-            }),
-            _ => unimplemented!(),
+        context: &mut Context<typed_ast::TypedBinder>,
+    ) -> Result<Option<typed_ast::TypedStmt>, ErrorValue> {
+        stmt.pass_2(self, context)
+    }
+
+    /// Enter a unit, symbols declared inside of it are prefixed with its name
+    pub fn enter_unit(&mut self, name: &ast::Namespace) {
+        self.prefix.extend(name.scopes.iter().cloned());
+    }
+
+    pub fn exit_unit(&mut self, name: &ast::Namespace) {
+        self.prefix.truncate(self.prefix.len() - name.scopes.len());
+    }
+
+    /// Enter the body of a loop of type `ty`, returns the id of the loop
+    pub fn enter_loop(&mut self, label: Option<ast::NameID>, ty: AnnotationType) -> usize {
+        self.loop_counter += 1;
+        self.loops.push(LoopScope {
+            id: self.loop_counter,
+            label,
+            ty,
+            breaks: false,
+        });
+        self.loop_counter
+    }
+
+    pub fn exit_loop(&mut self) -> LoopScope {
+        self.loops.pop().unwrap()
+    }
+
+    /// Enter the body of a function, the loops around it can't be left from inside of it
+    pub fn enter_function(&mut self) -> Vec<LoopScope> {
+        std::mem::take(&mut self.loops)
+    }
+
+    pub fn exit_function(&mut self, loops: Vec<LoopScope>) {
+        self.loops = loops;
+    }
+
+    /// Declare a binding without a value at `pos`
+    pub fn defer(&mut self, pos: Pos) {
+        self.deferred.insert(pos);
+    }
+
+    /// Whether the binding declared at `pos` was declared without a value
+    ///
+    /// A plain assignment can give it one, `Initialization` checks that it happens exactly once
+    /// before it is used
+    pub fn is_deferred(&self, pos: Pos) -> bool {
+        self.deferred.contains(&pos)
+    }
+
+    /// Loop left by a `break` or `continue`, the innermost one unless it has a label
+    pub fn find_loop(
+        &mut self,
+        label: Option<&ast::NameID>,
+        keyword: &str,
+        pos: Pos,
+    ) -> Result<&mut LoopScope, ErrorValue> {
+        if self.loops.is_empty() {
+            return Err(ErrorValue::new(
+                format!("`{}` outside of a loop", keyword),
+                ErrorType::OutsideOfLoop,
+                pos,
+                ErrorDisplayType::Error,
+                vec![ErrorAnnotation::new(
+                    Some(format!("cannot `{}` outside of a loop", keyword)),
+                    pos,
+                    ErrorDisplayType::Error,
+                )],
+            ));
+        }
+
+        let label = match label {
+            Some(label) => label,
+            None => return Ok(self.loops.last_mut().unwrap()),
+        };
+
+        match self
+            .loops
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.label.as_ref() == Some(label))
+        {
+            Some(scope) => Ok(scope),
+            None => Err(ErrorValue::new(
+                format!(
+                    "undefined label `{}`",
+                    label.sourcemap.borrow().get_segment(label.pos)
+                ),
+                ErrorType::UndefinedSymbol,
+                label.pos,
+                ErrorDisplayType::Error,
+                vec![ErrorAnnotation::new(
+                    Some("not the label of a loop around it".to_string()),
+                    label.pos,
+                    ErrorDisplayType::Error,
+                )],
+            )),
+        }
+    }
+
+    /// Full name of a symbol declared in the current unit
+    pub fn declare_name(&self, name: &Rc<ast::Namespace>) -> Rc<ast::Namespace> {
+        if self.prefix.is_empty() {
+            Rc::clone(name)
+        } else {
+            Rc::new(name.prepend_namespace_rc(&self.prefix))
+        }
+    }
+
+    /// Tags apply to the statement right after them, unknown tags are ignored
+    pub fn tag(&mut self, tag: &ast::Tag) {
+        if tag.content.sourcemap.borrow().get_segment(tag.content.pos) == "printer" {
+            self.printer_tag = true;
+        }
+    }
+
+    /// Whether the current statement follows a `@[printer]` tag, which only applies to it
+    pub fn take_printer_tag(&mut self) -> bool {
+        std::mem::replace(&mut self.printer_tag, false)
+    }
+
+    /// Remember a declaration tagged `@[printer]`, it has to print its only argument
+    pub fn declare_printer(&mut self, binder: &typed_ast::TypedBinder) -> Result<(), ErrorValue> {
+        match binder.ty {
+            AnnotationType::Function(ref args, _, _) if args.len() == 1 => {
+                self.printers.push(binder.clone());
+                Ok(())
+            }
+            _ => Err(ErrorValue::new(
+                "a printer is a function taking a single argument".to_string(),
+                ErrorType::TypeMismatch,
+                binder.pos,
+                ErrorDisplayType::Error,
+                vec![ErrorAnnotation::new(
+                    Some(format!("`{}` type here", binder.ty)),
+                    binder.ty.pos(),
+                    ErrorDisplayType::Error,
+                )],
+            )),
+        }
+    }
+
+    pub fn printers(&self) -> &[typed_ast::TypedBinder] {
+        &self.printers
+    }
+
+    /// Resolve a name to the full name it was declared with
+    ///
+    /// Symbols in the innermost unit shadow the ones in the outer units
+    pub fn resolve<T>(
+        &self,
+        name: &Rc<ast::Namespace>,
+        context: &Context<T>,
+    ) -> Rc<ast::Namespace> {
+        for idx in (1..=self.prefix.len()).rev() {
+            let full_name = Rc::new(name.prepend_namespace_rc(&self.prefix[..idx]));
+            if context.get_local(&full_name).is_some() {
+                return full_name;
+            }
+        }
+        Rc::clone(name)
+    }
+
+    /// Collect the type aliases declared in `stmts` and in the units inside of it
+    pub fn collect_aliases(&mut self, stmts: &[Statement]) {
+        for stmt in stmts {
+            match stmt {
+                Statement::TypeAssign(type_assign) => {
+                    let full_name = self.declare_name(&type_assign.name);
+                    self.aliases.set_local(
+                        Rc::clone(&full_name),
+                        Alias {
+                            value: type_assign.value.clone(),
+                            prefix: self.prefix.clone(),
+                            pos: type_assign.pos,
+                        },
+                    );
+                    self.pending.push(full_name);
+                }
+                Statement::Unit(unit) => {
+                    self.enter_unit(&unit.name);
+                    self.collect_aliases(&unit.block);
+                    self.exit_unit(&unit.name);
+                }
+                _ => {}
+            }
         }
     }
 
+    /// Resolve the collected aliases, an alias may refer to aliases declared after it
+    pub fn resolve_aliases(&mut self) -> Result<(), ErrorValue> {
+        let mut stack = Vec::new();
+        for full_name in std::mem::take(&mut self.pending) {
+            self.resolve_alias(&full_name, &mut stack)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve the aliases this alias depends on before itself, `stack` holds the aliases
+    /// currently being resolved to detect cycles
+    fn resolve_alias(
+        &mut self,
+        full_name: &Rc<ast::Namespace>,
+        stack: &mut Vec<Rc<ast::Namespace>>,
+    ) -> Result<(), ErrorValue> {
+        if self.types.get_local(full_name).is_some() {
+            return Ok(());
+        }
+
+        if let Some(idx) = stack.iter().position(|alias| alias == full_name) {
+            return Err(self.alias_cycle_err(&stack[idx..]));
+        }
+
+        let (value, prefix) = match self.aliases.get_local(full_name).as_ref() {
+            Some(alias) => (alias.value.clone(), alias.prefix.clone()),
+            // Not an alias
+            None => return Ok(()),
+        };
+
+        // Names in the alias are resolved from the unit it was declared in
+        let outer_prefix = std::mem::replace(&mut self.prefix, prefix);
+        stack.push(Rc::clone(full_name));
+        let result = self.resolve_dependencies(&value, stack);
+        stack.pop();
+
+        if result.is_ok() {
+            let ty = self.annon_type(&value);
+            self.types.set_local(Rc::clone(full_name), ty);
+        }
+        self.prefix = outer_prefix;
+
+        result
+    }
+
+    fn resolve_dependencies(
+        &mut self,
+        ty: &ast::Type,
+        stack: &mut Vec<Rc<ast::Namespace>>,
+    ) -> Result<(), ErrorValue> {
+        match &ty.value {
+            ast::TypeType::Unknown => Ok(()),
+            ast::TypeType::Type(namespace) => {
+                let full_name = self.resolve(namespace, &self.aliases);
+                self.resolve_alias(&full_name, stack)
+            }
+            ast::TypeType::Tuple(tuple) => tuple
+                .iter()
+                .try_for_each(|item| self.resolve_dependencies(item, stack)),
+            ast::TypeType::Function(args, ret) => {
+                args.iter()
+                    .try_for_each(|arg| self.resolve_dependencies(arg, stack))?;
+                self.resolve_dependencies(ret, stack)
+            }
+        }
+    }
+
+    fn alias_cycle_err(&self, cycle: &[Rc<ast::Namespace>]) -> ErrorValue {
+        let alias_pos = |full_name: &Rc<ast::Namespace>| {
+            self.aliases
+                .get_local(full_name)
+                .as_ref()
+                .map(|alias| alias.pos)
+                .unwrap()
+        };
+
+        ErrorValue::new(
+            format!("cycle detected when resolving type alias `{}`", cycle[0]),
+            ErrorType::UndefinedType,
+            alias_pos(&cycle[0]),
+            ErrorDisplayType::Error,
+            cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .map(|(alias, next)| {
+                    ErrorAnnotation::new(
+                        Some(format!("`{}` refers to `{}`", alias, next)),
+                        alias_pos(alias),
+                        ErrorDisplayType::Error,
+                    )
+                })
+                .collect(),
+        )
+    }
+
     pub fn annon_type(&mut self, ty: &ast::Type) -> AnnotationType {
         match &ty.value {
             // The important part!
             ast::TypeType::Unknown => self.unique(ty.pos),
 
-            ast::TypeType::Type(namespace) => AnnotationType::Type(Rc::clone(namespace), ty.pos),
+            ast::TypeType::Type(namespace) => {
+                // Aliases are expanded before looking for primitives
+                let full_name = self.resolve(namespace, &self.types);
+                if let Some(alias) = self.types.get_local(&full_name).as_ref() {
+                    return (*alias).clone();
+                }
+
+                match Prim::from_namespace(namespace) {
+                    Some(prim) => AnnotationType::Prim(prim, ty.pos),
+                    None => AnnotationType::Type(full_name, ty.pos),
+                }
+            }
             ast::TypeType::Tuple(tuple) => AnnotationType::Tuple(
                 Rc::new(
                     tuple
@@ -76,8 +411,37 @@ impl Annotator {
                 ),
                 ty.pos,
             ),
-            ast::TypeType::Function(args, ret) => unimplemented!(),
+            ast::TypeType::Function(args, ret) => AnnotationType::Function(
+                Rc::new(args.iter().map(|arg| self.annon_type(arg)).collect()),
+                Rc::new(self.annon_type(ret)),
+                ty.pos,
+            ),
+        }
+    }
+
+    /// Type of a literal, its suffix or inferred if possible or else its default type
+    pub fn literal_type(&mut self, literal: &ast::Literal) -> AnnotationType {
+        if let Some(prim) = literal.suffix {
+            return AnnotationType::Prim(prim, literal.pos);
+        }
+
+        let ty = match literal.literal_type {
+            ast::LiteralType::String => return AnnotationType::Prim(Prim::Str, literal.pos),
+            ast::LiteralType::Char => return AnnotationType::Prim(Prim::Char, literal.pos),
+            ast::LiteralType::Bool => return AnnotationType::Prim(Prim::Bool, literal.pos),
+            _ => self.unique(literal.pos),
+        };
+        // Unsuffixed literals nothing else constrains are `i32` or `f64`, like in Rust
+        match literal.literal_type {
+            ast::LiteralType::Number => self.literal_defaults.push((ty.clone(), Prim::I32)),
+            ast::LiteralType::Float => self.literal_defaults.push((ty.clone(), Prim::F64)),
+            _ => (),
         }
+        ty
+    }
+
+    pub fn literal_defaults(&self) -> &[(AnnotationType, Prim)] {
+        &self.literal_defaults
     }
 
     pub fn unique(&mut self, pos: Pos) -> AnnotationType {
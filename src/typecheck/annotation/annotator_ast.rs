@@ -6,7 +6,7 @@ use crate::typecheck::context::{Context, TOption};
 
 use std::rc::Rc;
 
-impl<'a> TOption<&'a AnnotationType> {
+impl<'a> TOption<&'a TypedBinder> {
     fn undefined_symbol(name: &Rc<ast::Namespace>) -> ErrorValue {
         let err_msg = format!("undefined symbol `{}`", name.to_string());
         ErrorValue::new(
@@ -22,7 +22,7 @@ impl<'a> TOption<&'a AnnotationType> {
         )
     }
 
-    fn symbol(self, name: &Rc<ast::Namespace>) -> Result<&'a AnnotationType, ErrorValue> {
+    fn symbol(self, name: &Rc<ast::Namespace>) -> Result<&'a TypedBinder, ErrorValue> {
         match *self {
             Some(value) => Ok(value),
             None => Err(Self::undefined_symbol(name)),
@@ -34,7 +34,7 @@ impl ast::Function {
     pub fn pass_1(
         &mut self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<AnnotationType, ErrorValue> {
         self.block.pass_1(annotator, context)?;
         let args: Vec<_> = self
@@ -56,7 +56,7 @@ impl ast::Function {
     pub fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         let args = match self.ty.clone().unwrap() {
             AnnotationType::Function(args, _, _) => Rc::clone(&args),
@@ -64,16 +64,24 @@ impl ast::Function {
         };
 
         let mut new_context = context.clone();
+        let mut arg_names = Vec::with_capacity(args.len());
 
         for (ty, (name, _)) in args.iter().zip(self.arguments.positional) {
-            new_context.set_local(name, ty.clone())
+            let name = annotator.declare_name(&name);
+            arg_names.push(Rc::clone(&name));
+            let binder = TypedBinder::new(Some(Rc::clone(&name)), ty.clone(), name.pos);
+            new_context.set_local(name, binder)
         }
 
-        let block = self.block.pass_2(annotator, &mut new_context)?;
+        let outer_loops = annotator.enter_function();
+        let block = self.block.pass_2(annotator, &mut new_context);
+        annotator.exit_function(outer_loops);
+        let block = block?;
 
         Ok(TypedExpr {
             expr: TypedExprEnum::Function(TypedFunction {
                 ty: self.ty.unwrap(),
+                arg_names,
                 block: Box::new(block),
             }),
             pos: self.pos,
@@ -85,8 +93,11 @@ impl ast::Block {
     pub fn pass_1(
         &mut self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<AnnotationType, ErrorValue> {
+        annotator.collect_aliases(&self.nodes);
+        annotator.resolve_aliases()?;
+
         for stmt in self.nodes.iter_mut() {
             stmt.pass_1(annotator, context)?;
         }
@@ -99,17 +110,288 @@ impl ast::Block {
     pub fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
+        let pos = self.pos;
         Ok(TypedExpr {
-            expr: TypedExprEnum::Block(TypedBlock {
-                ty: self.ty.unwrap(),
-                stmts: self
-                    .nodes
+            expr: TypedExprEnum::Block(self.pass_2_block(annotator, context)?),
+            pos,
+        })
+    }
+
+    fn pass_2_block(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedBlock, ErrorValue> {
+        Ok(TypedBlock {
+            ty: self.ty.unwrap(),
+            stmts: self
+                .nodes
+                .into_iter()
+                .map(|node| node.pass_2(annotator, context))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+        })
+    }
+}
+
+impl ast::Conditional {
+    fn pass_1(
+        &mut self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<AnnotationType, ErrorValue> {
+        for branch in self.if_branches.iter_mut() {
+            branch.cond.pass_1(annotator, context)?;
+            branch.block.pass_1(annotator, context)?;
+        }
+
+        if let Some(else_branch) = &mut self.else_branch {
+            else_branch.block.pass_1(annotator, context)?;
+        }
+
+        Ok(annotator.unique(self.pos))
+    }
+
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        let if_branches = self
+            .if_branches
+            .into_iter()
+            .map(|branch| {
+                Ok(TypedIfBranch {
+                    cond: branch.cond.pass_2(annotator, context)?,
+                    block: branch.block.pass_2_block(annotator, context)?,
+                    pos: branch.pos,
+                })
+            })
+            .collect::<Result<Vec<_>, ErrorValue>>()?;
+
+        let else_branch = match self.else_branch {
+            Some(else_branch) => Some(TypedElseBranch {
+                block: else_branch.block.pass_2_block(annotator, context)?,
+                pos: else_branch.pos,
+            }),
+            None => None,
+        };
+
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Conditional(TypedConditional {
+                ty: annotator.unique(self.pos),
+                if_branches,
+                else_branch,
+            }),
+        })
+    }
+}
+
+impl ast::Loop {
+    fn pass_1(
+        &mut self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<AnnotationType, ErrorValue> {
+        match &mut self.kind {
+            ast::LoopKind::Loop => {}
+            ast::LoopKind::While(cond) => {
+                cond.pass_1(annotator, context)?;
+            }
+            ast::LoopKind::For { range, .. } => {
+                range.pass_1(annotator, context)?;
+            }
+        }
+        self.block.pass_1(annotator, context)?;
+
+        Ok(annotator.unique(self.pos))
+    }
+
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        // Symbols declared in the body are only visible in it
+        let mut loop_context = context.clone();
+
+        let kind = match self.kind {
+            ast::LoopKind::Loop => TypedLoopKind::Loop,
+            ast::LoopKind::While(cond) => {
+                TypedLoopKind::While(Box::new(cond.pass_2(annotator, context)?))
+            }
+            ast::LoopKind::For { name, range } => {
+                let range = range.pass_2(annotator, context)?;
+
+                let name = annotator.declare_name(&name);
+                let ty = annotator.unique(name.pos);
+                let binder = TypedBinder::new(Some(Rc::clone(&name)), ty, name.pos);
+                loop_context.set_local(name, binder.clone());
+
+                TypedLoopKind::For {
+                    binder,
+                    range: Box::new(range),
+                }
+            }
+        };
+
+        let ty = annotator.unique(self.pos);
+        let id = annotator.enter_loop(self.label, ty.clone());
+        let block = self.block.pass_2_block(annotator, &mut loop_context);
+        let scope = annotator.exit_loop();
+
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Loop(TypedLoop {
+                id,
+                ty,
+                kind,
+                block: block?,
+                breaks: scope.breaks,
+            }),
+        })
+    }
+}
+
+impl ast::Break {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        let expr = match self.expression {
+            Some(expression) => Some(Box::new(expression.pass_2(annotator, context)?)),
+            None => None,
+        };
+
+        let scope = annotator.find_loop(self.label.as_ref(), "break", self.pos)?;
+        scope.breaks = true;
+
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Break(TypedBreak {
+                loop_id: scope.id,
+                loop_ty: scope.ty.clone(),
+                expr,
+                ty: AnnotationType::Never(self.pos),
+            }),
+        })
+    }
+}
+
+impl ast::Continue {
+    fn pass_2(self, annotator: &mut Annotator) -> Result<TypedExpr, ErrorValue> {
+        let scope = annotator.find_loop(self.label.as_ref(), "continue", self.pos)?;
+
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Continue(TypedContinue {
+                loop_id: scope.id,
+                ty: AnnotationType::Never(self.pos),
+            }),
+        })
+    }
+}
+
+impl ast::Match {
+    fn pass_1(
+        &mut self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<AnnotationType, ErrorValue> {
+        self.expr.pass_1(annotator, context)?;
+        for arm in self.arms.iter_mut() {
+            if let Some(guard) = &mut arm.guard {
+                guard.pass_1(annotator, context)?;
+            }
+            arm.expr.pass_1(annotator, context)?;
+        }
+
+        Ok(annotator.unique(self.pos))
+    }
+
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        let expr = self.expr.pass_2(annotator, context)?;
+
+        let arms = self
+            .arms
+            .into_iter()
+            .map(|arm| {
+                // Names bound by the pattern are only visible in its arm
+                let mut arm_context = context.clone();
+                let pattern = arm.pattern.pass_2(annotator, &mut arm_context)?;
+                let guard = match arm.guard {
+                    Some(guard) => Some(guard.pass_2(annotator, &mut arm_context)?),
+                    None => None,
+                };
+
+                Ok(TypedMatchArm {
+                    pattern,
+                    guard,
+                    expr: arm.expr.pass_2(annotator, &mut arm_context)?,
+                    pos: arm.pos,
+                })
+            })
+            .collect::<Result<Vec<_>, ErrorValue>>()?;
+
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Match(TypedMatch {
+                ty: annotator.unique(self.pos),
+                expr: Box::new(expr),
+                arms,
+            }),
+        })
+    }
+}
+
+impl ast::Pattern {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedPattern, ErrorValue> {
+        let kind = match self.kind {
+            ast::PatternKind::Wildcard => TypedPatternKind::Wildcard,
+            ast::PatternKind::Literal(literal) => {
+                TypedPatternKind::Literal(Box::new(literal.pass_2(annotator, context)?))
+            }
+            ast::PatternKind::Binding(name) => {
+                let name = annotator.declare_name(&name);
+                let ty = annotator.unique(name.pos);
+                let binder = TypedBinder::new(Some(Rc::clone(&name)), ty, self.pos);
+                context.set_local(name, binder.clone());
+                TypedPatternKind::Binding(binder)
+            }
+            ast::PatternKind::Tuple(patterns) => TypedPatternKind::Tuple(
+                patterns
                     .into_iter()
-                    .map(|node| node.pass_2(annotator, context))
+                    .map(|pattern| pattern.pass_2(annotator, context))
                     .collect::<Result<Vec<_>, _>>()?,
-            }),
+            ),
+            ast::PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => TypedPatternKind::Range {
+                start: Box::new(start.pass_2(annotator, context)?),
+                end: Box::new(end.pass_2(annotator, context)?),
+                inclusive,
+            },
+        };
+
+        Ok(TypedPattern {
+            kind,
+            ty: annotator.unique(self.pos),
             pos: self.pos,
         })
     }
@@ -118,14 +400,15 @@ impl ast::Block {
 impl ast::RefID {
     fn pass_2(
         self,
-        _annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
+        let name = annotator.resolve(&self.value, context);
         Ok(TypedExpr {
             pos: self.pos,
             expr: TypedExprEnum::RefID(TypedRefID {
-                ty: context.get_local(&self.value).symbol(&self.value)?.clone(),
-                name: self.value,
+                ty: context.get_local(&name).symbol(&self.value)?.ty.clone(),
+                name,
             }),
         })
     }
@@ -135,48 +418,269 @@ impl ast::VariableAssignDeclaration {
     fn pass_1(
         &mut self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<AnnotationType, ErrorValue> {
         let typed_type = self.expr.pass_1(annotator, context)?;
 
-        context.set_local(Rc::clone(&self.name), typed_type.clone());
+        let name = annotator.declare_name(&self.name);
+        context.set_local(Rc::clone(&name), self.binder(name, typed_type.clone()));
         Ok(typed_type)
     }
 
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
-        let typed_type = annotator.annon_type(&self.ty);
+        let name = annotator.declare_name(&self.name);
+        let binder = self.binder(Rc::clone(&name), annotator.annon_type(&self.ty));
         let typed_expr = self.expr.pass_2(annotator, context)?;
 
-        context.set_local(Rc::clone(&self.name), typed_type.clone());
+        context.set_local(name, binder.clone());
 
         Ok(TypedExpr {
             pos: self.pos,
             expr: TypedExprEnum::VariableAssignDeclaration(TypedAssign {
                 expr: Box::new(typed_expr),
+                visibility: self.visibility,
+                docs: self.docs,
+                binder,
+            }),
+        })
+    }
+
+    fn binder(&self, name: Rc<ast::Namespace>, ty: AnnotationType) -> TypedBinder {
+        TypedBinder {
+            name: Some(name),
+            ty,
+            mutable: self.mutable,
+            pos: self.pos,
+        }
+    }
+}
+
+impl ast::VariableAssign {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        let name = annotator.resolve(&self.name, context);
+        let binder = context.get_local(&name).symbol(&self.name)?.clone();
+        if !binder.mutable {
+            match self.operator {
+                None if annotator.is_deferred(binder.pos) => {}
+                // `x += 1` reads `x`, which is only given a value by a plain assignment
+                Some(_) if annotator.is_deferred(binder.pos) => {
+                    return Err(self.uninitialized_err(&binder))
+                }
+                _ => return Err(self.immutable_err(&binder)),
+            }
+        }
+
+        let mut typed_expr = self.expr.pass_2(annotator, context)?;
+
+        // `x += 1` assigns `x + 1`
+        if let Some(operator) = self.operator {
+            typed_expr = TypedExpr {
+                pos: self.pos,
+                expr: TypedExprEnum::Infix(TypedInfix {
+                    ty: annotator.unique(self.pos),
+                    left: Box::new(TypedExpr {
+                        pos: self.name.pos,
+                        expr: TypedExprEnum::RefID(TypedRefID {
+                            ty: binder.ty.clone(),
+                            name,
+                        }),
+                    }),
+                    right: Box::new(typed_expr),
+                    operator,
+                }),
+            };
+        }
+
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::VariableAssign(TypedAssign {
+                expr: Box::new(typed_expr),
+                visibility: ast::Visibility::Private,
+                docs: None,
                 binder: TypedBinder {
-                    name: Some(Rc::clone(&self.name)),
-                    ty: typed_type,
                     pos: self.pos,
+                    ..binder
                 },
             }),
         })
     }
+
+    fn immutable_err(&self, binder: &TypedBinder) -> ErrorValue {
+        let bound_pos = binder.name.as_ref().map_or(binder.pos, |name| name.pos);
+        ErrorValue::new(
+            format!("cannot assign twice to immutable binding `{}`", self.name),
+            ErrorType::ImmutableAssign,
+            self.pos,
+            ErrorDisplayType::Error,
+            vec![
+                ErrorAnnotation::new(
+                    Some("cannot assign twice".to_string()),
+                    self.pos,
+                    ErrorDisplayType::Error,
+                ),
+                ErrorAnnotation::new(
+                    Some(format!("`{}` is bound here", self.name)),
+                    bound_pos,
+                    ErrorDisplayType::Info,
+                ),
+            ],
+        )
+        .with_note("only bindings declared with `let mut` can be assigned again".to_string())
+    }
+
+    fn uninitialized_err(&self, binder: &TypedBinder) -> ErrorValue {
+        let bound_pos = binder.name.as_ref().map_or(binder.pos, |name| name.pos);
+        ErrorValue::new(
+            format!("used binding `{}` is possibly uninitialized", self.name),
+            ErrorType::PossibleUninitVal,
+            self.pos,
+            ErrorDisplayType::Error,
+            vec![
+                ErrorAnnotation::new(
+                    Some(format!("`{}` is read here", self.name)),
+                    self.pos,
+                    ErrorDisplayType::Error,
+                ),
+                ErrorAnnotation::new(
+                    Some(format!("`{}` is declared here without a value", self.name)),
+                    bound_pos,
+                    ErrorDisplayType::Info,
+                ),
+            ],
+        )
+        .with_note("a declaration without a value is only given one by a plain `=`".to_string())
+    }
+}
+
+impl ast::VariableDeclaration {
+    fn pass_1(
+        &mut self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+        printer: bool,
+    ) -> Result<(), ErrorValue> {
+        let name = annotator.declare_name(&self.name);
+        let binder = self.binder(Rc::clone(&name), annotator.annon_type(&self.ty));
+        if printer {
+            annotator.declare_printer(&binder)?;
+        }
+        context.set_local(name, binder);
+        if !self.is_extern {
+            annotator.defer(self.pos);
+        }
+        Ok(())
+    }
+
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedStmt, ErrorValue> {
+        let name = annotator.declare_name(&self.name);
+        let typed_type = match context.get_local(&name).as_ref() {
+            // Declared on the outer scope during the first pass
+            Some(binder) => binder.ty.clone(),
+            None => annotator.annon_type(&self.ty),
+        };
+        let binder = self.binder(Rc::clone(&name), typed_type);
+        context.set_local(name, binder.clone());
+
+        Ok(TypedStmt {
+            pos: self.pos,
+            stmt: TypedStmtEnum::VariableDeclaration(TypedDeclaration {
+                binder,
+                is_extern: self.is_extern,
+                visibility: self.visibility,
+                docs: self.docs,
+            }),
+        })
+    }
+
+    /// Declarations without a value are not mutable, but unless they are external symbols an
+    /// assignment gives them their value
+    fn binder(&self, name: Rc<ast::Namespace>, ty: AnnotationType) -> TypedBinder {
+        TypedBinder {
+            name: Some(name),
+            ty,
+            mutable: false,
+            pos: self.pos,
+        }
+    }
+}
+
+impl ast::TypeAssign {
+    fn pass_2(self, annotator: &mut Annotator) -> Result<TypedStmt, ErrorValue> {
+        Ok(TypedStmt {
+            pos: self.pos,
+            stmt: TypedStmtEnum::TypeAssign(TypedTypeAssign {
+                name: annotator.declare_name(&self.name),
+                ty: annotator.annon_type(&self.value),
+                visibility: self.visibility,
+                docs: self.docs,
+            }),
+        })
+    }
+}
+
+impl ast::Unit {
+    fn pass_1(
+        &mut self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<(), ErrorValue> {
+        annotator.enter_unit(&self.name);
+        let result = self
+            .block
+            .iter_mut()
+            .try_for_each(|stmt| stmt.pass_1(annotator, context));
+        annotator.exit_unit(&self.name);
+        result
+    }
+
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedStmt, ErrorValue> {
+        let name = annotator.declare_name(&Rc::new(self.name.clone()));
+
+        annotator.enter_unit(&self.name);
+        let stmts = self
+            .block
+            .into_iter()
+            .map(|stmt| stmt.pass_2(annotator, context))
+            .collect::<Result<Vec<_>, _>>();
+        annotator.exit_unit(&self.name);
+
+        Ok(TypedStmt {
+            pos: self.pos,
+            stmt: TypedStmtEnum::Unit(TypedUnit {
+                name,
+                stmts: stmts?.into_iter().flatten().collect(),
+                docs: self.docs,
+            }),
+        })
+    }
 }
 
 impl ast::Literal {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        _context: &mut Context<AnnotationType>,
+        _context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         Ok(TypedExpr {
             pos: self.pos,
             expr: TypedExprEnum::Literal(TypedLiteral {
-                ty: annotator.unique(self.pos),
+                ty: annotator.literal_type(&self),
                 value: self,
             }),
         })
@@ -187,10 +691,22 @@ impl ast::FunctionCall {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
-        let func_sig = context.get_local(&self.name);
-        let func_ty = func_sig.symbol(&self.name)?.clone();
+        let name = annotator.resolve(&self.name, context);
+        let func_sig = context.get_local(&name);
+
+        // Unless it is shadowed, `print` calls the printer taking the type of its argument,
+        // which is picked once the argument type is known
+        let (func_ty, overloads) = match *func_sig {
+            None if self.is_print() && !annotator.printers().is_empty() => {
+                let unit = AnnotationType::Tuple(Rc::new(Vec::new()), self.pos);
+                let args = vec![annotator.unique(self.pos)];
+                let func_ty = AnnotationType::Function(Rc::new(args), Rc::new(unit), self.pos);
+                (func_ty, annotator.printers().to_vec())
+            }
+            _ => (func_sig.symbol(&self.name)?.ty.clone(), Vec::new()),
+        };
 
         let ret_ty = match func_ty {
             AnnotationType::Function(_, ref ret, _) => (**ret).clone(),
@@ -204,23 +720,28 @@ impl ast::FunctionCall {
             expr: TypedExprEnum::FunctionCall(TypedFunctionCall {
                 ty: ret_ty,
                 func_ty,
-                name: Rc::clone(&self.name),
+                name,
                 arguments: self
                     .arguments
                     .positional
                     .into_iter()
                     .map(|expr| expr.pass_2(annotator, context))
                     .collect::<Result<Vec<_>, _>>()?,
+                overloads,
             }),
         })
     }
+
+    fn is_print(&self) -> bool {
+        self.name.scopes.len() == 1 && self.name.to_string() == "print"
+    }
 }
 
 impl ast::IsExpr {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         Ok(TypedExpr {
             pos: self.pos,
@@ -232,11 +753,27 @@ impl ast::IsExpr {
     }
 }
 
+impl ast::AsExpr {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::As(TypedAs {
+                ty: annotator.annon_type(&self.ty),
+                expr: Box::new(self.expr.pass_2(annotator, context)?),
+            }),
+        })
+    }
+}
+
 impl ast::Tuple {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         Ok(TypedExpr {
             pos: self.pos,
@@ -253,16 +790,125 @@ impl ast::Tuple {
     }
 }
 
+impl ast::Infix {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Infix(TypedInfix {
+                ty: annotator.unique(self.pos),
+                left: Box::new(self.left.pass_2(annotator, context)?),
+                right: Box::new(self.right.pass_2(annotator, context)?),
+                operator: self.operator.token,
+            }),
+        })
+    }
+}
+
+impl ast::Range {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Range(TypedRange {
+                ty: annotator.unique(self.pos),
+                start: Box::new(self.start.pass_2(annotator, context)?),
+                end: Box::new(self.end.pass_2(annotator, context)?),
+                inclusive: self.inclusive,
+            }),
+        })
+    }
+}
+
+impl ast::Prefix {
+    fn pass_2(
+        self,
+        annotator: &mut Annotator,
+        context: &mut Context<TypedBinder>,
+    ) -> Result<TypedExpr, ErrorValue> {
+        Ok(TypedExpr {
+            pos: self.pos,
+            expr: TypedExprEnum::Prefix(TypedPrefix {
+                ty: annotator.unique(self.pos),
+                val: Box::new(self.val.pass_2(annotator, context)?),
+                operator: self.operator.token,
+            }),
+        })
+    }
+}
+
 impl ast::Expr {
     fn pass_1(
         &mut self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<AnnotationType, ErrorValue> {
         match self {
             ast::Expr::Function(func) => func.pass_1(annotator, context),
             ast::Expr::VariableAssignDeclaration(var_dec) => var_dec.pass_1(annotator, context),
             ast::Expr::Block(block) => block.pass_1(annotator, context),
+            ast::Expr::Conditional(conditional) => conditional.pass_1(annotator, context),
+            ast::Expr::Loop(loop_expr) => loop_expr.pass_1(annotator, context),
+            ast::Expr::Match(match_expr) => match_expr.pass_1(annotator, context),
+
+            // Visit nested expressions, as they may contain blocks
+            ast::Expr::Return(ast::Return { expression, .. })
+            | ast::Expr::Yield(ast::Yield { expression, .. })
+            | ast::Expr::Is(ast::IsExpr {
+                expr: expression, ..
+            })
+            | ast::Expr::As(ast::AsExpr {
+                expr: expression, ..
+            })
+            | ast::Expr::Prefix(ast::Prefix {
+                val: expression, ..
+            }) => {
+                expression.pass_1(annotator, context)?;
+                Ok(annotator.unique(self.pos()))
+            }
+            ast::Expr::Break(ast::Break {
+                expression: Some(expression),
+                pos,
+                ..
+            }) => {
+                expression.pass_1(annotator, context)?;
+                Ok(annotator.unique(*pos))
+            }
+            ast::Expr::Infix(infix) => {
+                infix.left.pass_1(annotator, context)?;
+                infix.right.pass_1(annotator, context)?;
+                Ok(annotator.unique(infix.pos))
+            }
+            ast::Expr::Range(range) => {
+                range.start.pass_1(annotator, context)?;
+                range.end.pass_1(annotator, context)?;
+                Ok(annotator.unique(range.pos))
+            }
+            ast::Expr::VariableAssign(var_assign) => {
+                var_assign.expr.pass_1(annotator, context)?;
+                Ok(annotator.unique(var_assign.pos))
+            }
+            ast::Expr::Tuple(ast::Tuple { values, pos })
+            | ast::Expr::FunctionCall(ast::FunctionCall {
+                arguments:
+                    ast::ArgumentsRun {
+                        positional: values, ..
+                    },
+                pos,
+                ..
+            }) => {
+                for value in values.iter_mut() {
+                    value.pass_1(annotator, context)?;
+                }
+                Ok(annotator.unique(*pos))
+            }
+
             _ => Ok(annotator.unique(self.pos())),
         }
     }
@@ -270,19 +916,29 @@ impl ast::Expr {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         match self {
             ast::Expr::RefID(id) => id.pass_2(annotator, context),
             ast::Expr::VariableAssignDeclaration(var_dec) => var_dec.pass_2(annotator, context),
             ast::Expr::Literal(lit) => lit.pass_2(annotator, context),
             ast::Expr::Is(is) => is.pass_2(annotator, context),
+            ast::Expr::As(as_expr) => as_expr.pass_2(annotator, context),
             ast::Expr::FunctionCall(func_call) => func_call.pass_2(annotator, context),
             ast::Expr::Yield(yield_val) => yield_val.pass_2(annotator, context),
             ast::Expr::Return(return_val) => return_val.pass_2(annotator, context),
             ast::Expr::Function(func) => func.pass_2(annotator, context),
             ast::Expr::Block(block) => block.pass_2(annotator, context),
             ast::Expr::Tuple(tuple) => tuple.pass_2(annotator, context),
+            ast::Expr::Infix(infix) => infix.pass_2(annotator, context),
+            ast::Expr::Prefix(prefix) => prefix.pass_2(annotator, context),
+            ast::Expr::Range(range) => range.pass_2(annotator, context),
+            ast::Expr::Conditional(conditional) => conditional.pass_2(annotator, context),
+            ast::Expr::VariableAssign(var_assign) => var_assign.pass_2(annotator, context),
+            ast::Expr::Loop(loop_expr) => loop_expr.pass_2(annotator, context),
+            ast::Expr::Break(break_expr) => break_expr.pass_2(annotator, context),
+            ast::Expr::Continue(continue_expr) => continue_expr.pass_2(annotator),
+            ast::Expr::Match(match_expr) => match_expr.pass_2(annotator, context),
             _ => panic!("Unimplemented {}", self.as_str()),
         }
     }
@@ -292,7 +948,7 @@ impl ast::ExpressionStatement {
     pub fn pass_1(
         &mut self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<(), ErrorValue> {
         self.expression.pass_1(annotator, context)?;
         Ok(())
@@ -301,7 +957,7 @@ impl ast::ExpressionStatement {
     pub fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedStmt, ErrorValue> {
         Ok(TypedStmt {
             pos: self.pos,
@@ -314,7 +970,7 @@ impl ast::Return {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         Ok(TypedExpr {
             pos: self.pos,
@@ -330,7 +986,7 @@ impl ast::Yield {
     fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<TypedExpr, ErrorValue> {
         Ok(TypedExpr {
             pos: self.pos,
@@ -343,25 +999,48 @@ impl ast::Yield {
 }
 
 impl ast::Statement {
-    fn pass_1(
+    pub fn pass_1(
         &mut self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
+        context: &mut Context<TypedBinder>,
     ) -> Result<(), ErrorValue> {
+        let printer = annotator.take_printer_tag();
         match self {
             ast::Statement::ExpressionStatement(expr_stmt) => expr_stmt.pass_1(annotator, context),
-            _ => panic!("Unimplemented {}", self.as_str()),
+            ast::Statement::VariableDeclaration(var_dec) => {
+                var_dec.pass_1(annotator, context, printer)
+            }
+            ast::Statement::Unit(unit) => unit.pass_1(annotator, context),
+            ast::Statement::Tag(tag) => {
+                annotator.tag(tag);
+                Ok(())
+            }
+            // Type aliases are resolved before pass 1 by `Annotator::resolve_aliases`
+            ast::Statement::TypeAssign(_)
+            | ast::Statement::Import(_)
+            | ast::Statement::Empty(_) => Ok(()),
         }
     }
 
-    fn pass_2(
+    /// Imports and empty statements have no typed statement, imported files are parsed as units
+    pub fn pass_2(
         self,
         annotator: &mut Annotator,
-        context: &mut Context<AnnotationType>,
-    ) -> Result<TypedStmt, ErrorValue> {
-        match self {
-            ast::Statement::ExpressionStatement(expr_stmt) => expr_stmt.pass_2(annotator, context),
-            _ => panic!("Unimplemented {}", self.as_str()),
-        }
+        context: &mut Context<TypedBinder>,
+    ) -> Result<Option<TypedStmt>, ErrorValue> {
+        let stmt = match self {
+            ast::Statement::ExpressionStatement(expr_stmt) => {
+                expr_stmt.pass_2(annotator, context)?
+            }
+            ast::Statement::VariableDeclaration(var_dec) => var_dec.pass_2(annotator, context)?,
+            ast::Statement::TypeAssign(type_assign) => type_assign.pass_2(annotator)?,
+            ast::Statement::Unit(unit) => unit.pass_2(annotator, context)?,
+            ast::Statement::Tag(tag) => TypedStmt {
+                pos: tag.pos,
+                stmt: TypedStmtEnum::Tag(tag),
+            },
+            ast::Statement::Import(_) | ast::Statement::Empty(_) => return Ok(None),
+        };
+        Ok(Some(stmt))
     }
 }
@@ -1,5 +1,6 @@
 use super::AnnotationType;
 use crate::helpers;
+use crate::lexer;
 use crate::parser::ast;
 
 use std::fmt;
@@ -24,12 +25,21 @@ pub struct TypedIs {
     pub expr: Box<TypedExpr>,
 }
 
+#[derive(Clone, Debug)]
+/// Conversion with `as`, `ty` is the type converted to
+pub struct TypedAs {
+    pub ty: AnnotationType,
+    pub expr: Box<TypedExpr>,
+}
+
 #[derive(Clone, Debug)]
 pub struct TypedFunctionCall {
     pub func_ty: AnnotationType,
     pub ty: AnnotationType,
     pub name: Rc<ast::Namespace>,
     pub arguments: Vec<TypedExpr>,
+    /// Functions a `print` call picks from by the type of its argument
+    pub overloads: Vec<TypedBinder>,
 }
 
 #[derive(Clone, Debug)]
@@ -39,6 +49,190 @@ pub struct TypedTuple {
     pub pos: helpers::Pos,
 }
 
+#[derive(Clone, Debug)]
+pub struct TypedInfix {
+    pub ty: AnnotationType,
+    pub left: Box<TypedExpr>,
+    pub right: Box<TypedExpr>,
+    pub operator: lexer::TokenType,
+}
+
+impl TypedInfix {
+    /// Operators that result in a `bool`, instead of the type of their operands
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self.operator,
+            lexer::TokenType::GT
+                | lexer::TokenType::LT
+                | lexer::TokenType::GE
+                | lexer::TokenType::LE
+                | lexer::TokenType::EQ
+                | lexer::TokenType::NE
+                | lexer::TokenType::DMod
+        )
+    }
+
+    /// Operators on the bits of integers
+    pub fn is_bitwise(&self) -> bool {
+        matches!(
+            self.operator,
+            lexer::TokenType::BitAnd
+                | lexer::TokenType::BitOr
+                | lexer::TokenType::BitXor
+                | lexer::TokenType::Shl
+                | lexer::TokenType::Shr
+        )
+    }
+
+    /// `&&` and `||`, the right operand is only evaluated if the left one doesn't decide the
+    /// result
+    pub fn is_logical(&self) -> bool {
+        matches!(self.operator, lexer::TokenType::And | lexer::TokenType::Or)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedRange {
+    pub ty: AnnotationType,
+    pub start: Box<TypedExpr>,
+    pub end: Box<TypedExpr>,
+    pub inclusive: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedPrefix {
+    pub ty: AnnotationType,
+    pub val: Box<TypedExpr>,
+    pub operator: lexer::TokenType,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedIfBranch {
+    pub cond: TypedExpr,
+    pub block: TypedBlock,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedElseBranch {
+    pub block: TypedBlock,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedConditional {
+    pub ty: AnnotationType,
+    pub if_branches: Vec<TypedIfBranch>,
+    pub else_branch: Option<TypedElseBranch>,
+}
+
+impl TypedConditional {
+    /// Whether every branch returns, so the conditional never evaluates to a value
+    pub fn diverges(&self) -> bool {
+        match &self.else_branch {
+            Some(else_branch) => {
+                else_branch.block.diverges()
+                    && self
+                        .if_branches
+                        .iter()
+                        .all(|branch| branch.block.diverges())
+            }
+            // A missing else branch can always be taken
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// What stops a loop
+pub enum TypedLoopKind {
+    Loop,
+    While(Box<TypedExpr>),
+    /// Counts through the values of `range`, `binder` is the current count
+    For {
+        binder: TypedBinder,
+        range: Box<TypedExpr>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedLoop {
+    /// Unique in the module, `break` and `continue` refer to their loop with it
+    pub id: usize,
+    pub ty: AnnotationType,
+    pub kind: TypedLoopKind,
+    pub block: TypedBlock,
+    /// Whether a `break` leaves this loop
+    pub breaks: bool,
+}
+
+impl TypedLoop {
+    /// Whether the loop never finishes, only a `loop` without `break` can't
+    pub fn diverges(&self) -> bool {
+        matches!(self.kind, TypedLoopKind::Loop) && !self.breaks
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedBreak {
+    pub loop_id: usize,
+    /// Type of the loop it leaves
+    pub loop_ty: AnnotationType,
+    pub expr: Option<Box<TypedExpr>>,
+    pub ty: AnnotationType,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedContinue {
+    pub loop_id: usize,
+    pub ty: AnnotationType,
+}
+
+#[derive(Clone, Debug)]
+pub enum TypedPatternKind {
+    Wildcard,
+    /// Literal, or a negated integer literal
+    Literal(Box<TypedExpr>),
+    Binding(TypedBinder),
+    Tuple(Vec<TypedPattern>),
+    /// From `start` up to `end`, which is only matched if the range is inclusive
+    Range {
+        start: Box<TypedExpr>,
+        end: Box<TypedExpr>,
+        inclusive: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedPattern {
+    pub kind: TypedPatternKind,
+    /// Type of the values it matches
+    pub ty: AnnotationType,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedMatchArm {
+    pub pattern: TypedPattern,
+    pub guard: Option<TypedExpr>,
+    pub expr: TypedExpr,
+    pub pos: helpers::Pos,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedMatch {
+    pub ty: AnnotationType,
+    pub expr: Box<TypedExpr>,
+    pub arms: Vec<TypedMatchArm>,
+}
+
+impl TypedMatch {
+    /// Whether every arm diverges, so the match never evaluates to a value
+    pub fn diverges(&self) -> bool {
+        self.arms.iter().all(|arm| arm.expr.diverges())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum TypedExprEnum {
     Tuple(TypedTuple),
@@ -48,10 +242,19 @@ pub enum TypedExprEnum {
     Literal(TypedLiteral),
     RefID(TypedRefID),
     Is(TypedIs),
+    As(TypedAs),
     FunctionCall(TypedFunctionCall),
     Yield(TypedYield),
     Return(TypedReturn),
     Function(TypedFunction),
+    Infix(TypedInfix),
+    Prefix(TypedPrefix),
+    Range(TypedRange),
+    Conditional(TypedConditional),
+    Loop(TypedLoop),
+    Break(TypedBreak),
+    Continue(TypedContinue),
+    Match(TypedMatch),
 }
 
 #[derive(Clone, Debug)]
@@ -72,6 +275,7 @@ impl TypedExpr {
             TypedExprEnum::RefID(val) => &val.ty,
 
             TypedExprEnum::Is(val) => &val.ty,
+            TypedExprEnum::As(val) => &val.ty,
 
             TypedExprEnum::FunctionCall(val) => &val.ty,
             TypedExprEnum::Function(val) => &val.ty,
@@ -80,6 +284,31 @@ impl TypedExpr {
             TypedExprEnum::Return(val) => &val.ty,
 
             TypedExprEnum::Tuple(val) => &val.ty,
+
+            TypedExprEnum::Infix(val) => &val.ty,
+            TypedExprEnum::Prefix(val) => &val.ty,
+            TypedExprEnum::Range(val) => &val.ty,
+
+            TypedExprEnum::Conditional(val) => &val.ty,
+
+            TypedExprEnum::Loop(val) => &val.ty,
+            TypedExprEnum::Break(val) => &val.ty,
+            TypedExprEnum::Continue(val) => &val.ty,
+
+            TypedExprEnum::Match(val) => &val.ty,
+        }
+    }
+
+    /// Whether evaluating this expression never finishes, i.e. it returns from the function
+    /// or jumps out of a loop
+    pub fn diverges(&self) -> bool {
+        match &self.expr {
+            TypedExprEnum::Return(_) | TypedExprEnum::Break(_) | TypedExprEnum::Continue(_) => true,
+            TypedExprEnum::Loop(loop_expr) => loop_expr.diverges(),
+            TypedExprEnum::Block(block) => block.diverges(),
+            TypedExprEnum::Conditional(conditional) => conditional.diverges(),
+            TypedExprEnum::Match(match_expr) => match_expr.diverges(),
+            _ => false,
         }
     }
 }
@@ -88,6 +317,8 @@ impl TypedExpr {
 pub struct TypedBinder {
     pub name: Option<Rc<ast::Namespace>>,
     pub ty: AnnotationType,
+    /// Whether the binding can be assigned a new value, i.e. `let mut x = 0;`
+    pub mutable: bool,
     pub pos: helpers::Pos,
 }
 
@@ -115,8 +346,14 @@ impl fmt::Display for TypedBinder {
 }
 
 impl TypedBinder {
+    /// Immutable binding
     pub fn new(name: Option<Rc<ast::Namespace>>, ty: AnnotationType, pos: helpers::Pos) -> Self {
-        Self { name, ty, pos }
+        Self {
+            name,
+            ty,
+            mutable: false,
+            pos,
+        }
     }
 }
 
@@ -124,11 +361,24 @@ impl TypedBinder {
 pub struct TypedAssign {
     pub binder: TypedBinder,
     pub expr: Box<TypedExpr>,
+    pub visibility: ast::Visibility,
+    /// Doc comments of a declaration
+    pub docs: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+/// Declaration without a value, i.e. `extern let print_long: (i64) -> ();`
+pub struct TypedDeclaration {
+    pub binder: TypedBinder,
+    pub is_extern: bool,
+    pub visibility: ast::Visibility,
+    pub docs: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct TypedFunction {
     pub ty: AnnotationType,
+    pub arg_names: Vec<Rc<ast::Namespace>>,
     pub block: Box<TypedExpr>,
 }
 
@@ -144,10 +394,27 @@ pub struct TypedReturn {
     pub ty: AnnotationType,
 }
 
+#[derive(Clone, Debug)]
+pub struct TypedTypeAssign {
+    pub name: Rc<ast::Namespace>,
+    pub ty: AnnotationType,
+    pub visibility: ast::Visibility,
+    pub docs: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypedUnit {
+    pub name: Rc<ast::Namespace>,
+    pub stmts: Vec<TypedStmt>,
+    pub docs: Option<String>,
+}
+
 #[derive(Clone, Debug)]
 pub enum TypedStmtEnum {
     Expression(TypedExpr),
-    VariableDeclaration(TypedBinder),
+    VariableDeclaration(TypedDeclaration),
+    TypeAssign(TypedTypeAssign),
+    Unit(TypedUnit),
     Tag(ast::Tag),
 }
 
@@ -162,3 +429,24 @@ pub struct TypedBlock {
     pub stmts: Vec<TypedStmt>,
     pub ty: AnnotationType,
 }
+
+impl TypedBlock {
+    /// Whether the block never finishes, see `TypedExpr::diverges`
+    pub fn diverges(&self) -> bool {
+        self.stmts.iter().any(|stmt| match &stmt.stmt {
+            TypedStmtEnum::Expression(expr) => expr.diverges(),
+            _ => false,
+        })
+    }
+
+    /// Whether the block yields a value
+    pub fn yields(&self) -> bool {
+        self.stmts.iter().any(|stmt| match &stmt.stmt {
+            TypedStmtEnum::Expression(TypedExpr {
+                expr: TypedExprEnum::Yield(_),
+                ..
+            }) => true,
+            _ => false,
+        })
+    }
+}
@@ -1,5 +1,7 @@
+use crate::helpers::Pos;
 use crate::typecheck::annotation::{
-    AnnotationType, TypedExpr, TypedExprEnum, TypedFunction, TypedStmt, TypedStmtEnum,
+    AnnotationType, Prim, TypedBlock, TypedExpr, TypedExprEnum, TypedFunction, TypedLoopKind,
+    TypedPattern, TypedPatternKind, TypedStmt, TypedStmtEnum,
 };
 
 use std::collections::HashSet;
@@ -60,8 +62,70 @@ pub fn generate(
                     .0
                     .extend(generate_expr(expr, outer_ty.clone(), inner_ty.clone()).0);
             }
-            TypedStmtEnum::Tag(_) => {}
-            _ => panic!("Unimplemented {:?}", node),
+            TypedStmtEnum::Unit(unit) => {
+                constraints
+                    .0
+                    .extend(generate(&unit.stmts, outer_ty.clone(), inner_ty.clone()).0);
+            }
+            TypedStmtEnum::VariableDeclaration(_)
+            | TypedStmtEnum::TypeAssign(_)
+            | TypedStmtEnum::Tag(_) => {}
+        }
+    }
+    constraints
+}
+
+fn generate_block(block: &TypedBlock, pos: Pos, outer_ty: Option<AnnotationType>) -> Constraints {
+    let mut constraints = generate(&block.stmts, outer_ty, Some(block.ty.clone()));
+
+    if !block.yields() {
+        let ty = if block.diverges() {
+            AnnotationType::Never(pos)
+        } else {
+            // Blocks without a `yield` evaluate to `()`
+            AnnotationType::Tuple(Rc::new(Vec::new()), pos)
+        };
+        constraints.0.insert(Constraint::new(block.ty.clone(), ty));
+    }
+
+    constraints
+}
+
+/// Everything inside of a pattern has the type of the values it matches
+fn generate_pattern(pattern: &TypedPattern) -> Constraints {
+    let mut constraints = Constraints::new();
+    match &pattern.kind {
+        TypedPatternKind::Wildcard => {}
+        TypedPatternKind::Literal(literal) => {
+            constraints.0.extend(generate_expr(literal, None, None).0);
+            constraints
+                .0
+                .insert(Constraint::new(pattern.ty.clone(), literal.ty().clone()));
+        }
+        TypedPatternKind::Binding(binder) => {
+            constraints
+                .0
+                .insert(Constraint::new(pattern.ty.clone(), binder.ty.clone()));
+        }
+        TypedPatternKind::Tuple(patterns) => {
+            for item in patterns {
+                constraints.0.extend(generate_pattern(item).0);
+            }
+            constraints.0.insert(Constraint::new(
+                pattern.ty.clone(),
+                AnnotationType::Tuple(
+                    Rc::new(patterns.iter().map(|item| item.ty.clone()).collect()),
+                    pattern.pos,
+                ),
+            ));
+        }
+        TypedPatternKind::Range { start, end, .. } => {
+            for bound in &[start, end] {
+                constraints.0.extend(generate_expr(bound, None, None).0);
+                constraints
+                    .0
+                    .insert(Constraint::new(pattern.ty.clone(), bound.ty().clone()));
+            }
         }
     }
     constraints
@@ -74,10 +138,12 @@ fn generate_expr(
 ) -> Constraints {
     let mut constraints = Constraints::new();
     match &expr.expr {
-        TypedExprEnum::Function(TypedFunction {
-            ty: ty @ AnnotationType::Function(ref func_args, ref ret, _),
-            block,
-        }) => {
+        TypedExprEnum::Function(TypedFunction { ty, block, .. }) => {
+            // The annotator gives every function a function type
+            let (func_args, ret) = match ty {
+                AnnotationType::Function(func_args, ret, _) => (func_args, ret),
+                _ => unreachable!(),
+            };
             constraints.0.extend(
                 // Set the outer and inner return type to be function annotation type
                 generate_expr(
@@ -87,14 +153,17 @@ fn generate_expr(
                 )
                 .0,
             );
-            constraints.0.insert(Constraint::new(
-                ty.clone(),
-                AnnotationType::Function(
-                    Rc::clone(func_args),
-                    Rc::new(block.ty().clone()),
-                    expr.pos,
-                ),
-            ));
+            // A body that always returns has type never, which says nothing about the return type
+            if !block.diverges() {
+                constraints.0.insert(Constraint::new(
+                    ty.clone(),
+                    AnnotationType::Function(
+                        Rc::clone(func_args),
+                        Rc::new(block.ty().clone()),
+                        expr.pos,
+                    ),
+                ));
+            }
         }
 
         TypedExprEnum::FunctionCall(func_call) => {
@@ -120,6 +189,16 @@ fn generate_expr(
             ));
         }
 
+        TypedExprEnum::VariableAssign(assign) => {
+            constraints
+                .0
+                .extend(generate_expr(assign.expr.as_ref(), outer_ty, inner_ty).0);
+            constraints.0.insert(Constraint::new(
+                assign.binder.ty.clone(),
+                assign.expr.as_ref().ty().clone(),
+            ));
+        }
+
         TypedExprEnum::VariableAssignDeclaration(assign_dec) => {
             constraints
                 .0
@@ -157,10 +236,166 @@ fn generate_expr(
         TypedExprEnum::Block(block) => {
             constraints
                 .0
-                .extend(generate(&block.stmts, outer_ty, Some(block.ty.clone())).0);
+                .extend(generate_block(block, expr.pos, outer_ty).0);
+        }
+
+        TypedExprEnum::Conditional(conditional) => {
+            for branch in &conditional.if_branches {
+                constraints
+                    .0
+                    .extend(generate_expr(&branch.cond, outer_ty.clone(), inner_ty.clone()).0);
+                constraints.0.insert(Constraint::new(
+                    branch.cond.ty().clone(),
+                    AnnotationType::Prim(Prim::Bool, branch.cond.pos),
+                ));
+
+                constraints
+                    .0
+                    .extend(generate_block(&branch.block, branch.pos, outer_ty.clone()).0);
+                // Branches that return never give the conditional a value
+                if !branch.block.diverges() {
+                    constraints.0.insert(Constraint::new(
+                        conditional.ty.clone(),
+                        branch.block.ty.clone(),
+                    ));
+                }
+            }
+
+            match &conditional.else_branch {
+                Some(else_branch) => {
+                    constraints
+                        .0
+                        .extend(generate_block(&else_branch.block, else_branch.pos, outer_ty).0);
+                    if !else_branch.block.diverges() {
+                        constraints.0.insert(Constraint::new(
+                            conditional.ty.clone(),
+                            else_branch.block.ty.clone(),
+                        ));
+                    }
+                }
+                // Without an `else`, there is no value when every condition is false
+                None => {
+                    constraints.0.insert(Constraint::new(
+                        conditional.ty.clone(),
+                        AnnotationType::Tuple(Rc::new(Vec::new()), expr.pos),
+                    ));
+                }
+            }
+
+            if conditional.diverges() {
+                constraints.0.insert(Constraint::new(
+                    conditional.ty.clone(),
+                    AnnotationType::Never(expr.pos),
+                ));
+            }
+        }
+
+        TypedExprEnum::Loop(loop_expr) => {
+            match &loop_expr.kind {
+                TypedLoopKind::Loop => {}
+                TypedLoopKind::While(cond) => {
+                    constraints
+                        .0
+                        .extend(generate_expr(cond, outer_ty.clone(), inner_ty.clone()).0);
+                    constraints.0.insert(Constraint::new(
+                        cond.ty().clone(),
+                        AnnotationType::Prim(Prim::Bool, cond.pos),
+                    ));
+                }
+                TypedLoopKind::For { binder, range } => {
+                    constraints
+                        .0
+                        .extend(generate_expr(range, outer_ty.clone(), inner_ty.clone()).0);
+                    constraints.0.insert(Constraint::new(
+                        range.ty().clone(),
+                        AnnotationType::Range(Rc::new(binder.ty.clone()), range.pos),
+                    ));
+                }
+            }
+
+            constraints
+                .0
+                .extend(generate_block(&loop_expr.block, expr.pos, outer_ty).0);
+
+            // A `loop` gets its value from its `break`, the others can't have a value
+            let ty = match loop_expr.kind {
+                TypedLoopKind::Loop if loop_expr.breaks => None,
+                TypedLoopKind::Loop => Some(AnnotationType::Never(expr.pos)),
+                _ => Some(AnnotationType::Tuple(Rc::new(Vec::new()), expr.pos)),
+            };
+            if let Some(ty) = ty {
+                constraints
+                    .0
+                    .insert(Constraint::new(loop_expr.ty.clone(), ty));
+            }
+        }
+
+        TypedExprEnum::Break(break_expr) => {
+            let value_ty = match &break_expr.expr {
+                Some(value) => {
+                    constraints
+                        .0
+                        .extend(generate_expr(value, outer_ty, inner_ty).0);
+                    value.ty().clone()
+                }
+                // `break;` leaves with `()`
+                None => AnnotationType::Tuple(Rc::new(Vec::new()), expr.pos),
+            };
+            constraints
+                .0
+                .insert(Constraint::new(break_expr.loop_ty.clone(), value_ty));
         }
 
-        TypedExprEnum::RefID(_) => {}
+        TypedExprEnum::Match(match_expr) => {
+            constraints
+                .0
+                .extend(generate_expr(&match_expr.expr, outer_ty.clone(), inner_ty.clone()).0);
+
+            for arm in &match_expr.arms {
+                constraints.0.extend(generate_pattern(&arm.pattern).0);
+                constraints.0.insert(Constraint::new(
+                    arm.pattern.ty.clone(),
+                    match_expr.expr.ty().clone(),
+                ));
+
+                if let Some(guard) = &arm.guard {
+                    constraints
+                        .0
+                        .extend(generate_expr(guard, outer_ty.clone(), inner_ty.clone()).0);
+                    constraints.0.insert(Constraint::new(
+                        guard.ty().clone(),
+                        AnnotationType::Prim(Prim::Bool, guard.pos),
+                    ));
+                }
+
+                constraints
+                    .0
+                    .extend(generate_expr(&arm.expr, outer_ty.clone(), inner_ty.clone()).0);
+                // Arms that diverge never give the match a value
+                if !arm.expr.diverges() {
+                    constraints.0.insert(Constraint::new(
+                        match_expr.ty.clone(),
+                        arm.expr.ty().clone(),
+                    ));
+                }
+            }
+
+            if match_expr.diverges() {
+                constraints.0.insert(Constraint::new(
+                    match_expr.ty.clone(),
+                    AnnotationType::Never(expr.pos),
+                ));
+            }
+        }
+
+        TypedExprEnum::RefID(_) | TypedExprEnum::Continue(_) => {}
+
+        // The converted value keeps its own type, `substitute` checks the conversion is valid
+        TypedExprEnum::As(as_expr) => {
+            constraints
+                .0
+                .extend(generate_expr(as_expr.expr.as_ref(), outer_ty, inner_ty).0);
+        }
 
         TypedExprEnum::Is(is) => {
             constraints
@@ -190,7 +425,54 @@ fn generate_expr(
             ));
         }
 
-        _ => unimplemented!(),
+        TypedExprEnum::Infix(infix) => {
+            constraints
+                .0
+                .extend(generate_expr(infix.left.as_ref(), outer_ty.clone(), inner_ty.clone()).0);
+            constraints
+                .0
+                .extend(generate_expr(infix.right.as_ref(), outer_ty, inner_ty).0);
+
+            // Both operands are the same type
+            constraints.0.insert(Constraint::new(
+                infix.left.ty().clone(),
+                infix.right.ty().clone(),
+            ));
+
+            let result_ty = if infix.is_comparison() {
+                AnnotationType::Prim(Prim::Bool, expr.pos)
+            } else {
+                infix.left.ty().clone()
+            };
+            constraints
+                .0
+                .insert(Constraint::new(infix.ty.clone(), result_ty));
+        }
+
+        TypedExprEnum::Prefix(prefix) => {
+            constraints
+                .0
+                .extend(generate_expr(prefix.val.as_ref(), outer_ty, inner_ty).0);
+            constraints
+                .0
+                .insert(Constraint::new(prefix.ty.clone(), prefix.val.ty().clone()));
+        }
+        TypedExprEnum::Range(range) => {
+            // Both endpoints have the type of the values of the range
+            for bound in &[&range.start, &range.end] {
+                constraints
+                    .0
+                    .extend(generate_expr(bound, outer_ty.clone(), inner_ty.clone()).0);
+            }
+            constraints.0.insert(Constraint::new(
+                range.start.ty().clone(),
+                range.end.ty().clone(),
+            ));
+            constraints.0.insert(Constraint::new(
+                range.ty.clone(),
+                AnnotationType::Range(Rc::new(range.start.ty().clone()), expr.pos),
+            ));
+        }
     }
     constraints
 }
@@ -0,0 +1,482 @@
+use super::annotation::*;
+
+use crate::helpers::Pos;
+use crate::lexer;
+use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
+use crate::parser::ast::LiteralType;
+use crate::sourcemap::SourceMap;
+
+/// Largest char, one past it ends the values of `char`
+const CHAR_MAX: i128 = 0x10FFFF;
+/// Most missing patterns a diagnostic names
+const MAX_LISTED: usize = 3;
+
+/// What a pattern matches, without its bindings
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Bool(bool),
+    Tuple(Vec<Pat>),
+    /// Integers or chars from the first value up to the second one excluded
+    Range(i128, i128),
+}
+
+/// Values of a type, as far as patterns can tell them apart
+enum Space {
+    Bool,
+    Tuple(Vec<Space>),
+    /// Integers or chars from `min` up to `end` excluded
+    Range {
+        min: i128,
+        end: i128,
+        is_char: bool,
+    },
+    /// Only matched by wildcards and bindings, i.e. `str`
+    Opaque,
+}
+
+impl Space {
+    fn new(ty: &AnnotationType) -> Space {
+        match ty {
+            AnnotationType::Prim(Prim::Bool, _) => Space::Bool,
+            AnnotationType::Prim(Prim::Char, _) => Space::Range {
+                min: 0,
+                end: CHAR_MAX + 1,
+                is_char: true,
+            },
+            AnnotationType::Prim(prim, _) if prim.is_integer() => {
+                let max = prim.max_value().unwrap() as i128;
+                Space::Range {
+                    min: if prim.is_signed() { -max - 1 } else { 0 },
+                    end: max + 1,
+                    is_char: false,
+                }
+            }
+            AnnotationType::Tuple(tys, _) => Space::Tuple(tys.iter().map(Space::new).collect()),
+            _ => Space::Opaque,
+        }
+    }
+
+    /// Pattern as it would be written, `_` stands for every value
+    fn show(&self, pat: &Pat) -> String {
+        match (self, pat) {
+            (_, Pat::Wild) => "_".to_string(),
+            (_, Pat::Bool(value)) => value.to_string(),
+            (Space::Tuple(spaces), Pat::Tuple(pats)) => {
+                let items: Vec<String> = spaces
+                    .iter()
+                    .zip(pats.iter())
+                    .map(|(space, pat)| space.show(pat))
+                    .collect();
+                match items.len() {
+                    1 => format!("({},)", items[0]),
+                    _ => format!("({})", items.join(", ")),
+                }
+            }
+            (Space::Range { min, end, is_char }, Pat::Range(start, stop)) => {
+                let show_value = |value: i128| match std::char::from_u32(value as u32) {
+                    Some(c) if *is_char => format!("{:?}", c),
+                    None if *is_char => format!("'\\u{{{:x}}}'", value),
+                    _ => value.to_string(),
+                };
+
+                if start == min && stop == end {
+                    "_".to_string()
+                } else if *stop == start + 1 {
+                    show_value(*start)
+                } else if stop == end {
+                    format!("{}..={}", show_value(*start), show_value(stop - 1))
+                } else {
+                    format!("{}..{}", show_value(*start), show_value(*stop))
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Constructor splitting the values of a space, every pattern matches all or none of its values
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    Tuple(usize),
+    Range(i128, i128),
+    Opaque,
+}
+
+impl Ctor {
+    /// Constructors covering `space`, split so that each is matched entirely or not at all by
+    /// any of `heads`
+    fn split(space: &Space, heads: &[&Pat]) -> Vec<Ctor> {
+        match space {
+            Space::Bool => vec![Ctor::Bool(true), Ctor::Bool(false)],
+            Space::Tuple(spaces) => vec![Ctor::Tuple(spaces.len())],
+            Space::Range { min, end, .. } => {
+                let mut bounds = vec![*min, *end];
+                for head in heads {
+                    if let Pat::Range(start, stop) = head {
+                        bounds.push((*start).clamp(*min, *end));
+                        bounds.push((*stop).clamp(*min, *end));
+                    }
+                }
+                bounds.sort_unstable();
+                bounds.dedup();
+
+                bounds
+                    .windows(2)
+                    .map(|bounds| Ctor::Range(bounds[0], bounds[1]))
+                    .collect()
+            }
+            Space::Opaque => vec![Ctor::Opaque],
+        }
+    }
+
+    /// Whether the constructor is one of the patterns of `pat`, wildcards excluded
+    fn is_named_by(&self, pat: &Pat) -> bool {
+        match (self, pat) {
+            (Ctor::Bool(value), Pat::Bool(other)) => value == other,
+            (Ctor::Tuple(_), Pat::Tuple(_)) => true,
+            (Ctor::Range(start, stop), Pat::Range(other_start, other_stop)) => {
+                other_start <= start && stop <= other_stop
+            }
+            _ => false,
+        }
+    }
+
+    /// Spaces of the values inside the constructor
+    fn fields<'a>(&self, space: &'a Space) -> Vec<&'a Space> {
+        match space {
+            Space::Tuple(spaces) => spaces.iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Pattern for the constructor with its fields
+    fn apply(&self, fields: Vec<Pat>) -> Pat {
+        match self {
+            Ctor::Bool(value) => Pat::Bool(*value),
+            Ctor::Tuple(_) => Pat::Tuple(fields),
+            Ctor::Range(start, stop) => Pat::Range(*start, *stop),
+            Ctor::Opaque => Pat::Wild,
+        }
+    }
+}
+
+/// Remaining row after matching the constructor with the head of the row, `None` if the row
+/// doesn't match it
+fn specialize(row: &[Pat], ctor: Ctor, arity: usize) -> Option<Vec<Pat>> {
+    let mut specialized = match &row[0] {
+        Pat::Wild => vec![Pat::Wild; arity],
+        Pat::Tuple(pats) if ctor.is_named_by(&row[0]) => pats.clone(),
+        pat if ctor.is_named_by(pat) => Vec::new(),
+        _ => return None,
+    };
+    specialized.extend_from_slice(&row[1..]);
+    Some(specialized)
+}
+
+/// Values matched by `row` but by none of `rows`, written as patterns
+fn uncovered(rows: &[Vec<Pat>], row: &[Pat], spaces: &[&Space]) -> Vec<Vec<Pat>> {
+    if row.is_empty() {
+        return if rows.is_empty() {
+            vec![Vec::new()]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let mut heads: Vec<&Pat> = rows.iter().map(|row| &row[0]).collect();
+    heads.push(&row[0]);
+    let mut ctors = Ctor::split(spaces[0], &heads);
+    let mut witnesses = Vec::new();
+
+    // The values of constructors a wildcard covers but no row names are only matched by the
+    // rows starting with a wildcard, whatever follows
+    if let Pat::Wild = row[0] {
+        let missing: Vec<Ctor> = ctors
+            .iter()
+            .filter(|ctor| !rows.iter().any(|row| ctor.is_named_by(&row[0])))
+            .copied()
+            .collect();
+
+        if !missing.is_empty() {
+            let default: Vec<Vec<Pat>> = rows
+                .iter()
+                .filter(|row| matches!(row[0], Pat::Wild))
+                .map(|row| row[1..].to_vec())
+                .collect();
+
+            // Nothing named the head, so it is missing as a whole
+            let heads = if missing.len() == ctors.len() {
+                vec![Pat::Wild]
+            } else {
+                missing.iter().map(|ctor| ctor.apply(Vec::new())).collect()
+            };
+
+            for witness in uncovered(&default, &row[1..], &spaces[1..]) {
+                for head in heads.iter() {
+                    let mut full = vec![head.clone()];
+                    full.extend_from_slice(&witness);
+                    witnesses.push(full);
+                }
+            }
+            ctors.retain(|ctor| !missing.contains(ctor));
+        }
+    }
+
+    for ctor in ctors.into_iter() {
+        let fields = ctor.fields(spaces[0]);
+        let row = match specialize(row, ctor, fields.len()) {
+            Some(row) => row,
+            None => continue,
+        };
+        let rows: Vec<Vec<Pat>> = rows
+            .iter()
+            .filter_map(|row| specialize(row, ctor, fields.len()))
+            .collect();
+        let mut sub_spaces = fields.clone();
+        sub_spaces.extend_from_slice(&spaces[1..]);
+
+        for witness in uncovered(&rows, &row, &sub_spaces) {
+            let mut full = vec![ctor.apply(witness[..fields.len()].to_vec())];
+            full.extend_from_slice(&witness[fields.len()..]);
+            witnesses.push(full);
+        }
+    }
+    witnesses
+}
+
+/// Checks that every `match` covers all values of its scrutinee and has no dead arms
+pub struct Exhaustiveness {
+    sourcemap: SourceMap,
+    errors: Vec<ErrorValue>,
+    /// Arms that can never be reached
+    warnings: Vec<ErrorValue>,
+}
+
+impl Exhaustiveness {
+    pub fn new(sourcemap: SourceMap) -> Exhaustiveness {
+        Exhaustiveness {
+            sourcemap,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Check every match in `stmts`, including nested ones
+    pub fn check(&mut self, stmts: &[TypedStmt]) -> Result<(), Vec<ErrorValue>> {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Warnings found so far, i.e. unreachable arms
+    pub fn take_warnings(&mut self) -> Vec<ErrorValue> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) {
+        match &stmt.stmt {
+            TypedStmtEnum::Expression(expr) => self.expr(expr),
+            TypedStmtEnum::Unit(unit) => {
+                for stmt in unit.stmts.iter() {
+                    self.stmt(stmt);
+                }
+            }
+            TypedStmtEnum::VariableDeclaration(_)
+            | TypedStmtEnum::TypeAssign(_)
+            | TypedStmtEnum::Tag(_) => {}
+        }
+    }
+
+    fn block(&mut self, block: &TypedBlock) {
+        for stmt in block.stmts.iter() {
+            self.stmt(stmt);
+        }
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) {
+        match &expr.expr {
+            TypedExprEnum::Tuple(tuple) => {
+                for expr in tuple.exprs.iter() {
+                    self.expr(expr);
+                }
+            }
+            TypedExprEnum::Block(block) => self.block(block),
+            TypedExprEnum::VariableAssign(assign)
+            | TypedExprEnum::VariableAssignDeclaration(assign) => self.expr(&assign.expr),
+            TypedExprEnum::Is(is) => self.expr(&is.expr),
+            TypedExprEnum::As(as_expr) => self.expr(&as_expr.expr),
+            TypedExprEnum::FunctionCall(call) => {
+                for expr in call.arguments.iter() {
+                    self.expr(expr);
+                }
+            }
+            TypedExprEnum::Yield(yield_expr) => self.expr(&yield_expr.expr),
+            TypedExprEnum::Return(ret) => self.expr(&ret.expr),
+            TypedExprEnum::Function(func) => self.expr(&func.block),
+            TypedExprEnum::Infix(infix) => {
+                self.expr(&infix.left);
+                self.expr(&infix.right);
+            }
+            TypedExprEnum::Prefix(prefix) => self.expr(&prefix.val),
+            TypedExprEnum::Range(range) => {
+                self.expr(&range.start);
+                self.expr(&range.end);
+            }
+            TypedExprEnum::Conditional(conditional) => {
+                for branch in conditional.if_branches.iter() {
+                    self.expr(&branch.cond);
+                    self.block(&branch.block);
+                }
+                if let Some(else_branch) = &conditional.else_branch {
+                    self.block(&else_branch.block);
+                }
+            }
+            TypedExprEnum::Loop(loop_expr) => {
+                match &loop_expr.kind {
+                    TypedLoopKind::Loop => {}
+                    TypedLoopKind::While(cond) => self.expr(cond),
+                    TypedLoopKind::For { range, .. } => self.expr(range),
+                }
+                self.block(&loop_expr.block);
+            }
+            TypedExprEnum::Break(break_expr) => {
+                if let Some(value) = &break_expr.expr {
+                    self.expr(value);
+                }
+            }
+            TypedExprEnum::Match(match_expr) => {
+                self.expr(&match_expr.expr);
+                for arm in match_expr.arms.iter() {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.expr);
+                }
+                self.check_match(match_expr, expr.pos);
+            }
+            TypedExprEnum::Literal(_) | TypedExprEnum::RefID(_) | TypedExprEnum::Continue(_) => {}
+        }
+    }
+
+    fn check_match(&mut self, match_expr: &TypedMatch, pos: Pos) {
+        let space = Space::new(match_expr.expr.ty());
+
+        // Guarded arms may fail, so they never cover anything
+        let mut rows: Vec<Vec<Pat>> = Vec::new();
+        for arm in match_expr.arms.iter() {
+            let row = vec![self.pat(&arm.pattern)];
+            if uncovered(&rows, &row, &[&space]).is_empty() {
+                self.warnings.push(unreachable_arm(arm.pattern.pos));
+            }
+            if arm.guard.is_none() {
+                rows.push(row);
+            }
+        }
+
+        let missing: Vec<String> = uncovered(&rows, &[Pat::Wild], &[&space])
+            .iter()
+            .map(|witness| space.show(&witness[0]))
+            .collect();
+        if !missing.is_empty() {
+            self.errors
+                .push(non_exhaustive(&missing, match_expr.expr.pos, pos));
+        }
+    }
+
+    fn pat(&self, pattern: &TypedPattern) -> Pat {
+        match &pattern.kind {
+            TypedPatternKind::Wildcard | TypedPatternKind::Binding(_) => Pat::Wild,
+            TypedPatternKind::Literal(literal) => match &literal.expr {
+                TypedExprEnum::Literal(TypedLiteral { value, .. })
+                    if value.literal_type == LiteralType::Bool =>
+                {
+                    Pat::Bool(get_segment!(self.sourcemap, value.pos) == "true")
+                }
+                _ => {
+                    let value = self.value(literal);
+                    Pat::Range(value, value + 1)
+                }
+            },
+            TypedPatternKind::Tuple(patterns) => {
+                Pat::Tuple(patterns.iter().map(|pattern| self.pat(pattern)).collect())
+            }
+            TypedPatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let end = self.value(end) + *inclusive as i128;
+                Pat::Range(self.value(start), end)
+            }
+        }
+    }
+
+    /// Value of an integer or char literal in a pattern, possibly negated
+    fn value(&self, expr: &TypedExpr) -> i128 {
+        match &expr.expr {
+            TypedExprEnum::Prefix(prefix) => -self.value(&prefix.val),
+            TypedExprEnum::Literal(TypedLiteral { value, .. })
+                if value.literal_type == LiteralType::Char =>
+            {
+                let value = value.value.as_deref().unwrap_or_default();
+                value.chars().next().unwrap_or_default() as i128
+            }
+            TypedExprEnum::Literal(TypedLiteral { value, .. }) => {
+                // Literals were checked to fit in their type when substituted
+                let text = get_segment!(self.sourcemap, value.pos).to_string();
+                lexer::NumberParts::new(&text).int_value().unwrap() as i128
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn unreachable_arm(pos: Pos) -> ErrorValue {
+    ErrorValue::new(
+        "unreachable pattern".to_string(),
+        ErrorType::UnreachablePattern,
+        pos,
+        ErrorDisplayType::Warning,
+        vec![ErrorAnnotation::new(
+            Some("earlier arms already match every value of this pattern".to_string()),
+            pos,
+            ErrorDisplayType::Warning,
+        )],
+    )
+}
+
+fn non_exhaustive(missing: &[String], scrutinee_pos: Pos, pos: Pos) -> ErrorValue {
+    // Listing every missing pattern of a large tuple gets unreadable
+    let missing = match missing {
+        [pattern] => format!("pattern `{}`", pattern),
+        [patterns @ .., last] if missing.len() <= MAX_LISTED => {
+            format!("patterns `{}` and `{}`", patterns.join("`, `"), last)
+        }
+        _ => format!(
+            "patterns `{}` and {} more",
+            missing[..MAX_LISTED].join("`, `"),
+            missing.len() - MAX_LISTED
+        ),
+    };
+
+    ErrorValue::new(
+        format!("non-exhaustive match, {} not covered", missing),
+        ErrorType::NonExhaustive,
+        pos,
+        ErrorDisplayType::Error,
+        vec![ErrorAnnotation::new(
+            Some(format!("{} not covered", missing)),
+            scrutinee_pos,
+            ErrorDisplayType::Error,
+        )],
+    )
+    .with_note("add arms for the missing patterns or a `_` arm matching anything".to_string())
+}
@@ -0,0 +1,453 @@
+use super::annotation::*;
+
+use crate::helpers::Pos;
+use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
+use crate::parser::ast;
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Declarations without a value that have been given one, by their position
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// Given a value on every path that gets here
+    assigned: HashSet<Pos>,
+    /// Given a value on some path that gets here
+    maybe_assigned: HashSet<Pos>,
+    /// No path gets here, i.e. after a `return`
+    unreachable: bool,
+}
+
+impl State {
+    fn unreachable() -> State {
+        State {
+            unreachable: true,
+            ..State::default()
+        }
+    }
+
+    /// State where the paths of both states meet
+    fn join(self, other: State) -> State {
+        if self.unreachable {
+            return other;
+        } else if other.unreachable {
+            return self;
+        }
+
+        State {
+            assigned: self
+                .assigned
+                .intersection(&other.assigned)
+                .copied()
+                .collect(),
+            maybe_assigned: self
+                .maybe_assigned
+                .union(&other.maybe_assigned)
+                .copied()
+                .collect(),
+            unreachable: false,
+        }
+    }
+}
+
+/// Name in scope
+struct Binding {
+    name: Rc<ast::Namespace>,
+    /// Position of the declaration, if it was declared without a value
+    deferred: Option<Pos>,
+    /// Functions around the declaration
+    functions: usize,
+}
+
+/// Paths leaving the body of a loop
+struct LoopExits {
+    breaks: State,
+    continues: State,
+}
+
+/// Checks that every declaration without a value is given one on every path before it is used,
+/// and that it is given one at most once
+#[derive(Default)]
+pub struct Initialization {
+    /// Names in scope, the innermost one is last
+    bindings: Vec<Binding>,
+    state: State,
+    /// Exits of the loops we are currently in, by id
+    loops: HashMap<usize, LoopExits>,
+    /// Functions we are currently in
+    functions: usize,
+    errors: Vec<ErrorValue>,
+}
+
+impl Initialization {
+    /// Check the statements of a module, in the order they are run
+    pub fn check(&mut self, stmts: &[TypedStmt]) -> Result<(), Vec<ErrorValue>> {
+        // Declarations on the outer scope are visible before them, like in the annotator
+        self.declare_outer(stmts);
+        self.outer_stmts(stmts);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn declare_outer(&mut self, stmts: &[TypedStmt]) {
+        for stmt in stmts {
+            match &stmt.stmt {
+                TypedStmtEnum::VariableDeclaration(declaration) => self.declare(declaration),
+                TypedStmtEnum::Unit(unit) => self.declare_outer(&unit.stmts),
+                _ => {}
+            }
+        }
+    }
+
+    fn outer_stmts(&mut self, stmts: &[TypedStmt]) {
+        for stmt in stmts {
+            match &stmt.stmt {
+                TypedStmtEnum::Unit(unit) => self.outer_stmts(&unit.stmts),
+                // Already declared
+                TypedStmtEnum::VariableDeclaration(_) => {}
+                _ => self.stmt(stmt),
+            }
+        }
+    }
+
+    fn declare(&mut self, declaration: &TypedDeclaration) {
+        let deferred = if declaration.is_extern {
+            None
+        } else {
+            // Declared anew, i.e. on the next iteration of a loop
+            self.state.assigned.remove(&declaration.binder.pos);
+            self.state.maybe_assigned.remove(&declaration.binder.pos);
+            Some(declaration.binder.pos)
+        };
+        self.bind(declaration.binder.name.as_ref(), deferred);
+    }
+
+    /// Bind a name that has a value
+    fn bind(&mut self, name: Option<&Rc<ast::Namespace>>, deferred: Option<Pos>) {
+        if let Some(name) = name {
+            self.bindings.push(Binding {
+                name: Rc::clone(name),
+                deferred,
+                functions: self.functions,
+            });
+        }
+    }
+
+    fn find(&self, name: &ast::Namespace) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|binding| *binding.name == *name)
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) {
+        match &stmt.stmt {
+            TypedStmtEnum::Expression(expr) => self.expr(expr),
+            TypedStmtEnum::VariableDeclaration(declaration) => self.declare(declaration),
+            TypedStmtEnum::Unit(unit) => self.outer_stmts(&unit.stmts),
+            TypedStmtEnum::TypeAssign(_) | TypedStmtEnum::Tag(_) => {}
+        }
+    }
+
+    fn block(&mut self, block: &TypedBlock) {
+        for stmt in block.stmts.iter() {
+            self.stmt(stmt);
+        }
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) {
+        match &expr.expr {
+            TypedExprEnum::Tuple(tuple) => {
+                for expr in tuple.exprs.iter() {
+                    self.expr(expr);
+                }
+            }
+            TypedExprEnum::Block(block) => self.block(block),
+            TypedExprEnum::VariableAssign(assign) => {
+                self.expr(&assign.expr);
+                self.assign(assign, expr.pos);
+            }
+            TypedExprEnum::VariableAssignDeclaration(assign) => {
+                self.expr(&assign.expr);
+                self.bind(assign.binder.name.as_ref(), None);
+            }
+            TypedExprEnum::RefID(ref_id) => self.read(&ref_id.name, expr.pos),
+            TypedExprEnum::Is(is) => self.expr(&is.expr),
+            TypedExprEnum::As(as_expr) => self.expr(&as_expr.expr),
+            TypedExprEnum::FunctionCall(call) => {
+                for expr in call.arguments.iter() {
+                    self.expr(expr);
+                }
+            }
+            TypedExprEnum::Yield(yield_expr) => self.expr(&yield_expr.expr),
+            TypedExprEnum::Return(ret) => {
+                self.expr(&ret.expr);
+                self.state = State::unreachable();
+            }
+            TypedExprEnum::Function(func) => self.function(func),
+            TypedExprEnum::Infix(infix) if infix.is_logical() => {
+                // The right operand may not be evaluated
+                self.expr(&infix.left);
+                let skipped = self.state.clone();
+                self.expr(&infix.right);
+                self.state = skipped.join(std::mem::take(&mut self.state));
+            }
+            TypedExprEnum::Infix(infix) => {
+                self.expr(&infix.left);
+                self.expr(&infix.right);
+            }
+            TypedExprEnum::Prefix(prefix) => self.expr(&prefix.val),
+            TypedExprEnum::Range(range) => {
+                self.expr(&range.start);
+                self.expr(&range.end);
+            }
+            TypedExprEnum::Conditional(conditional) => self.conditional(conditional),
+            TypedExprEnum::Loop(loop_expr) => self.loop_expr(loop_expr),
+            TypedExprEnum::Break(break_expr) => {
+                if let Some(value) = &break_expr.expr {
+                    self.expr(value);
+                }
+                let state = std::mem::replace(&mut self.state, State::unreachable());
+                if let Some(exits) = self.loops.get_mut(&break_expr.loop_id) {
+                    exits.breaks = std::mem::take(&mut exits.breaks).join(state);
+                }
+            }
+            TypedExprEnum::Continue(continue_expr) => {
+                let state = std::mem::replace(&mut self.state, State::unreachable());
+                if let Some(exits) = self.loops.get_mut(&continue_expr.loop_id) {
+                    exits.continues = std::mem::take(&mut exits.continues).join(state);
+                }
+            }
+            TypedExprEnum::Match(match_expr) => self.match_expr(match_expr),
+            TypedExprEnum::Literal(_) => {}
+        }
+    }
+
+    fn read(&mut self, name: &ast::Namespace, pos: Pos) {
+        let declaration = match self.find(name) {
+            // Functions are called after the code declaring what they use has run
+            Some(Binding {
+                deferred: Some(declaration),
+                functions,
+                ..
+            }) if *functions == self.functions => *declaration,
+            _ => return,
+        };
+
+        if !self.state.unreachable && !self.state.assigned.contains(&declaration) {
+            self.errors.push(uninitialized_err(name, pos, declaration));
+        }
+    }
+
+    fn assign(&mut self, assign: &TypedAssign, pos: Pos) {
+        let name = match &assign.binder.name {
+            Some(name) => name,
+            None => return,
+        };
+        let (declaration, functions) = match self.find(name) {
+            Some(Binding {
+                deferred: Some(declaration),
+                functions,
+                ..
+            }) => (*declaration, *functions),
+            // Mutability is checked by the annotator
+            _ => return,
+        };
+
+        if functions != self.functions {
+            self.errors.push(outer_assign_err(name, pos, declaration));
+        } else if !self.state.unreachable && self.state.maybe_assigned.contains(&declaration) {
+            self.errors.push(assign_twice_err(name, pos, declaration));
+        }
+        self.state.assigned.insert(declaration);
+        self.state.maybe_assigned.insert(declaration);
+    }
+
+    fn function(&mut self, func: &TypedFunction) {
+        let bindings = self.bindings.len();
+        let state = std::mem::take(&mut self.state);
+        self.functions += 1;
+
+        for name in func.arg_names.iter() {
+            self.bind(Some(name), None);
+        }
+        self.expr(&func.block);
+
+        self.functions -= 1;
+        self.state = state;
+        self.bindings.truncate(bindings);
+    }
+
+    fn conditional(&mut self, conditional: &TypedConditional) {
+        let mut exits = State::unreachable();
+        for branch in conditional.if_branches.iter() {
+            self.expr(&branch.cond);
+            let skipped = self.state.clone();
+            self.block(&branch.block);
+            exits = exits.join(std::mem::replace(&mut self.state, skipped));
+        }
+
+        // Without an else branch, the state when every condition is false leaves it
+        if let Some(else_branch) = &conditional.else_branch {
+            self.block(&else_branch.block);
+        }
+        self.state = exits.join(std::mem::take(&mut self.state));
+    }
+
+    fn loop_expr(&mut self, loop_expr: &TypedLoop) {
+        if let TypedLoopKind::For { range, .. } = &loop_expr.kind {
+            self.expr(range);
+        }
+
+        // Find what one iteration gives the next one, without reporting anything, then check
+        // the body with the values it may already have from an earlier iteration
+        let entry = self.state.clone();
+        let errors = self.errors.len();
+        let (next, _) = self.iteration(loop_expr);
+        self.errors.truncate(errors);
+
+        self.state = entry.join(next);
+        let (_, exit) = self.iteration(loop_expr);
+        self.state = exit;
+    }
+
+    /// Check one iteration of a loop from the current state, returns the states going to the
+    /// next iteration and leaving the loop
+    fn iteration(&mut self, loop_expr: &TypedLoop) -> (State, State) {
+        let bindings = self.bindings.len();
+        let finished = match &loop_expr.kind {
+            TypedLoopKind::Loop => State::unreachable(),
+            TypedLoopKind::While(cond) => {
+                self.expr(cond);
+                self.state.clone()
+            }
+            TypedLoopKind::For { binder, .. } => {
+                let finished = self.state.clone();
+                self.bind(binder.name.as_ref(), None);
+                finished
+            }
+        };
+
+        self.loops.insert(
+            loop_expr.id,
+            LoopExits {
+                breaks: State::unreachable(),
+                continues: State::unreachable(),
+            },
+        );
+        self.block(&loop_expr.block);
+        let exits = self.loops.remove(&loop_expr.id).unwrap();
+        self.bindings.truncate(bindings);
+
+        let next = std::mem::take(&mut self.state).join(exits.continues);
+        (next, finished.join(exits.breaks))
+    }
+
+    fn match_expr(&mut self, match_expr: &TypedMatch) {
+        self.expr(&match_expr.expr);
+
+        // State when the arms before did not match, a failing guard keeps what it assigned
+        let mut skipped = self.state.clone();
+        let mut exits = State::unreachable();
+        for arm in match_expr.arms.iter() {
+            let bindings = self.bindings.len();
+            self.state = skipped.clone();
+            self.pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                self.expr(guard);
+                skipped = skipped.join(self.state.clone());
+            }
+            self.expr(&arm.expr);
+            exits = exits.join(std::mem::take(&mut self.state));
+            self.bindings.truncate(bindings);
+        }
+        self.state = exits;
+    }
+
+    fn pattern(&mut self, pattern: &TypedPattern) {
+        match &pattern.kind {
+            TypedPatternKind::Binding(binder) => self.bind(binder.name.as_ref(), None),
+            TypedPatternKind::Tuple(patterns) => {
+                for pattern in patterns.iter() {
+                    self.pattern(pattern);
+                }
+            }
+            TypedPatternKind::Wildcard
+            | TypedPatternKind::Literal(_)
+            | TypedPatternKind::Range { .. } => {}
+        }
+    }
+}
+
+fn declared_here(name: &ast::Namespace, declaration: Pos) -> ErrorAnnotation {
+    ErrorAnnotation::new(
+        Some(format!("`{}` is declared here without a value", name)),
+        declaration,
+        ErrorDisplayType::Info,
+    )
+}
+
+fn uninitialized_err(name: &ast::Namespace, pos: Pos, declaration: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("used binding `{}` is possibly uninitialized", name),
+        ErrorType::PossibleUninitVal,
+        pos,
+        ErrorDisplayType::Error,
+        vec![
+            ErrorAnnotation::new(
+                Some(format!(
+                    "`{}` is used here before it is given a value",
+                    name
+                )),
+                pos,
+                ErrorDisplayType::Error,
+            ),
+            declared_here(name, declaration),
+        ],
+    )
+    .with_note("every path reaching a use has to assign it first".to_string())
+}
+
+fn assign_twice_err(name: &ast::Namespace, pos: Pos, declaration: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("cannot assign twice to immutable binding `{}`", name),
+        ErrorType::ImmutableAssign,
+        pos,
+        ErrorDisplayType::Error,
+        vec![
+            ErrorAnnotation::new(
+                Some("it may already have a value here".to_string()),
+                pos,
+                ErrorDisplayType::Error,
+            ),
+            declared_here(name, declaration),
+        ],
+    )
+    .with_note("a declaration without a value can be given one only once".to_string())
+}
+
+fn outer_assign_err(name: &ast::Namespace, pos: Pos, declaration: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("cannot assign `{}` inside of a function", name),
+        ErrorType::ImmutableAssign,
+        pos,
+        ErrorDisplayType::Error,
+        vec![
+            ErrorAnnotation::new(
+                Some("assigned inside of the function".to_string()),
+                pos,
+                ErrorDisplayType::Error,
+            ),
+            declared_here(name, declaration),
+        ],
+    )
+    .with_note(format!(
+        "the function can be called any number of times, give `{}` its value where it is \
+         declared",
+        name
+    ))
+}
@@ -1,6 +1,8 @@
 pub mod annotation;
 pub mod constraint_gen;
 pub mod context;
+pub mod exhaustiveness;
+pub mod initialization;
 pub mod substitute;
 pub mod typecheck_module;
 pub mod unifier;
@@ -2,15 +2,19 @@ use super::annotation::*;
 use super::unifier::Substitutions;
 
 use crate::helpers::Pos;
+use crate::lexer;
 use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
-use crate::parser::ast::LiteralType;
+use crate::parser::{ast, ast::LiteralType};
+use crate::sourcemap::SourceMap;
 
 use std::rc::Rc;
 
 impl AnnotationType {
     fn sub(&mut self, solved_constraints: &Substitutions) -> Result<(), ErrorValue> {
         match self {
-            AnnotationType::Type(_, _) | AnnotationType::Never(_) => {}
+            AnnotationType::Prim(_, _) | AnnotationType::Never(_) => {}
+            // Aliases are expanded by the annotator, any other named type is undefined
+            AnnotationType::Type(name, pos) => return Err(undefined_type_err(name, *pos)),
             AnnotationType::Tuple(tys, _) => {
                 for ty in Rc::make_mut(tys).iter_mut() {
                     ty.sub(solved_constraints)?;
@@ -22,6 +26,7 @@ impl AnnotationType {
                 }
                 Rc::make_mut(ret_ty).sub(solved_constraints)?;
             }
+            AnnotationType::Range(ty, _) => Rc::make_mut(ty).sub(solved_constraints)?,
             // Important part!
             AnnotationType::Infer(infer_num, pos) => {
                 match solved_constraints.subs.get(&infer_num) {
@@ -48,74 +53,255 @@ impl TypedBinder {
 }
 
 impl TypedBlock {
-    fn substitute(&mut self, solved_constraints: &Substitutions) -> Result<(), ErrorValue> {
+    fn substitute(
+        &mut self,
+        solved_constraints: &Substitutions,
+        sourcemap: &SourceMap,
+    ) -> Result<(), ErrorValue> {
         self.ty.sub(solved_constraints)?;
         for stmt in self.stmts.iter_mut() {
-            stmt.substitute(solved_constraints)?;
+            stmt.substitute(solved_constraints, sourcemap)?;
+        }
+        Ok(())
+    }
+}
+
+impl TypedPattern {
+    fn substitute(
+        &mut self,
+        solved_constraints: &Substitutions,
+        sourcemap: &SourceMap,
+    ) -> Result<(), ErrorValue> {
+        self.ty.sub(solved_constraints)?;
+        match &mut self.kind {
+            TypedPatternKind::Wildcard => {}
+            TypedPatternKind::Literal(literal) => {
+                literal.substitute(solved_constraints, sourcemap)?
+            }
+            TypedPatternKind::Binding(binder) => binder.substitute(solved_constraints)?,
+            TypedPatternKind::Tuple(patterns) => {
+                for pattern in patterns.iter_mut() {
+                    pattern.substitute(solved_constraints, sourcemap)?;
+                }
+            }
+            TypedPatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                start.substitute(solved_constraints, sourcemap)?;
+                end.substitute(solved_constraints, sourcemap)?;
+
+                // Only integers and chars are ordered
+                match self.ty.is_primitive() {
+                    Some(prim) if prim.is_integer() || prim == Prim::Char => {}
+                    _ => return Err(bad_operand(&self.ty, range_operator(*inclusive), self.pos)),
+                }
+            }
         }
         Ok(())
     }
 }
 
 impl TypedExpr {
-    fn substitute(&mut self, solved_constraints: &Substitutions) -> Result<(), ErrorValue> {
+    fn substitute(
+        &mut self,
+        solved_constraints: &Substitutions,
+        sourcemap: &SourceMap,
+    ) -> Result<(), ErrorValue> {
         match &mut self.expr {
             TypedExprEnum::Tuple(tup) => {
                 tup.ty.sub(solved_constraints)?;
                 for ty in tup.exprs.iter_mut() {
-                    ty.substitute(solved_constraints)?;
+                    ty.substitute(solved_constraints, sourcemap)?;
                 }
             }
             TypedExprEnum::Block(block) => {
-                block.substitute(solved_constraints)?;
+                block.substitute(solved_constraints, sourcemap)?;
             }
             TypedExprEnum::Is(is) => {
                 is.ty.sub(solved_constraints)?;
-                is.expr.substitute(solved_constraints)?;
+                is.expr.substitute(solved_constraints, sourcemap)?;
+            }
+            TypedExprEnum::As(as_expr) => {
+                as_expr.ty.sub(solved_constraints)?;
+                as_expr.expr.substitute(solved_constraints, sourcemap)?;
+
+                let from = as_expr.expr.ty();
+                match (from.is_primitive(), as_expr.ty.is_primitive()) {
+                    (Some(from_prim), Some(to_prim)) if from_prim.can_cast(to_prim) => {}
+                    _ => return Err(bad_cast(from, &as_expr.ty, self.pos)),
+                }
             }
             TypedExprEnum::RefID(ref_id) => {
                 ref_id.ty.sub(solved_constraints)?;
             }
             TypedExprEnum::Yield(yie) => {
                 yie.ty.sub(solved_constraints)?;
-                yie.expr.substitute(solved_constraints)?;
+                yie.expr.substitute(solved_constraints, sourcemap)?;
             }
             TypedExprEnum::Return(ret) => {
                 ret.ty.sub(solved_constraints)?;
-                ret.expr.substitute(solved_constraints)?;
+                ret.expr.substitute(solved_constraints, sourcemap)?;
             }
             TypedExprEnum::Literal(literal) => {
-                literal.ty.sub(solved_constraints)?;
-                match literal.ty.is_primitive() {
-                    Some(prim) => match (prim, literal.value.literal_type) {
-                        (Prim::Bool, LiteralType::Bool) => {}
-                        (Prim::I16, LiteralType::Number) => {}
-                        (Prim::I32, LiteralType::Number) => {}
-                        (Prim::I64, LiteralType::Number) => {}
-
-                        (_, _) => return Err(bad_literal(&literal.ty, self.pos)),
-                    },
-                    None => return Err(bad_literal(&literal.ty, self.pos)),
-                }
+                literal.substitute(solved_constraints, sourcemap, false, self.pos)?
             }
             TypedExprEnum::Function(func) => {
                 func.ty.sub(solved_constraints)?;
-                func.block.substitute(solved_constraints)?;
+                func.block.substitute(solved_constraints, sourcemap)?;
             }
             TypedExprEnum::FunctionCall(call) => {
                 call.ty.sub(solved_constraints)?;
                 call.func_ty.sub(solved_constraints)?;
                 for ty in call.arguments.iter_mut() {
-                    ty.substitute(solved_constraints)?;
+                    ty.substitute(solved_constraints, sourcemap)?;
+                }
+
+                // `print` calls the printer taking the type of its argument
+                if !call.overloads.is_empty() {
+                    let arg_ty = call.arguments[0].ty();
+                    let printer = call.overloads.iter().find(|binder| match binder.ty {
+                        AnnotationType::Function(ref args, _, _) => args[0] == *arg_ty,
+                        _ => false,
+                    });
+                    match printer {
+                        Some(binder) => {
+                            call.name = Rc::clone(binder.name.as_ref().unwrap());
+                            call.func_ty = binder.ty.clone();
+                        }
+                        None => return Err(bad_print(arg_ty, self.pos)),
+                    }
                 }
             }
             TypedExprEnum::VariableAssign(var) => {
                 var.binder.substitute(solved_constraints)?;
-                var.expr.substitute(solved_constraints)?;
+                var.expr.substitute(solved_constraints, sourcemap)?;
             }
             TypedExprEnum::VariableAssignDeclaration(var) => {
                 var.binder.substitute(solved_constraints)?;
-                var.expr.substitute(solved_constraints)?;
+                var.expr.substitute(solved_constraints, sourcemap)?;
+            }
+            TypedExprEnum::Infix(infix) => {
+                infix.ty.sub(solved_constraints)?;
+                infix.left.substitute(solved_constraints, sourcemap)?;
+                infix.right.substitute(solved_constraints, sourcemap)?;
+
+                let operand_ty = infix.left.ty();
+                let valid = match (operand_ty.is_primitive(), infix.operator) {
+                    // Only booleans are combined by `&&` and `||`
+                    (prim, _) if infix.is_logical() => prim == Some(Prim::Bool),
+                    // Only integers have their bits manipulated
+                    (Some(prim), _) if infix.is_bitwise() => prim.is_integer(),
+                    (Some(Prim::Str), _) => false,
+                    // Chars are ordered by their code point
+                    (Some(Prim::Char), _) => infix.is_comparison(),
+                    // Booleans can only be compared for equality
+                    (Some(_), lexer::TokenType::EQ) | (Some(_), lexer::TokenType::NE) => true,
+                    (Some(prim), _) => prim.is_numeric(),
+                    (None, _) => false,
+                };
+
+                if !valid {
+                    return Err(bad_operand(operand_ty, infix.operator, self.pos));
+                }
+            }
+            TypedExprEnum::Conditional(conditional) => {
+                conditional.ty.sub(solved_constraints)?;
+                for branch in conditional.if_branches.iter_mut() {
+                    branch.cond.substitute(solved_constraints, sourcemap)?;
+                    branch.block.substitute(solved_constraints, sourcemap)?;
+                }
+                if let Some(else_branch) = &mut conditional.else_branch {
+                    else_branch
+                        .block
+                        .substitute(solved_constraints, sourcemap)?;
+                }
+            }
+            TypedExprEnum::Loop(loop_expr) => {
+                loop_expr.ty.sub(solved_constraints)?;
+                match &mut loop_expr.kind {
+                    TypedLoopKind::Loop => {}
+                    TypedLoopKind::While(cond) => cond.substitute(solved_constraints, sourcemap)?,
+                    TypedLoopKind::For { binder, range } => {
+                        binder.substitute(solved_constraints)?;
+                        range.substitute(solved_constraints, sourcemap)?;
+
+                        // Only integers can be counted
+                        match binder.ty.is_primitive() {
+                            Some(prim) if prim.is_integer() => {}
+                            _ => {
+                                return Err(bad_operand(
+                                    &binder.ty,
+                                    lexer::TokenType::DoubleDot,
+                                    self.pos,
+                                ))
+                            }
+                        }
+                    }
+                }
+                loop_expr.block.substitute(solved_constraints, sourcemap)?;
+            }
+            TypedExprEnum::Break(break_expr) => {
+                break_expr.ty.sub(solved_constraints)?;
+                break_expr.loop_ty.sub(solved_constraints)?;
+                if let Some(value) = &mut break_expr.expr {
+                    value.substitute(solved_constraints, sourcemap)?;
+                }
+            }
+            TypedExprEnum::Continue(continue_expr) => {
+                continue_expr.ty.sub(solved_constraints)?;
+            }
+            TypedExprEnum::Match(match_expr) => {
+                match_expr.ty.sub(solved_constraints)?;
+                match_expr.expr.substitute(solved_constraints, sourcemap)?;
+                for arm in match_expr.arms.iter_mut() {
+                    arm.pattern.substitute(solved_constraints, sourcemap)?;
+                    if let Some(guard) = &mut arm.guard {
+                        guard.substitute(solved_constraints, sourcemap)?;
+                    }
+                    arm.expr.substitute(solved_constraints, sourcemap)?;
+                }
+            }
+            TypedExprEnum::Range(range) => {
+                range.ty.sub(solved_constraints)?;
+                range.start.substitute(solved_constraints, sourcemap)?;
+                range.end.substitute(solved_constraints, sourcemap)?;
+
+                // Only integers and chars are ordered and can be counted
+                let bound_ty = range.start.ty();
+                match bound_ty.is_primitive() {
+                    Some(prim) if prim.is_integer() || prim == Prim::Char => {}
+                    _ => {
+                        return Err(bad_operand(
+                            bound_ty,
+                            range_operator(range.inclusive),
+                            self.pos,
+                        ));
+                    }
+                }
+            }
+            TypedExprEnum::Prefix(prefix) => {
+                prefix.ty.sub(solved_constraints)?;
+                match &mut prefix.val.expr {
+                    // A negated literal is checked against the negative range of its type
+                    TypedExprEnum::Literal(literal) if prefix.operator == lexer::TokenType::Sub => {
+                        literal.substitute(solved_constraints, sourcemap, true, prefix.val.pos)?
+                    }
+                    _ => prefix.val.substitute(solved_constraints, sourcemap)?,
+                }
+
+                // Unsigned values cannot be negated, only booleans have a logical not and only
+                // integers a bitwise not
+                let valid = match (prefix.val.ty().is_primitive(), prefix.operator) {
+                    (Some(prim), lexer::TokenType::Not) => prim == Prim::Bool,
+                    (Some(prim), lexer::TokenType::BitNot) => prim.is_integer(),
+                    (Some(prim), _) => prim.is_signed(),
+                    (None, _) => false,
+                };
+                if !valid {
+                    return Err(bad_operand(prefix.val.ty(), prefix.operator, self.pos));
+                }
             }
         }
 
@@ -123,12 +309,59 @@ impl TypedExpr {
     }
 }
 
+impl TypedLiteral {
+    /// Integers must fit in their type but a negated literal may be one past the maximum, i.e.
+    /// `-128i8`
+    fn substitute(
+        &mut self,
+        solved_constraints: &Substitutions,
+        sourcemap: &SourceMap,
+        negated: bool,
+        pos: Pos,
+    ) -> Result<(), ErrorValue> {
+        self.ty.sub(solved_constraints)?;
+        match self.ty.is_primitive() {
+            Some(prim) => match (prim, self.value.literal_type) {
+                (Prim::Bool, LiteralType::Bool) => {}
+                (Prim::Str, LiteralType::String) => {}
+                (Prim::Char, LiteralType::Char) => {}
+                (prim, LiteralType::Number) if prim.is_integer() => {
+                    let text = get_segment!(sourcemap, self.value.pos).to_string();
+                    let max = prim.max_value().unwrap();
+                    let limit = if negated { max + 1 } else { max };
+                    match lexer::NumberParts::new(&text).int_value() {
+                        Some(value) if value <= limit => {}
+                        _ => return Err(literal_out_of_range(&text, prim, max, self.value.pos)),
+                    }
+                }
+                (prim, LiteralType::Float) if prim.is_float() => {}
+
+                (_, _) => return Err(bad_literal(&self.ty, pos)),
+            },
+            None => return Err(bad_literal(&self.ty, pos)),
+        }
+        Ok(())
+    }
+}
+
 impl TypedStmt {
-    fn substitute(&mut self, solved_constraints: &Substitutions) -> Result<(), ErrorValue> {
+    fn substitute(
+        &mut self,
+        solved_constraints: &Substitutions,
+        sourcemap: &SourceMap,
+    ) -> Result<(), ErrorValue> {
         match &mut self.stmt {
             TypedStmtEnum::Tag(_) => {}
-            TypedStmtEnum::Expression(expr) => expr.substitute(solved_constraints)?,
-            TypedStmtEnum::VariableDeclaration(_) => unimplemented!(),
+            TypedStmtEnum::Expression(expr) => expr.substitute(solved_constraints, sourcemap)?,
+            TypedStmtEnum::VariableDeclaration(declaration) => {
+                declaration.binder.substitute(solved_constraints)?
+            }
+            TypedStmtEnum::TypeAssign(type_assign) => type_assign.ty.sub(solved_constraints)?,
+            TypedStmtEnum::Unit(unit) => {
+                for stmt in unit.stmts.iter_mut() {
+                    stmt.substitute(solved_constraints, sourcemap)?;
+                }
+            }
         }
 
         Ok(())
@@ -138,11 +371,12 @@ impl TypedStmt {
 pub fn substitute(
     stmts: &mut [TypedStmt],
     solved_constraints: Substitutions,
+    sourcemap: &SourceMap,
 ) -> Result<(), Vec<ErrorValue>> {
     let mut errors = Vec::new();
 
     for stmt in stmts.iter_mut() {
-        match stmt.substitute(&solved_constraints) {
+        match stmt.substitute(&solved_constraints, sourcemap) {
             Ok(_) => {}
             Err(err) => errors.push(err),
         }
@@ -155,6 +389,14 @@ pub fn substitute(
     }
 }
 
+fn range_operator(inclusive: bool) -> lexer::TokenType {
+    if inclusive {
+        lexer::TokenType::DoubleDotEq
+    } else {
+        lexer::TokenType::DoubleDot
+    }
+}
+
 fn bad_literal(ty: &AnnotationType, pos: Pos) -> ErrorValue {
     ErrorValue::new(
         "invalid literal type".to_string(),
@@ -176,6 +418,98 @@ fn bad_literal(ty: &AnnotationType, pos: Pos) -> ErrorValue {
     )
 }
 
+fn literal_out_of_range(text: &str, prim: Prim, max: u128, pos: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("literal out of range for `{}`", prim.as_str()),
+        ErrorType::LiteralOutOfRange,
+        pos,
+        ErrorDisplayType::Error,
+        vec![ErrorAnnotation::new(
+            Some(format!(
+                "`{}` does not fit into `{}` whose maximum is `{}`",
+                text,
+                prim.as_str(),
+                max
+            )),
+            pos,
+            ErrorDisplayType::Error,
+        )],
+    )
+}
+
+fn bad_operand(ty: &AnnotationType, operator: lexer::TokenType, pos: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("cannot apply {} to type `{}`", operator.f(), ty),
+        ErrorType::TypeMismatch,
+        pos,
+        ErrorDisplayType::Error,
+        vec![
+            ErrorAnnotation::new(
+                Some(format!("`{}` type here", ty)),
+                ty.pos(),
+                ErrorDisplayType::Error,
+            ),
+            ErrorAnnotation::new(
+                Some("for this operation".to_string()),
+                pos,
+                ErrorDisplayType::Info,
+            ),
+        ],
+    )
+}
+
+fn bad_print(ty: &AnnotationType, pos: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("no printer for type `{}`", ty),
+        ErrorType::TypeMismatch,
+        pos,
+        ErrorDisplayType::Error,
+        vec![
+            ErrorAnnotation::new(
+                Some(format!("`{}` type here", ty)),
+                ty.pos(),
+                ErrorDisplayType::Error,
+            ),
+            ErrorAnnotation::new(
+                Some("for this call".to_string()),
+                pos,
+                ErrorDisplayType::Info,
+            ),
+        ],
+    )
+}
+
+fn bad_cast(from: &AnnotationType, to: &AnnotationType, pos: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("cannot cast `{}` as `{}`", from, to),
+        ErrorType::TypeCast,
+        pos,
+        ErrorDisplayType::Error,
+        vec![
+            ErrorAnnotation::new(
+                Some(format!("`{}` type here", from)),
+                from.pos(),
+                ErrorDisplayType::Error,
+            ),
+            ErrorAnnotation::new(
+                Some(format!("cannot be converted into `{}`", to)),
+                pos,
+                ErrorDisplayType::Info,
+            ),
+        ],
+    )
+}
+
+fn undefined_type_err(name: &ast::Namespace, pos: Pos) -> ErrorValue {
+    ErrorValue::new(
+        format!("undefined type `{}`", name),
+        ErrorType::UndefinedType,
+        pos,
+        ErrorDisplayType::Error,
+        vec![ErrorAnnotation::new(None, pos, ErrorDisplayType::Error)],
+    )
+}
+
 fn cannot_infer_err(pos: Pos) -> ErrorValue {
     ErrorValue::new(
         "cannot infer type".to_string(),
@@ -1,5 +1,7 @@
 use super::constraint_gen::generate;
 use super::context::Context;
+use super::exhaustiveness::Exhaustiveness;
+use super::initialization::Initialization;
 use super::substitute;
 use super::unifier::unify;
 use super::{annotation, annotation::TypedStmt};
@@ -58,9 +60,23 @@ impl TypeCheckModule {
             .map_err(|e| vec![e])?;
 
         let constraints = generate(&typed_ast, None, None);
-        let solved_constraints = unify(constraints).map_err(|e| vec![e])?;
+        let mut solved_constraints = unify(constraints).map_err(|e| vec![e])?;
+        for (ty, prim) in annotator.literal_defaults() {
+            solved_constraints.default(ty, *prim);
+        }
+
+        substitute(&mut typed_ast, solved_constraints, &self.sourcemap)?;
+
+        // Declarations without a value have to be given one before they are used
+        Initialization::default().check(&typed_ast)?;
 
-        substitute(&mut typed_ast, solved_constraints)?;
+        // Patterns can only be compared once every type is known
+        let mut exhaustiveness = Exhaustiveness::new(Rc::clone(&self.sourcemap));
+        let checked = exhaustiveness.check(&typed_ast);
+        self.logger
+            .borrow_mut()
+            .append_errors(exhaustiveness.take_warnings());
+        checked?;
 
         self.logger.borrow().log_verbose(&|| {
             format!(
@@ -2,7 +2,7 @@ use crate::helpers::plural;
 use crate::helpers::Pos;
 use crate::logger::{ErrorAnnotation, ErrorDisplayType, ErrorType, ErrorValue};
 use crate::typecheck::{
-    annotation::AnnotationType,
+    annotation::{AnnotationType, Prim},
     constraint_gen::{Constraint, Constraints},
 };
 
@@ -38,6 +38,19 @@ impl Substitutions {
         sub
     }
 
+    /// Give `ty` the primitive `prim` if it is still unknown
+    pub fn default(&mut self, ty: &AnnotationType, prim: Prim) {
+        if let AnnotationType::Infer(infer_num, pos) = self.apply(ty.clone()) {
+            let prim = AnnotationType::Prim(prim, pos);
+            let subs = std::mem::take(&mut self.subs);
+            self.subs = subs
+                .into_iter()
+                .map(|(val, ty)| (val, self.substitute(ty, infer_num, &prim)))
+                .collect();
+            self.subs.insert(infer_num, prim);
+        }
+    }
+
     fn apply_constraints(&self, constraints: Constraints) -> Constraints {
         Constraints(
             constraints
@@ -68,7 +81,7 @@ impl Substitutions {
         replacement: &AnnotationType,
     ) -> AnnotationType {
         match ty {
-            AnnotationType::Type(_, _) => ty,
+            AnnotationType::Type(_, _) | AnnotationType::Prim(_, _) => ty,
             AnnotationType::Function(args_ty, ret_ty, pos) => AnnotationType::Function(
                 Rc::new(
                     args_ty
@@ -80,6 +93,10 @@ impl Substitutions {
                 pos,
             ),
             AnnotationType::Never(_) => ty,
+            AnnotationType::Range(inner, pos) => AnnotationType::Range(
+                Rc::new(self.substitute((*inner).clone(), infer_num, replacement)),
+                pos,
+            ),
             AnnotationType::Tuple(tys, pos) => AnnotationType::Tuple(
                 Rc::new(
                     tys.iter()
@@ -113,6 +130,11 @@ pub fn unify(mut constraints: Constraints) -> Result<Substitutions, ErrorValue>
 
         let subst_rest = unify(subst.apply_constraints(constraints))?;
 
+        // Compose the substitutions, so solutions found later are applied to earlier ones
+        for ty in subst.subs.values_mut() {
+            *ty = subst_rest.apply(ty.clone());
+        }
+
         subst.subs.extend(subst_rest.subs);
         Ok(subst)
     }
@@ -127,6 +149,13 @@ fn unify_one(constraint: &Constraint) -> Result<Substitutions, ErrorValue> {
                 Err(type_mismatch_err(&type1, &type2))
             }
         }
+        (type1 @ AnnotationType::Prim(_, _), type2 @ AnnotationType::Prim(_, _)) => {
+            if type1 == type2 {
+                Ok(Substitutions::empty())
+            } else {
+                Err(type_mismatch_err(&type1, &type2))
+            }
+        }
         (
             AnnotationType::Function(args1, return1, pos1),
             AnnotationType::Function(args2, return2, pos2),
@@ -168,6 +197,14 @@ fn unify_one(constraint: &Constraint) -> Result<Substitutions, ErrorValue> {
 
             unify(constraints)
         }
+        (AnnotationType::Range(ty1, _), AnnotationType::Range(ty2, _)) => {
+            let mut constraints = Constraints::with_capacity(1);
+            constraints
+                .0
+                .insert(Constraint::new((*ty1).clone(), (*ty2).clone()));
+
+            unify(constraints)
+        }
         (ty1, ty2) => Err(type_mismatch_err(&ty1, &ty2)),
     }
 }
@@ -196,6 +233,7 @@ impl AnnotationType {
                 args.iter().any(|arg| arg.occurs(other)) || ret.occurs(other)
             }
             AnnotationType::Tuple(tys, _) => tys.iter().any(|ty| ty.occurs(other)),
+            AnnotationType::Range(ty, _) => ty.occurs(other),
             _ => false,
         }
     }
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path;
+use std::process::Command;
+
+/// Compile `args` with the compiler and return what the program it produces prints
+///
+/// The compiler finds the core library relative to the root of the crate and writes `a.out`
+/// there, so every program is compiled and run from one test.
+fn run(args: &[&str]) -> String {
+    let root = path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let program = root.join("a.out");
+    // A stale program would hide a failed link
+    let _ = fs::remove_file(&program);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_bin"))
+        .args(args)
+        .current_dir(root)
+        .status()
+        .expect("Failed to run the compiler");
+    assert!(status.success(), "Failed to compile {:?}", args);
+
+    let output = Command::new(&program)
+        .output()
+        .expect("Failed to run the program");
+    assert!(output.status.success(), "{:?} failed", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn run_programs() {
+    assert_eq!(run(&["examples/fib.fl"]), "2971215073\n");
+    assert_eq!(run(&["examples/factorial.fl"]), "2432902008176640000\n");
+    assert_eq!(
+        run(&[
+            "-c",
+            r#"let greeting = "Hello";
+let mut count: i64 = 40;
+let limit: i64;
+limit = count * 2;
+
+let entry = () {
+    count += 2;
+    print(greeting);
+    print(count);
+    print(limit);
+    print(255u8);
+};"#
+        ]),
+        "Hello\n42\n80\n255\n"
+    );
+    assert_eq!(
+        run(&[
+            "-c",
+            r#"let double = (x: i32) -> i32 {
+    return x * 2;
+};
+
+let apply = (f: (i32) -> i32, x: i32) -> i32 {
+    return f(x);
+};
+
+let entry = () {
+    let g = double;
+    print(apply(double, 1));
+    print(g(21));
+};"#
+        ]),
+        "2\n42\n"
+    );
+    assert_eq!(
+        run(&[
+            "-c",
+            r#"let entry = () {
+    print("a\0b");
+};"#
+        ]),
+        "a\0b\n"
+    );
+    assert_eq!(
+        run(&[
+            "-c",
+            r#"let entry = () {
+    print(1.5);
+    print(0.25f32);
+    print(true);
+    print(1 > 2);
+};"#
+        ]),
+        "1.5\n0.25\ntrue\nfalse\n"
+    );
+}
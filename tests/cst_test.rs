@@ -0,0 +1,139 @@
+use lib::formatter;
+use lib::lexer::TokenType;
+use lib::logger::{ErrorType, LoggerInner};
+use lib::parser::cst::{SyntaxElement, SyntaxKind, SyntaxNode};
+use lib::parser::Parser;
+use lib::sourcemap::{SourceMap, SourceMapInner};
+
+use std::path;
+use std::rc::Rc;
+
+const CODE: &str = "/** Math */
+unit math {
+    --- Doubles `x`
+    pub let double = (x: i64) -> i64 {
+        -- Comment
+        return x  *  2; /* block /* nested */ */
+    };
+}\r\n
+type km   =   i32 ;
+let entry = () {
+    let caf\u{e9}: km = math::double(10) as km;\t
+};
+";
+
+fn parse(code: &str) -> (Parser, SourceMap) {
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap
+        .borrow_mut()
+        .insert_file(path::PathBuf::from("cst_test.fl"), code.to_string());
+    let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+    let mut parser = Parser::new(filename_id, logger, Rc::clone(&sourcemap));
+    parser.initialize_expr();
+    parser.parse().expect("Failed to parse");
+    (parser, sourcemap)
+}
+
+fn find(node: &SyntaxNode, kind: SyntaxKind) -> Option<&SyntaxNode> {
+    if node.kind == kind {
+        return Some(node);
+    }
+    node.nodes().find_map(|child| find(child, kind))
+}
+
+#[test]
+fn print_lossless() {
+    let (parser, _) = parse(CODE);
+    assert_eq!(parser.syntax_tree().to_string(), CODE);
+}
+
+#[test]
+fn print_empty() {
+    let (parser, _) = parse("  -- Nothing here\n");
+    assert_eq!(parser.syntax_tree().to_string(), "  -- Nothing here\n");
+}
+
+#[test]
+fn tree_shape() {
+    let (parser, _) = parse(CODE);
+    let tree = parser.syntax_tree();
+
+    assert_eq!(
+        tree.nodes().map(|node| node.kind).collect::<Vec<_>>(),
+        vec![
+            SyntaxKind::Unit,
+            SyntaxKind::TypeAssign,
+            SyntaxKind::ExpressionStatement
+        ]
+    );
+
+    let type_assign = tree.nodes().nth(1).unwrap();
+    assert_eq!(type_assign.to_string(), "type km   =   i32 ;");
+    assert_eq!(
+        find(type_assign, SyntaxKind::Type).unwrap().to_string(),
+        "i32"
+    );
+
+    let trivia = tree
+        .tokens()
+        .into_iter()
+        .filter(|token| token.kind.is_trivia())
+        .count();
+    assert!(trivia > 0);
+    // Doc comments are part of the declaration they document
+    let unit = tree.nodes().next().unwrap();
+    assert!(matches!(
+        unit.children.first(),
+        Some(SyntaxElement::Token(token)) if token.kind == TokenType::DocComment
+    ));
+}
+
+#[test]
+fn derive_ast() {
+    let (parser, sourcemap) = parse(CODE);
+    let tree = parser.syntax_tree();
+    let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+    assert_eq!(
+        tree.statements(Rc::clone(&logger), Rc::clone(&sourcemap)),
+        Ok(parser.ast.clone().unwrap())
+    );
+
+    let unit = tree.nodes().next().unwrap();
+    assert_eq!(
+        unit.statement(Rc::clone(&logger), Rc::clone(&sourcemap)),
+        Ok(parser.ast.clone().unwrap().remove(0))
+    );
+
+    let infix = find(&tree, SyntaxKind::Infix).unwrap();
+    assert_eq!(infix.to_string(), "x  *  2");
+    assert!(infix.expr(logger, sourcemap).is_ok());
+}
+
+#[test]
+fn syntax_error() {
+    let code = "let entry = () {\n    let x = ;\n};\n";
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap
+        .borrow_mut()
+        .insert_file(path::PathBuf::from("cst_test.fl"), code.to_string());
+    let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+    let mut parser = Parser::new(filename_id, Rc::clone(&logger), Rc::clone(&sourcemap));
+    parser.initialize_expr();
+    let errors = parser.parse().expect_err("Parsed a syntax error");
+    assert!(!errors.is_empty());
+    assert!(errors
+        .iter()
+        .all(|err| err.get_error_type() == ErrorType::Syntax));
+
+    // Without an ast the tokens are directly under the root node
+    let tree = parser.syntax_tree();
+    assert_eq!(tree.to_string(), code);
+    assert_eq!(tree.nodes().count(), 0);
+    assert!(tree.statements(logger, Rc::clone(&sourcemap)).is_err());
+
+    let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+    assert!(formatter::format_file(filename_id, logger, sourcemap).is_err());
+}
@@ -0,0 +1,76 @@
+use lib::doc::Documentation;
+use lib::logger::LoggerInner;
+use lib::sourcemap::SourceMapInner;
+use lib::typecheck::TypeCheckModule;
+
+use std::path;
+use std::rc::Rc;
+
+fn document(code: &str) -> Documentation {
+    let filename = path::PathBuf::from("this_is_a_doc_filename_test.fl");
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap
+        .borrow_mut()
+        .insert_file(filename, format!("@[no_std]\n@[no_core]\n{}", code));
+
+    let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+    let typed_ast = TypeCheckModule::new(filename_id, logger, sourcemap)
+        .type_check()
+        .expect("Failed to typecheck");
+    Documentation::new(&typed_ast)
+}
+
+fn path(unit: &str) -> Vec<String> {
+    unit.split("::").map(str::to_string).collect()
+}
+
+const CODE: &str = r#"/** Formatting */
+unit core {
+    unit fmt {
+        --- Prints `n` and a newline
+        pub extern let print_long: (i64) -> ();
+
+        --- Prints a distance, see `km`
+        pub let print_km = (n: km) {
+            print_long(n);
+        };
+
+        --- Kilometres
+        pub type km = i64;
+
+        let private = () {};
+    }
+}
+
+--- Calls `core::fmt::print_km`
+pub let entry = () {
+    core::fmt::print_km(10);
+};"#;
+
+#[test]
+fn unit_page() {
+    let page = document(CODE).render(&path("core::fmt"));
+
+    assert!(
+        page.starts_with("# Unit `core::fmt`\n\n[index](index.md) / [core](unit.core.md) / fmt\n")
+    );
+    assert!(page.contains("### `print_km`\n\n```fluo\npub let print_km: fn (i64) => ();\n```"));
+    assert!(page.contains("Prints a distance, see [`km`](unit.core.fmt.md#type.km)"));
+    assert!(page.contains("```fluo\npub type km = i64;\n```\n\nKilometres\n"));
+    assert!(page
+        .contains("pub extern let print_long: fn (i64) => ();\n```\n\nPrints `n` and a newline"));
+    assert!(!page.contains("private"));
+}
+
+#[test]
+fn index_page() {
+    let page = document(CODE).render(&[]);
+
+    assert!(page.contains("- [`core`](unit.core.md)"));
+    assert!(page.contains("Calls [`core::fmt::print_km`](unit.core.fmt.md#fn.print_km)"));
+
+    let core = document(CODE).render(&path("core"));
+    assert!(core.contains("Formatting"));
+    assert!(core.contains("- [`core::fmt`](unit.core.fmt.md)"));
+}
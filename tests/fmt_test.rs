@@ -0,0 +1,200 @@
+use lib::formatter;
+use lib::logger::LoggerInner;
+use lib::sourcemap::SourceMapInner;
+
+use std::path;
+use std::rc::Rc;
+
+fn format(code: &str) -> String {
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap
+        .borrow_mut()
+        .insert_file(path::PathBuf::from("fmt_test.fl"), code.to_string());
+    let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+    formatter::format_file(filename_id, logger, sourcemap).expect("Failed to parse")
+}
+
+macro_rules! assert_fmt {
+    ($code: expr, $expected: expr, $name: ident) => {
+        #[test]
+        fn $name() {
+            assert_eq!(format($code), $expected);
+            // Formatted code stays as it is
+            assert_eq!(format($expected), $expected);
+        }
+    };
+}
+
+assert_fmt!(
+    "let   entry=(n:i64)->i64{
+return n*2+-n ;};",
+    "let entry = (n: i64) -> i64 {
+    return n * 2 + -n;
+};
+",
+    spacing_and_indent
+);
+
+assert_fmt!(
+    "@[no_std]   @[no_core]
+extern let print: (i64) -> ();
+let entry = () {}  ;",
+    "@[no_std]
+@[no_core]
+extern let print: (i64) -> ();
+let entry = () {};
+",
+    tags_and_empty_block
+);
+
+assert_fmt!(
+    "let entry = () -> (i64,) {
+    print(1, 2,);
+    return (1,);
+};",
+    "let entry = () -> (i64,) {
+    print(1, 2);
+    return (1,);
+};
+",
+    trailing_commas
+);
+
+assert_fmt!(
+    "let entry = (a: i64,
+    b: i64) {
+    print(a,
+        b);
+};",
+    "let entry = (
+    a: i64,
+    b: i64,
+) {
+    print(
+        a,
+        b,
+    );
+};
+",
+    multiline_groups
+);
+
+assert_fmt!(
+    "-- Entry point
+
+
+
+let entry = () {   -- Does nothing
+
+    /* block */ let x = 1;
+
+
+    let y = x; -- Trailing
+
+};",
+    "-- Entry point
+
+let entry = () { -- Does nothing
+    /* block */ let x = 1;
+
+    let y = x; -- Trailing
+};
+",
+    comments_and_blank_lines
+);
+
+assert_fmt!(
+    "/** Math */
+unit math {
+--- Doubles `x`
+pub let double = (x: i64) -> i64 { if x < 0 { return 0; } else { return x * 2; }; };
+}",
+    "/** Math */
+unit math {
+    --- Doubles `x`
+    pub let double = (x: i64) -> i64 {
+        if x < 0 {
+            return 0;
+        } else {
+            return x * 2;
+        };
+    };
+}
+",
+    nested_blocks
+);
+
+assert_fmt!(
+    "let entry = (n: i64) {
+'outer : for i in 0 .. n{while i<n{break 'outer;};loop{continue;};};
+};",
+    "let entry = (n: i64) {
+    'outer: for i in 0..n {
+        while i < n {
+            break 'outer;
+        };
+        loop {
+            continue;
+        };
+    };
+};
+",
+    loops
+);
+
+assert_fmt!(
+    "let entry = (n: i64) -> i64 {
+return match (n,n %% 2){(0,_)=>0,(_,true) if n>10=>{yield 1;}(x , false)=> - x,};
+};",
+    "let entry = (n: i64) -> i64 {
+    return match (n, n %% 2) {
+        (0, _) => 0,
+        (_, true) if n > 10 => {
+            yield 1;
+        }
+        (x, false) => -x,
+    };
+};
+",
+    match_arms
+);
+
+assert_fmt!(
+    "let entry = (a: bool, b: bool) -> bool {
+    return ! a&&b||a!=b;
+};",
+    "let entry = (a: bool, b: bool) -> bool {
+    return !a && b || a != b;
+};
+",
+    logical_operators
+);
+
+assert_fmt!(
+    "let entry = (x: u8) -> u8 {
+    return ~ x&0xF|x<<4^x>>1;
+};",
+    "let entry = (x: u8) -> u8 {
+    return ~x & 0xF | x << 4 ^ x >> 1;
+};
+",
+    bitwise_operators
+);
+
+assert_fmt!(
+    "let entry = () -> i64 {
+    let  mut x=1;
+    x+=2;
+    x%=  3;
+    return x;
+};",
+    "let entry = () -> i64 {
+    let mut x = 1;
+    x += 2;
+    x %= 3;
+    return x;
+};
+",
+    mutable_assign
+);
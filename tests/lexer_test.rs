@@ -1,6 +1,7 @@
 use lib::helpers::Pos;
 use lib::lexer::TokenType;
 use lib::lexer::*;
+use lib::logger::ErrorType;
 use lib::sourcemap::SourceMapInner;
 
 use std::path;
@@ -20,6 +21,24 @@ macro_rules! lex_assert {
     };
 }
 
+macro_rules! lex_error {
+    ($source: expr, $error: expr, $name: ident) => {
+        #[test]
+        fn $name() {
+            let sourcemap = SourceMapInner::new();
+            let filename_id = sourcemap
+                .borrow_mut()
+                .insert_file(path::PathBuf::from("test_fl.fl"), $source.to_string());
+
+            let mut l = Lexer::new(filename_id, sourcemap);
+            assert_eq!(
+                l.advance().map_err(|err| err.get_error_type()).err(),
+                Some($error)
+            )
+        }
+    };
+}
+
 lex_assert!(" true", TokenType::True, true_test);
 lex_assert!(" false", TokenType::False, false_test);
 
@@ -30,12 +49,52 @@ lex_assert!(
     string_escape_test
 );
 
+lex_assert!(
+    " \"\\n\\t\\\\ \\u{4e16}\"",
+    TokenType::String,
+    string_escapes_test
+);
+lex_error!(" \"\\q\"", ErrorType::InvalidEscape, unknown_escape_test);
+lex_error!(
+    " \"\\u{110000}\"",
+    ErrorType::InvalidEscape,
+    invalid_unicode_escape_test
+);
+lex_error!(
+    " \"\\u{12\"",
+    ErrorType::InvalidEscape,
+    unterminated_unicode_escape_test
+);
+
+#[test]
+fn unescape_test() {
+    assert_eq!(
+        unescape("Hello,\\t\\u{4e16}\\u{754c}!\\n\\\"\\\\").ok(),
+        Some("Hello,\t\u{4e16}\u{754c}!\n\"\\".to_string())
+    );
+
+    let err = unescape("ab\\xcd").err().unwrap();
+    assert_eq!((err.start, err.end), (2, 4));
+}
+
+lex_assert!(" 'a'", TokenType::Char, char_test);
+lex_assert!(" '\\n'", TokenType::Char, char_escape_test);
+lex_assert!(" '\\''", TokenType::Char, char_quote_test);
+lex_assert!(" '\\u{1F30A}'", TokenType::Char, char_unicode_escape_test);
+lex_assert!(" '\u{1F30A}'", TokenType::Char, char_unicode_test);
+lex_error!(" ''", ErrorType::InvalidLiteral, empty_char_test);
+lex_error!(" 'ab'", ErrorType::InvalidLiteral, multiple_chars_test);
+lex_error!(" 'e\u{301}'", ErrorType::InvalidLiteral, char_grapheme_test);
+lex_error!(" '\\q'", ErrorType::InvalidEscape, char_unknown_escape_test);
+lex_error!(" 'a", ErrorType::UnterminatedString, unterminated_char_test);
+
 lex_assert!(" def", TokenType::Def, def_test);
 lex_assert!(" type", TokenType::Type, type_test);
 lex_assert!(" impl", TokenType::Impl, impl_test);
 lex_assert!(" pattern", TokenType::Pattern, pattern_test);
 lex_assert!(" unit", TokenType::Unit, unit_test);
 lex_assert!(" let", TokenType::Let, let_test);
+lex_assert!(" mut", TokenType::Mut, mut_test);
 lex_assert!(" as", TokenType::As, as_test);
 lex_assert!(" is", TokenType::Is, is_test);
 lex_assert!(" return", TokenType::Return, return_test);
@@ -47,12 +106,72 @@ lex_assert!(" @", TokenType::At, at_test);
 
 lex_assert!(" if", TokenType::If, if_test);
 lex_assert!(" else", TokenType::Else, else_test);
+lex_assert!(" loop", TokenType::Loop, loop_test);
+lex_assert!(" while", TokenType::While, while_test);
+lex_assert!(" for", TokenType::For, for_test);
+lex_assert!(" in", TokenType::In, in_test);
+lex_assert!(" break", TokenType::Break, break_test);
+lex_assert!(" continue", TokenType::Continue, continue_test);
+lex_assert!(" match", TokenType::Match, match_test);
+lex_assert!(" 'outer: loop", TokenType::Label, label_test);
+lex_assert!(" 'a;", TokenType::Label, short_label_test);
 
 lex_assert!(" _13292_293dh_238", TokenType::Identifier, ident_test_1);
 lex_assert!(" i1dw23", TokenType::Identifier, ident_test_2);
+lex_assert!(" ñandú", TokenType::Identifier, ident_unicode_test);
+lex_assert!(" 変数", TokenType::Identifier, ident_cjk_test);
+lex_assert!(
+    " e\u{301}t\u{e9}",
+    TokenType::Identifier,
+    ident_combining_test
+);
+lex_error!(" \u{1F30A}", ErrorType::UnknownCharacter, ident_emoji_test);
+
+/// Lex all of `source`, then take the warnings
+fn lex_warnings(source: &str) -> Vec<ErrorType> {
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap
+        .borrow_mut()
+        .insert_file(path::PathBuf::from("test_fl.fl"), source.to_string());
+
+    let mut l = Lexer::new(filename_id, sourcemap);
+    while l.advance().unwrap().token != TokenType::EOF {}
+    l.take_warnings()
+        .into_iter()
+        .map(|warning| warning.get_error_type())
+        .collect()
+}
+
+#[test]
+fn ident_warnings_test() {
+    // Latin `paypal` and the same name with a cyrillic `а`
+    assert_eq!(
+        lex_warnings("let paypal = p\u{430}ypal;"),
+        vec![
+            ErrorType::MixedScriptIdentifier,
+            ErrorType::ConfusableIdentifier
+        ]
+    );
+    // Composed and decomposed `é` are the same identifier
+    assert_eq!(lex_warnings("let caf\u{e9} = cafe\u{301};"), vec![]);
+    // Confusable ASCII identifiers are not reported
+    assert_eq!(lex_warnings("let rn = m + l + I;"), vec![]);
+}
 
 lex_assert!(" 1", TokenType::Number, number_test_1);
 lex_assert!(" 1287321234567890", TokenType::Number, number_test_2);
+lex_assert!(" 1.5", TokenType::Float, float_test_1);
+lex_assert!(" 2e10", TokenType::Float, float_test_2);
+lex_assert!(" 1.25E-3", TokenType::Float, float_test_3);
+lex_assert!(" 0xFF", TokenType::Number, hex_test);
+lex_assert!(" 0o755", TokenType::Number, oct_test);
+lex_assert!(" 0b1010_1010", TokenType::Number, bin_test);
+lex_assert!(" 1_000_000", TokenType::Number, separator_test);
+lex_assert!(" 255u8", TokenType::Number, suffix_test);
+lex_assert!(" 1f32", TokenType::Float, float_suffix_test);
+lex_error!(" 0x", ErrorType::InvalidLiteral, missing_digits_test);
+lex_error!(" 10u7", ErrorType::InvalidLiteral, invalid_suffix_test);
+lex_error!(" 1.5i32", ErrorType::InvalidLiteral, float_int_suffix_test);
 
 lex_assert!(" /", TokenType::Div, div_test);
 lex_assert!(" %", TokenType::Mod, mod_test);
@@ -66,6 +185,24 @@ lex_assert!(" <", TokenType::LT, lt_test);
 lex_assert!(" >=", TokenType::GE, ge_test);
 lex_assert!(" <=", TokenType::LE, le_test);
 lex_assert!(" ==", TokenType::EQ, eq_test);
+lex_assert!(" !=", TokenType::NE, ne_test);
+
+lex_assert!(" &&", TokenType::And, and_test);
+lex_assert!(" ||", TokenType::Or, or_test);
+lex_assert!(" !", TokenType::Not, not_test);
+
+lex_assert!(" &", TokenType::BitAnd, bit_and_test);
+lex_assert!(" |", TokenType::BitOr, bit_or_test);
+lex_assert!(" ^", TokenType::BitXor, bit_xor_test);
+lex_assert!(" ~", TokenType::BitNot, bit_not_test);
+lex_assert!(" <<", TokenType::Shl, shl_test);
+lex_assert!(" >>", TokenType::Shr, shr_test);
+
+lex_assert!(" +=", TokenType::AddEq, add_eq_test);
+lex_assert!(" -=", TokenType::SubEq, sub_eq_test);
+lex_assert!(" *=", TokenType::MulEq, mul_eq_test);
+lex_assert!(" /=", TokenType::DivEq, div_eq_test);
+lex_assert!(" %=", TokenType::ModEq, mod_eq_test);
 
 lex_assert!(" ->", TokenType::Arrow, arrow_test);
 lex_assert!(" =>", TokenType::FatArrow, fat_arrow_test);
@@ -78,6 +215,8 @@ lex_assert!(" [", TokenType::LB, lb_test);
 lex_assert!(" ]", TokenType::RB, rb_test);
 lex_assert!(" ?", TokenType::Question, question_test);
 lex_assert!(" .", TokenType::Dot, dot_test);
+lex_assert!(" ..", TokenType::DoubleDot, double_dot_test);
+lex_assert!(" ..=", TokenType::DoubleDotEq, double_dot_eq_test);
 lex_assert!(" =", TokenType::Equals, equals_test);
 lex_assert!(" :", TokenType::Colon, color_test);
 lex_assert!(" ::", TokenType::DoubleColon, double_colon_test);
@@ -86,3 +225,73 @@ lex_assert!(" ;", TokenType::Semi, semi_test);
 lex_assert!(" ,", TokenType::Comma, comma_test);
 lex_assert!(" _", TokenType::Underscore, underscore_test);
 lex_assert!("", TokenType::EOF, eof_test);
+
+lex_assert!(" --- Docs\n1", TokenType::DocComment, doc_comment_test);
+lex_assert!(
+    " /** Docs */ 1",
+    TokenType::DocComment,
+    block_doc_comment_test
+);
+lex_assert!(" ---- Not docs\n1", TokenType::Number, line_comment_test);
+lex_assert!(" /*** Not docs */ 1", TokenType::Number, block_comment_test);
+lex_assert!(" /**/ 1", TokenType::Number, empty_block_comment_test);
+
+#[test]
+fn doc_text_test() {
+    assert_eq!(doc_text("--- Adds two numbers"), "Adds two numbers");
+    assert_eq!(doc_text("---  Indented"), " Indented");
+    assert_eq!(
+        doc_text("/**\n * Adds two\n * numbers\n */"),
+        "Adds two\nnumbers"
+    );
+    assert_eq!(doc_text("/** Inline */"), "Inline");
+}
+
+#[test]
+fn trivia_test() {
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap.borrow_mut().insert_file(
+        path::PathBuf::from("test_fl.fl"),
+        " -- line\n/* block */1".to_string(),
+    );
+
+    let mut l = Lexer::new(filename_id, sourcemap);
+    l.keep_trivia = true;
+    let tokens = std::iter::repeat_with(|| l.advance().unwrap().token)
+        .take(6)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            TokenType::Whitespace(1),
+            TokenType::LineComment(7),
+            TokenType::Whitespace(1),
+            TokenType::BlockComment(9),
+            TokenType::Number,
+            TokenType::EOF
+        ]
+    );
+}
+
+#[test]
+fn range_test() {
+    let sourcemap = SourceMapInner::new();
+    let filename_id = sourcemap
+        .borrow_mut()
+        .insert_file(path::PathBuf::from("test_fl.fl"), "1..n 'a'".to_string());
+
+    let mut l = Lexer::new(filename_id, sourcemap);
+    let tokens = std::iter::repeat_with(|| l.advance().unwrap().token)
+        .take(5)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            TokenType::Number,
+            TokenType::DoubleDot,
+            TokenType::Identifier,
+            TokenType::Char,
+            TokenType::EOF
+        ]
+    );
+}
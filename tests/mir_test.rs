@@ -0,0 +1,519 @@
+use lib::lexer;
+use lib::logger::LoggerInner;
+use lib::mir::*;
+use lib::sourcemap::SourceMapInner;
+use lib::typecheck::{annotation::Prim, TypeCheckModule};
+
+use std::path;
+use std::rc::Rc;
+
+macro_rules! lower {
+    ($code: expr) => {{
+        let filename = path::PathBuf::from("this_is_a_mir_filename_test.fl");
+        let sourcemap = SourceMapInner::new();
+        let filename_id = sourcemap.borrow_mut().insert_file(
+            filename,
+            concat!("@[no_std]\n@[no_core]\n", $code).to_string(),
+        );
+
+        let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+        let mut typechecker = TypeCheckModule::new(filename_id, logger, Rc::clone(&sourcemap));
+        lower_to_mir(typechecker.type_check().unwrap(), sourcemap)
+    }};
+}
+
+/// Get the function assigned by the last statement
+fn last_function(mir: &[MirStmt]) -> &FunctionExpr {
+    match mir.last() {
+        Some(MirStmt::Expression(MirExpr {
+            value: MirExprEnum::VariableAssignDeclaration(var_dec),
+            ..
+        })) => match &var_dec.expr.value {
+            MirExprEnum::Function(func) => func,
+            _ => panic!("Not a function"),
+        },
+        _ => panic!("Not a variable assign declaration"),
+    }
+}
+
+#[test]
+fn lower_function() {
+    let mir = lower!(
+        r#"let entry = (a: i32) -> (i32, i64) {
+    let x = 10;
+    return (a, x is i64);
+};"#
+    )
+    .unwrap();
+
+    let func = last_function(&mir);
+    assert_eq!(func.arg_names.len(), 1);
+    assert_eq!(func.block.nodes.len(), 2);
+    assert!(func.block.metadata.returns);
+
+    match &*func.signature.return_type {
+        MirType::Tuple(tys, _) => assert_eq!(tys.len(), 2),
+        _ => panic!("Return type is not a tuple"),
+    }
+}
+
+#[test]
+fn lower_literal() {
+    let mir = lower!(
+        r#"let entry = () -> i64 {
+    return 1923;
+};"#
+    )
+    .unwrap();
+
+    match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Literal(literal) => assert_eq!(literal.value, "1923"),
+            _ => panic!("Not a literal"),
+        },
+        _ => panic!("Not a return"),
+    }
+}
+
+#[test]
+fn lower_float_literal() {
+    let mir = lower!(
+        r#"let entry = () -> _ {
+    return 2.5;
+};"#
+    )
+    .unwrap();
+
+    match last_function(&mir).signature.return_type.as_ref() {
+        MirType::Primitive(Prim::F64, _) => {}
+        ty => panic!("Return type is not f64 {:?}", ty),
+    }
+}
+
+#[test]
+fn lower_number_literals() {
+    let mir = lower!(
+        r#"let entry = () -> (u8, i8, i64, f32) {
+    return (0xFF, -128i8, 1_000, 2.5f32);
+};"#
+    )
+    .unwrap();
+
+    let values = match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Tuple(values) => values,
+            _ => panic!("Not a tuple"),
+        },
+        _ => panic!("Not a return"),
+    };
+
+    let literals: Vec<_> = values
+        .iter()
+        .map(|value| match &value.value {
+            MirExprEnum::Literal(literal) => literal.value.as_str(),
+            MirExprEnum::Prefix(prefix) => match &prefix.val.value {
+                MirExprEnum::Literal(literal) => literal.value.as_str(),
+                _ => panic!("Not a literal"),
+            },
+            _ => panic!("Not a literal"),
+        })
+        .collect();
+    assert_eq!(literals, vec!["255", "128", "1000", "2.5"]);
+}
+
+#[test]
+fn lower_string_literal() {
+    let mir = lower!(
+        r#"let entry = () -> str {
+    return "Hello,\t\u{4e16}\u{754c}!";
+};"#
+    )
+    .unwrap();
+
+    match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Literal(literal) => {
+                assert_eq!(literal.value, "Hello,\t\u{4e16}\u{754c}!");
+                assert!(matches!(literal.ty, MirType::Primitive(Prim::Str, _)));
+            }
+            _ => panic!("Not a literal"),
+        },
+        _ => panic!("Not a return"),
+    }
+}
+
+#[test]
+fn lower_char_cast() {
+    let mir = lower!(
+        r#"let entry = () -> u32 {
+    return '\u{1F30A}' as u32;
+};"#
+    )
+    .unwrap();
+
+    match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Cast(literal) => {
+                assert!(matches!(value.ty, MirType::Primitive(Prim::U32, _)));
+                match &literal.value {
+                    MirExprEnum::Literal(literal) => {
+                        assert_eq!(literal.value, "127754");
+                        assert!(matches!(literal.ty, MirType::Primitive(Prim::Char, _)));
+                    }
+                    _ => panic!("Not a literal"),
+                }
+            }
+            _ => panic!("Not a cast"),
+        },
+        _ => panic!("Not a return"),
+    }
+}
+
+#[test]
+fn lower_function_call() {
+    let mir = lower!(
+        r#"let entry = () -> i32 {
+    return x(10);
+};
+
+let x = (a: i32) -> _ {
+    return a;
+};"#
+    )
+    .unwrap();
+
+    match &last_function(&mir).signature.return_type.as_ref() {
+        MirType::Primitive(_, _) => {}
+        _ => panic!("Return type is not a primitive"),
+    }
+}
+
+#[test]
+fn lower_else_if() {
+    let mir = lower!(
+        r#"let entry = (n: i32) -> i32 {
+    return if n < 0 {
+        yield -1;
+    } else if n == 0 {
+        yield 0;
+    } else {
+        yield 1;
+    };
+};"#
+    )
+    .unwrap();
+
+    let conditional = match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Conditional(conditional) => conditional,
+            _ => panic!("Not a conditional"),
+        },
+        _ => panic!("Not a return"),
+    };
+
+    // The `else if` is nested inside of the else branch
+    match &conditional.else_b.block.nodes[..] {
+        [MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Yield(value),
+            ..
+        })] => assert!(matches!(value.value, MirExprEnum::Conditional(_))),
+        _ => panic!("Else branch does not yield a conditional"),
+    }
+}
+
+#[test]
+fn lower_unit() {
+    let mir = lower!(
+        r#"unit math {
+    type km = i64;
+
+    pub let double = (x: km) -> km {
+        return x * 2;
+    };
+}"#
+    )
+    .unwrap();
+
+    // Units are flattened, and their symbols are prefixed
+    match &mir[..] {
+        [MirStmt::Tag(_), MirStmt::Tag(_), MirStmt::Expression(MirExpr {
+            value: MirExprEnum::VariableAssignDeclaration(var_dec),
+            ..
+        })] => assert_eq!(var_dec.var_name.to_string(), "math::double"),
+        _ => panic!("Unit was not flattened"),
+    }
+}
+
+#[test]
+fn lower_declarations() {
+    let mir = lower!(
+        r#"extern let print_long: (i64) -> ();
+let count: i64;"#
+    )
+    .unwrap();
+
+    match &mir[..] {
+        [MirStmt::Tag(_), MirStmt::Tag(_), MirStmt::VariableDeclaration(print), MirStmt::VariableDeclaration(count)] =>
+        {
+            assert!(print.is_extern);
+            assert!(!count.is_extern);
+        }
+        _ => panic!("Not two declarations"),
+    }
+}
+
+#[test]
+fn lower_print() {
+    let mir = lower!(
+        r#"unit core::fmt {
+    @[printer]
+    pub extern let print_byte: (u8) -> ();
+    @[printer]
+    pub extern let print_usize: (usize) -> ();
+}
+
+let entry = (byte: u8, size: usize) {
+    print(byte);
+    print(size);
+};"#
+    )
+    .unwrap();
+
+    // `print` calls the printer taking the type of its argument
+    let names: Vec<_> = last_function(&mir)
+        .block
+        .nodes
+        .iter()
+        .map(|stmt| match stmt {
+            MirStmt::Expression(MirExpr {
+                value: MirExprEnum::FunctionCall(call),
+                ..
+            }) => call.name.to_string(),
+            _ => panic!("Not a function call"),
+        })
+        .collect();
+    assert_eq!(names, ["core::fmt::print_byte", "core::fmt::print_usize"]);
+}
+
+#[test]
+fn lower_loop_labels() {
+    let mir = lower!(
+        r#"let entry = (n: i64) -> i64 {
+    return 'outer: loop {
+        for i in 0..n {
+            if i > 10 {
+                break 'outer i;
+            };
+            continue;
+        };
+    };
+};"#
+    )
+    .unwrap();
+
+    let outer = match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Loop(outer) => outer,
+            _ => panic!("Not a loop"),
+        },
+        _ => panic!("Not a return"),
+    };
+
+    let inner = match &outer.block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Loop(inner),
+            ..
+        }) => inner,
+        _ => panic!("Not a loop"),
+    };
+    assert!(matches!(inner.kind, LoopKind::For { .. }));
+
+    // `break 'outer` leaves the outer loop, `continue` the inner one
+    match &inner.block.nodes[..] {
+        [MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Conditional(conditional),
+            ..
+        }), MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Continue(continue_id),
+            ..
+        })] => {
+            assert_eq!(*continue_id, inner.id);
+            match &conditional.if_b.block.nodes[..] {
+                [MirStmt::Expression(MirExpr {
+                    value: MirExprEnum::Break(break_expr),
+                    ..
+                })] => assert_eq!(break_expr.loop_id, outer.id),
+                _ => panic!("Not a break"),
+            }
+        }
+        _ => panic!("Unexpected loop body"),
+    }
+}
+
+#[test]
+fn lower_match() {
+    let mir = lower!(
+        r#"let entry = (n: i8, a: bool) -> i8 {
+    return match (n, a) {
+        (-128..0, _) => -1,
+        (x, true) if x > 10 => x,
+        _ => 0,
+    };
+};"#
+    )
+    .unwrap();
+
+    let func = last_function(&mir);
+    let match_expr = match &func.block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Match(match_expr) => match_expr,
+            _ => panic!("Not a match"),
+        },
+        _ => panic!("Not a return"),
+    };
+    assert_eq!(match_expr.arms.len(), 3);
+
+    // The negated start of the range is checked against the negative range of `i8`
+    match &match_expr.arms[0].pattern.kind {
+        PatternKind::Tuple(patterns) => match &patterns[..] {
+            [Pattern {
+                kind: PatternKind::Range { start, .. },
+                ty: MirType::Primitive(Prim::I8, _),
+                ..
+            }, Pattern {
+                kind: PatternKind::Wildcard,
+                ..
+            }] => assert!(matches!(start.value, MirExprEnum::Prefix(_))),
+            _ => panic!("Unexpected tuple patterns"),
+        },
+        _ => panic!("Not a tuple pattern"),
+    }
+
+    let arm = &match_expr.arms[1];
+    assert!(arm.guard.is_some());
+    match &arm.pattern.kind {
+        PatternKind::Tuple(patterns) => {
+            assert!(matches!(patterns[0].kind, PatternKind::Binding(_)));
+            assert!(matches!(patterns[1].kind, PatternKind::Literal(_)));
+        }
+        _ => panic!("Not a tuple pattern"),
+    }
+}
+
+#[test]
+fn lower_range() {
+    let mir = lower!(
+        r#"let entry = (n: u8) -> u8 {
+    let r = 1..=n;
+    return n;
+};"#
+    )
+    .unwrap();
+
+    // A range is a tuple of its start, its end and whether the end is included
+    let var_dec = match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::VariableAssignDeclaration(var_dec),
+            ..
+        }) => var_dec,
+        _ => panic!("Not a variable assign declaration"),
+    };
+    match &var_dec.expr.ty {
+        MirType::Tuple(tys, _) => assert!(matches!(
+            &tys[..],
+            [
+                MirType::Primitive(Prim::U8, _),
+                MirType::Primitive(Prim::U8, _),
+                MirType::Primitive(Prim::Bool, _)
+            ]
+        )),
+        _ => panic!("Not a tuple type"),
+    }
+    match &var_dec.expr.value {
+        MirExprEnum::Tuple(values) => assert_eq!(values.len(), 3),
+        _ => panic!("Not a tuple"),
+    }
+}
+
+#[test]
+fn lower_logical() {
+    let mir = lower!(
+        r#"let entry = (a: bool, b: bool) -> bool {
+    return a || !b && a != b;
+};"#
+    )
+    .unwrap();
+
+    // `&&` and `||` are not plain infix operators, their right operand may not be evaluated
+    let logical = match &last_function(&mir).block.nodes[0] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::Return(value),
+            ..
+        }) => match &value.value {
+            MirExprEnum::Logical(logical) => logical,
+            _ => panic!("Not a logical operation"),
+        },
+        _ => panic!("Not a return"),
+    };
+    assert_eq!(logical.operator, lexer::TokenType::Or);
+    match &logical.right.value {
+        MirExprEnum::Logical(right) => {
+            assert_eq!(right.operator, lexer::TokenType::And);
+            assert!(matches!(right.left.value, MirExprEnum::Prefix(_)));
+            assert!(matches!(right.right.value, MirExprEnum::Infix(_)));
+        }
+        _ => panic!("Not a logical operation"),
+    }
+}
+
+#[test]
+fn lower_compound_assign() {
+    let mir = lower!(
+        r#"let entry = () -> i32 {
+    let mut x = 1;
+    x *= 3;
+    return x;
+};"#
+    )
+    .unwrap();
+
+    // `x *= 3` stores `x * 3` into `x`
+    match &last_function(&mir).block.nodes[1] {
+        MirStmt::Expression(MirExpr {
+            value: MirExprEnum::VariableAssign(var_assign),
+            ..
+        }) => match &var_assign.expr.value {
+            MirExprEnum::Infix(infix) => {
+                assert_eq!(infix.operator, lexer::TokenType::Mul);
+                match &infix.left.value {
+                    MirExprEnum::Variable(name) => assert_eq!(name, &var_assign.var_name),
+                    _ => panic!("Not a variable"),
+                }
+            }
+            _ => panic!("Not an infix"),
+        },
+        _ => panic!("Not a variable assign"),
+    }
+}
@@ -48,6 +48,33 @@ macro_rules! assert_ok {
     };
 }
 
+macro_rules! assert_warning {
+    ($code: expr, $expected_warning: expr, $name: ident) => {
+        #[test]
+        fn $name() {
+            let filename = path::PathBuf::from("this_is_another_filename_test.fl");
+            let sourcemap = SourceMapInner::new();
+            let filename_id = sourcemap.borrow_mut().insert_file(
+                filename,
+                concat!("@[no_std]\n@[no_core]\n", $code).to_string(),
+            );
+            let logger = LoggerInner::new(true, Rc::clone(&sourcemap));
+
+            let mut typechecker = TypeCheckModule::new(filename_id, Rc::clone(&logger), sourcemap);
+            assert!(typechecker.type_check().is_ok());
+            assert_eq!(
+                logger
+                    .borrow()
+                    .errors()
+                    .iter()
+                    .map(|err| err.get_error_type())
+                    .collect::<Vec<_>>(),
+                $expected_warning
+            );
+        }
+    };
+}
+
 /*
 
     assert_error!(
@@ -71,15 +98,6 @@ macro_rules! assert_ok {
         if_cond_no_else
     );
 
-    assert_error!(
-        r#"type Km = i32;
-let entry = () {
-    let x: Miles;
-};"#,
-        vec![ErrorType::UndefinedType],
-        undef_type
-    );
-
     assert_error!(
         r#"type Km = i32;
 let entry = () {
@@ -148,3 +166,890 @@ let x = (a: _) -> _ {
 };"#,
     identity_func_infer
 );
+
+assert_ok!(
+    r#"let entry = (n: i64) -> i64 {
+    return entry(n - 1) + -entry(n % 2) * 2;
+};"#,
+    infix_arithmetic
+);
+
+assert_ok!(
+    r#"let entry = (n: i32) -> (bool, bool) {
+    return (n <= 1, n %% 3);
+};"#,
+    infix_comparison
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    return n < 1;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    comparison_not_numeric
+);
+
+assert_error!(
+    r#"let entry = (a: bool, b: bool) -> bool {
+    return a + b;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    bool_arithmetic
+);
+
+assert_error!(
+    r#"let entry = (a: i32, b: i64) -> i32 {
+    return a * b;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    infix_operand_mismatch
+);
+
+assert_ok!(
+    r#"let entry = (x: f32) -> (f32, bool) {
+    let half = 0.5;
+    return (x * half - 2e10, x >= 1.5);
+};"#,
+    float_arithmetic
+);
+
+assert_ok!(
+    r#"let entry = () -> f64 {
+    let x = 1.5;
+    return x;
+};"#,
+    float_default_f64
+);
+
+assert_error!(
+    r#"let entry = () -> f32 {
+    let x = 1.5;
+    let y: f64 = x;
+    return x;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    float_mismatch
+);
+
+assert_error!(
+    r#"let entry = () -> i32 {
+    return 1.5;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    float_literal_int
+);
+
+assert_ok!(
+    r#"let entry = (a: u32, b: usize, c: isize) -> (u32, bool, u8, isize) {
+    return (a / 2 % 3, b >= 10, 255, -c);
+};"#,
+    unsigned_arithmetic
+);
+
+assert_error!(
+    r#"let entry = (a: u64) -> u64 {
+    return -a;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    unsigned_negation
+);
+
+assert_error!(
+    r#"let entry = (a: u8, b: i8) -> u8 {
+    return a + b;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    signedness_mismatch
+);
+
+assert_ok!(
+    r#"let entry = () -> (u8, i64, f32) {
+    let x = 255u8;
+    return (x, 0x7FFF_FFFFi64, 1f32);
+};"#,
+    literal_suffix
+);
+
+assert_error!(
+    r#"let entry = () -> i32 {
+    return 10i64;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    literal_suffix_mismatch
+);
+
+assert_ok!(
+    r#"let entry = () -> (i32, i64) {
+    let x = 0xFF;
+    let y = 10;
+    return (x, y);
+};"#,
+    integer_default_i32
+);
+
+assert_ok!(
+    r#"extern let print_str: (str) -> ();
+
+let entry = () {
+    let greeting = "Hello, \u{4e16}\u{754c}!\n";
+    print_str(greeting);
+};"#,
+    string_literal
+);
+
+assert_error!(
+    r#"let entry = () -> str {
+    return "a" + "b";
+};"#,
+    vec![ErrorType::TypeMismatch],
+    string_arithmetic
+);
+
+assert_ok!(
+    r#"let entry = () {
+    let b = true;
+    let c = !false || b;
+};"#,
+    bool_literal
+);
+
+assert_ok!(
+    r#"let entry = (c: char) -> (char, bool, u32, char) {
+    let wave = '\u{1F30A}';
+    return ('\n', c >= 'a', wave as u32, 65u8 as char);
+};"#,
+    char_literal
+);
+
+assert_error!(
+    r#"let entry = (c: char) -> char {
+    return c + 'a';
+};"#,
+    vec![ErrorType::TypeMismatch],
+    char_arithmetic
+);
+
+assert_ok!(
+    r#"let entry = (x: f64, b: bool) -> (i8, u64, f32, i32) {
+    return (x as i8, b as u64, 10 as f32, (1.5 as f32) as i32);
+};"#,
+    numeric_cast
+);
+
+assert_error!(
+    r#"let entry = (x: u32) -> char {
+    return x as char;
+};"#,
+    vec![ErrorType::TypeCast],
+    char_cast_error
+);
+
+assert_error!(
+    r#"let entry = (x: i32) -> bool {
+    return x as bool;
+};"#,
+    vec![ErrorType::TypeCast],
+    bool_cast_error
+);
+
+assert_ok!(
+    r#"let entry = (x: i32, c: char, a: bool, f: f64) -> bool {
+    return x != 0 && c != 'a' || !a && f != 0.5;
+};"#,
+    logical_operators
+);
+
+assert_error!(
+    r#"let entry = (x: i32) -> i32 {
+    return x && 1;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    logical_not_bool
+);
+
+assert_error!(
+    r#"let entry = (x: i32) -> i32 {
+    return !x;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    not_integer
+);
+
+assert_ok!(
+    r#"let entry = (flags: u32, hash: i64) -> (u32, i64) {
+    let packed = flags & 0xFF | (flags >> 8 ^ 1) << 16;
+    return (~packed, hash >> 3 & ~(1 << 63));
+};"#,
+    bitwise_operators
+);
+
+assert_error!(
+    r#"let entry = (x: f64) -> f64 {
+    return x << 1.0;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    shift_float
+);
+
+assert_error!(
+    r#"let entry = (a: bool, b: bool) -> bool {
+    return a | ~b;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    bitwise_bool
+);
+
+assert_ok!(
+    r#"let fib = (n: i64) -> i64 {
+    if n <= 1 {
+        return 1;
+    };
+    return fib(n - 2) + fib(n - 1);
+};"#,
+    conditional_return
+);
+
+assert_ok!(
+    r#"let entry = (n: i32) -> i32 {
+    let x = if n < 0 {
+        yield -1;
+    } else if n == 0 {
+        return 0;
+    } else {
+        yield 1;
+    };
+    return x;
+};"#,
+    conditional_value
+);
+
+assert_ok!(
+    r#"let entry = (n: i32) -> i32 {
+    if n < 0 {
+        return -1;
+    } else {
+        return 1;
+    };
+};"#,
+    conditional_never
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    if n {
+        return 1;
+    };
+    return 0;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    conditional_not_bool
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    let x = if n == 0 {
+        yield 1 is i32;
+    } else {
+        yield true;
+    };
+    return x;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    conditional_branch_mismatch
+);
+
+assert_ok!(
+    r#"let entry = () -> i32 {
+    let x: i32;
+    x = 10;
+    return x;
+};"#,
+    declaration_assign
+);
+
+assert_error!(
+    r#"let entry = () -> i32 {
+    let x: i32;
+    x = 1;
+    x = 2;
+    return x;
+};"#,
+    vec![ErrorType::ImmutableAssign],
+    declaration_assign_twice
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    let x: i32;
+    for i in 0..n {
+        x = i;
+    };
+    return n;
+};"#,
+    vec![ErrorType::ImmutableAssign],
+    declaration_assign_in_loop
+);
+
+assert_ok!(
+    r#"let entry = (n: i32) -> i32 {
+    let x: i32;
+    loop {
+        x = n;
+        break;
+    };
+    return x;
+};"#,
+    declaration_assign_in_loop_break
+);
+
+assert_ok!(
+    r#"let entry = (c: bool) -> i32 {
+    let x: i32;
+    if c {
+        x = 1;
+    } else {
+        x = 2;
+    };
+    return x;
+};"#,
+    declaration_assign_branches
+);
+
+assert_error!(
+    r#"let entry = (c: bool) -> i32 {
+    let x: i32;
+    if c {
+        x = 1;
+    };
+    return x;
+};"#,
+    vec![ErrorType::PossibleUninitVal],
+    declaration_assign_one_branch
+);
+
+assert_error!(
+    r#"let entry = () -> i32 {
+    let x: i32;
+    return x;
+};"#,
+    vec![ErrorType::PossibleUninitVal],
+    declaration_unassigned
+);
+
+assert_error!(
+    r#"let entry = () -> i32 {
+    let x: i32;
+    x += 1;
+    return x;
+};"#,
+    vec![ErrorType::PossibleUninitVal],
+    declaration_compound_assign
+);
+
+assert_error!(
+    r#"let limit: i32;
+
+let entry = () {
+    limit = 10;
+};"#,
+    vec![ErrorType::ImmutableAssign],
+    declaration_assign_in_function
+);
+
+assert_ok!(
+    r#"let entry = (n: i64) -> i64 {
+    let x = n;
+    let mut x = x;
+    x += 10;
+    x -= 1;
+    x *= n;
+    x /= 2;
+    x %= 7;
+    x = x + 1;
+    return x;
+};"#,
+    mutable_assign
+);
+
+assert_error!(
+    r#"let entry = () -> i64 {
+    let x = 10;
+    x = 11;
+    return x;
+};"#,
+    vec![ErrorType::ImmutableAssign],
+    immutable_assign
+);
+
+assert_error!(
+    r#"let entry = (n: i64) {
+    n += 1;
+};"#,
+    vec![ErrorType::ImmutableAssign],
+    argument_assign
+);
+
+assert_error!(
+    r#"let entry = () {
+    let mut greeting = "Hello";
+    greeting += ", world";
+};"#,
+    vec![ErrorType::TypeMismatch],
+    compound_assign_str
+);
+
+assert_ok!(
+    r#"extern let print_int: (i32) -> ();
+
+let entry = () {
+    print_int(10);
+};"#,
+    extern_declaration
+);
+
+assert_ok!(
+    r#"unit io {
+    @[printer]
+    pub extern let put_int: (i32) -> ();
+    @[printer]
+    pub extern let put_byte: (u8) -> ();
+    @[printer]
+    pub extern let put_usize: (usize) -> ();
+}
+
+let entry = (size: usize) {
+    print(10);
+    print(255u8);
+    print(size);
+};"#,
+    print_overloads
+);
+
+assert_error!(
+    r#"@[printer]
+extern let print_int: (i32) -> ();
+
+let entry = () {
+    print(1.5);
+};"#,
+    vec![ErrorType::TypeMismatch],
+    print_unprintable
+);
+
+assert_error!(
+    r#"unit core::fmt {
+    pub extern let print_int: (i32) -> ();
+}
+
+let entry = () {
+    print(10);
+};"#,
+    vec![ErrorType::UndefinedSymbol],
+    print_untagged
+);
+
+assert_ok!(
+    r#"@[printer]
+extern let print_int: (i32) -> ();
+
+let print = (x: i32, y: i32) {};
+
+let entry = () {
+    print(1, 2);
+};"#,
+    print_shadowed
+);
+
+assert_error!(
+    r#"@[printer]
+extern let answer: i32;"#,
+    vec![ErrorType::TypeMismatch],
+    printer_not_function
+);
+
+assert_ok!(
+    r#"type km = i32;
+
+let entry = (x: km) -> i32 {
+    return x;
+};"#,
+    type_alias
+);
+
+assert_error!(
+    r#"type km = i64;
+
+let entry = (x: km) -> i32 {
+    return x;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    type_alias_mismatch
+);
+
+assert_ok!(
+    r#"unit math {
+    pub let double = (x: i64) -> i64 {
+        return twice(x);
+    };
+
+    let twice = (x: i64) -> i64 {
+        return x * 2;
+    };
+}
+
+let entry = () -> i64 {
+    return math::double(10);
+};"#,
+    unit_prefix
+);
+
+assert_error!(
+    r#"unit math {
+    let twice = (x: i64) -> i64 {
+        return x * 2;
+    };
+}
+
+let entry = () -> i64 {
+    return twice(10);
+};"#,
+    vec![ErrorType::UndefinedSymbol],
+    unit_symbol_not_in_scope
+);
+
+assert_ok!(
+    r#"unit units {
+    type m = km;
+    type km = i32;
+
+    pub let val = () -> m {
+        return 10;
+    };
+}
+
+let entry = () -> i32 {
+    return units::val();
+};"#,
+    type_alias_out_of_order
+);
+
+assert_error!(
+    r#"type Km = i32;
+let entry = () {
+    let x: Miles;
+};"#,
+    vec![ErrorType::UndefinedType],
+    undef_type
+);
+
+assert_error!(
+    r#"type a = (i32, b);
+type b = a;
+
+let entry = (x: a) {};"#,
+    vec![ErrorType::UndefinedType],
+    type_alias_cycle
+);
+
+assert_ok!(
+    "let entry = (caf\u{e9}: i32) -> i32 {
+    return cafe\u{301};
+};",
+    unicode_identifier_normalized
+);
+
+assert_ok!(
+    r#"/** Math helpers */
+unit math {
+    --- Doubles `x`
+    pub let double = (x: i64) -> i64 {
+        --- Not a declaration
+        return x * 2;
+    };
+}
+
+--- Entry point
+let entry = () -> i64 {
+    return math::double(10);
+};"#,
+    doc_comments
+);
+
+assert_ok!(
+    r#"let entry = (n: i32) -> i32 {
+    let x = loop {
+        if n > 10 {
+            break n;
+        };
+    };
+    return x;
+};"#,
+    loop_break_value
+);
+
+assert_ok!(
+    r#"let entry = () -> i32 {
+    loop {};
+};"#,
+    loop_never
+);
+
+assert_ok!(
+    r#"let entry = (n: i32) -> i32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        i = i + 1;
+        if i %% 2 {
+            continue;
+        };
+        total = total + i;
+    };
+    return total;
+};"#,
+    while_loop
+);
+
+assert_ok!(
+    r#"let entry = (n: u64) -> u64 {
+    let mut total = 0;
+    'outer: for i in 0..n {
+        for j in i..n {
+            if j > 10 {
+                continue 'outer;
+            };
+            total = total + i * j;
+        };
+    };
+    return total;
+};"#,
+    for_loop_labels
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    let x = loop {
+        if n > 10 {
+            break n;
+        };
+        break true;
+    };
+    return x;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    loop_break_mismatch
+);
+
+assert_error!(
+    r#"let entry = () {
+    while true {
+        break 1;
+    };
+};"#,
+    vec![ErrorType::TypeMismatch],
+    while_break_value
+);
+
+assert_error!(
+    r#"let entry = () {
+    for x in 0.5..2.0 {};
+};"#,
+    vec![ErrorType::TypeMismatch],
+    for_not_integer
+);
+
+assert_ok!(
+    r#"let entry = (n: u32) -> u32 {
+    let digits = 0..=9;
+    let mut total = 0;
+    for i in digits {
+        total = total + i + n;
+    };
+    return total;
+};"#,
+    range_value
+);
+
+assert_error!(
+    r#"let entry = (n: i64) {
+    let r = 0i32..n;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    range_bound_mismatch
+);
+
+assert_error!(
+    r#"let entry = () {
+    let r = 0.5..=2.0;
+};"#,
+    vec![ErrorType::TypeMismatch],
+    range_not_integer
+);
+
+assert_error!(
+    r#"let entry = () {
+    loop {
+        let inner = () {
+            break;
+        };
+        break;
+    };
+};"#,
+    vec![ErrorType::OutsideOfLoop],
+    break_outside_loop
+);
+
+assert_error!(
+    r#"let entry = () {
+    'outer: loop {
+        continue 'inner;
+    };
+};"#,
+    vec![ErrorType::UndefinedSymbol],
+    undefined_label
+);
+
+assert_ok!(
+    r#"let entry = (a: bool, b: bool) -> i32 {
+    return match (a, b) {
+        (true, true) => 3,
+        (true, false) => 2,
+        (false, x) if x => 1,
+        (false, _) => 0,
+    };
+};"#,
+    match_bool_tuple
+);
+
+assert_ok!(
+    r#"let entry = (n: i64, c: char) -> i64 {
+    let digit = match c {
+        '0'..'9' => true,
+        _ => false,
+    };
+    return match n {
+        -10..0 => -1,
+        0 => 0,
+        x if digit => x,
+        _ => {
+            return 1;
+        }
+    };
+};"#,
+    match_ranges
+);
+
+assert_ok!(
+    r#"let entry = (n: u8) -> u8 {
+    return match n {
+        0..128 => 0,
+        128..255 => 1,
+        255 => 2,
+    };
+};"#,
+    match_full_integer_range
+);
+
+assert_ok!(
+    r#"let entry = (n: i8) -> i8 {
+    return match n {
+        -128..=-1 => -1,
+        0 => 0,
+        1..=127 => 1,
+    };
+};"#,
+    match_inclusive_range
+);
+
+assert_error!(
+    r#"let entry = () -> (u8, i8) {
+    return (300u8, -129i8);
+};"#,
+    vec![ErrorType::LiteralOutOfRange],
+    literal_out_of_range
+);
+
+assert_ok!(
+    r#"let entry = () -> (u8, i8) {
+    return (255u8, -128i8);
+};"#,
+    literal_negated_minimum
+);
+
+assert_error!(
+    r#"let entry = (n: u8) -> u8 {
+    return match n {
+        0..=255 => 0,
+        256 => 1,
+    };
+};"#,
+    vec![ErrorType::LiteralOutOfRange],
+    match_literal_out_of_range
+);
+
+assert_error!(
+    r#"let entry = (a: bool) -> i32 {
+    return match a {
+        true => 1,
+    };
+};"#,
+    vec![ErrorType::NonExhaustive],
+    match_missing_bool
+);
+
+assert_error!(
+    r#"let entry = (a: bool, b: bool) -> i32 {
+    return match (a, b) {
+        (true, _) => 1,
+        (false, true) => 2,
+        (false, false) if a => 3,
+    };
+};"#,
+    vec![ErrorType::NonExhaustive],
+    match_missing_tuple
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    return match n {
+        0 => 1,
+        1..100 => 2,
+    };
+};"#,
+    vec![ErrorType::NonExhaustive],
+    match_missing_integers
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    return match n {
+        0 => 1,
+        _ => true,
+    };
+};"#,
+    vec![ErrorType::TypeMismatch],
+    match_arm_mismatch
+);
+
+assert_error!(
+    r#"let entry = (n: i32) -> i32 {
+    return match n {
+        x if x => 1,
+        _ => 0,
+    };
+};"#,
+    vec![ErrorType::TypeMismatch],
+    match_guard_not_bool
+);
+
+assert_warning!(
+    r#"let entry = (n: i32) -> i32 {
+    return match n {
+        0..10 => 1,
+        _ => 2,
+        5 => 3,
+    };
+};"#,
+    vec![ErrorType::UnreachablePattern],
+    match_unreachable_arm
+);